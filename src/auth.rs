@@ -1,8 +1,12 @@
-use crate::cli::Cli;
+use crate::cli::{Cli, Provider};
+use crate::constants;
 use crate::errors::{GhrError, Result};
+use crate::http::HostToken;
 use jlogger_tracing::{jdebug, jinfo};
-use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION};
 use std::fs;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 /// Read GitHub token from .netrc file
 fn read_netrc_token() -> Option<String> {
@@ -18,17 +22,24 @@ fn read_netrc_token() -> Option<String> {
 }
 
 /// Parse GitHub token from .netrc file content
+///
+/// CRLF (and bare CR) line endings are normalized and anything after `#` on
+/// a line is treated as a comment.
 fn parse_netrc_github_token(content: &str) -> Option<String> {
-    let lines: Vec<&str> = content.lines().collect();
+    let content = content.replace("\r\n", "\n").replace('\r', "\n");
     let mut in_github = false;
 
-    for line in lines {
-        let trimmed = line.trim();
+    for line in content.lines() {
+        let trimmed = strip_netrc_comment(line).trim();
         if trimmed.starts_with("machine") && trimmed.contains("github.com") {
             jinfo!("Found machine github.com in .netrc");
             in_github = true;
         } else if in_github && trimmed.starts_with("password") {
-            return trimmed.split_whitespace().nth(1).map(String::from);
+            return trimmed
+                .split_whitespace()
+                .nth(1)
+                .map(|password| password.trim().to_string())
+                .filter(|password| !password.is_empty());
         } else if trimmed.starts_with("machine") {
             in_github = false;
         }
@@ -36,46 +47,253 @@ fn parse_netrc_github_token(content: &str) -> Option<String> {
     None
 }
 
+/// Remove an inline `#` comment from a .netrc line
+fn strip_netrc_comment(line: &str) -> &str {
+    match line.find('#') {
+        Some(pos) => &line[..pos],
+        None => line,
+    }
+}
+
+/// Build the `Authorization` header value for a token
+pub fn bearer(token: &str) -> Result<HeaderValue> {
+    Ok(HeaderValue::from_str(&format!("Bearer {}", token.trim()))?)
+}
+
+/// Add a token to the request headers in the form the provider expects
+///
+/// A GitLab token is returned instead, to be sent per request to the API host.
+fn insert_token(cli: &Cli, header: &mut HeaderMap, token: &str) -> Result<Option<HostToken>> {
+    match cli.provider {
+        Provider::Github => {
+            header.insert(AUTHORIZATION, bearer(token)?);
+            Ok(None)
+        }
+        Provider::Gitlab => Ok(Some(HostToken::new(
+            &cli.api_url,
+            HeaderName::from_static(constants::headers::GITLAB_TOKEN),
+            HeaderValue::from_str(token.trim())?,
+        ))),
+    }
+}
+
+/// Read the tokens in a token file, failing when it holds none
+fn read_token_file(path: &str) -> Result<Vec<String>> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| GhrError::Auth(format!("Failed to read token file: {}", e)))?;
+    let tokens = parse_token_file(&content);
+    if tokens.is_empty() {
+        return Err(GhrError::Auth(format!("No token found in '{}'", path)));
+    }
+    Ok(tokens)
+}
+
+/// Parse token file content: one token per line, blank lines and `#` comments ignored
+fn parse_token_file(content: &str) -> Vec<String> {
+    content
+        .lines()
+        .map(|line| strip_netrc_comment(line).trim())
+        .filter(|line| !line.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Tokens to rotate between per API request, when the token file holds several
+///
+/// A token given with `--token` takes precedence over the token file, so no
+/// pool is used in that case. Rotation is only supported for GitHub.
+pub fn token_pool(cli: &Cli) -> Option<TokenPool> {
+    if cli.token.is_some() || cli.provider != Provider::Github {
+        return None;
+    }
+    let tokens = read_token_file(cli.token_file.as_deref()?).ok()?;
+    (tokens.len() > 1).then(|| TokenPool::new(tokens))
+}
+
+/// Round-robin pool of API tokens that skips tokens whose rate limit is exhausted
+pub struct TokenPool {
+    tokens: Vec<String>,
+    state: Mutex<PoolState>,
+}
+
+struct PoolState {
+    /// Index of the token to try next
+    next: usize,
+    /// Unix time at which each exhausted token's rate limit resets
+    reset_at: Vec<Option<u64>>,
+}
+
+impl TokenPool {
+    pub fn new(tokens: Vec<String>) -> Self {
+        let reset_at = vec![None; tokens.len()];
+        Self {
+            tokens,
+            state: Mutex::new(PoolState { next: 0, reset_at }),
+        }
+    }
+
+    /// Pick the token for the next request
+    pub fn next_token(&self) -> &str {
+        self.next_token_at(unix_now())
+    }
+
+    fn next_token_at(&self, now: u64) -> &str {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let count = self.tokens.len();
+
+        let available = (0..count)
+            .map(|offset| (state.next + offset) % count)
+            .find(|&i| !matches!(state.reset_at[i], Some(reset) if reset > now));
+        // With every token exhausted, use the one whose limit resets first
+        let index = available.unwrap_or_else(|| {
+            (0..count)
+                .min_by_key(|&i| state.reset_at[i])
+                .unwrap_or_default()
+        });
+
+        if available.is_some() {
+            state.reset_at[index] = None;
+        }
+        state.next = (index + 1) % count;
+        &self.tokens[index]
+    }
+
+    /// Record the rate-limit headers of a response to a request made with `token`
+    pub fn record(&self, token: &str, headers: &HeaderMap) {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.parse::<u64>().ok())
+        };
+        let (Some(0), Some(reset)) = (header("x-ratelimit-remaining"), header("x-ratelimit-reset"))
+        else {
+            return;
+        };
+
+        if let Some(index) = self.tokens.iter().position(|t| t == token) {
+            jdebug!("Token #{} rate limited until {}", index + 1, reset);
+            let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+            state.reset_at[index] = Some(reset);
+        }
+    }
+}
+
+impl std::fmt::Debug for TokenPool {
+    // Never print the tokens themselves
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TokenPool")
+            .field("tokens", &self.tokens.len())
+            .finish()
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
 /// Add authentication header to request headers
-pub fn add_auth_header(cli: &Cli, header: &mut HeaderMap) -> Result<()> {
-    let mut success = false;
+///
+/// For GitLab, nothing is added and the token is returned as a [`HostToken`]
+/// to send to the API host only, since release links may point to any host.
+pub fn add_auth_header(cli: &Cli, header: &mut HeaderMap) -> Result<Option<HostToken>> {
+    let mut success = None;
 
     // Try direct token first
     if let Some(token) = &cli.token {
         jinfo!("Using token from command line");
-        let auth_value = format!("Bearer {}", token.trim());
-        header.insert(AUTHORIZATION, HeaderValue::from_str(&auth_value)?);
-        success = true;
+        success = Some(insert_token(cli, header, token)?);
     } else if let Some(token_file) = &cli.token_file {
         // Try token file
         jinfo!("Using token from file: {}", token_file);
-        match fs::read_to_string(token_file) {
-            Ok(token) => {
-                let auth_value = format!("Bearer {}", token.trim());
-                header.insert(AUTHORIZATION, HeaderValue::from_str(&auth_value)?);
-                success = true;
-            }
-            Err(e) => {
-                return Err(GhrError::Auth(format!("Failed to read token file: {}", e)));
-            }
+        let tokens = read_token_file(token_file)?;
+        if tokens.len() > 1 {
+            jinfo!("Rotating between {} tokens from file", tokens.len());
         }
-    } else {
-        // Try .netrc as fallback
+        success = Some(insert_token(cli, header, &tokens[0])?);
+    } else if cli.provider == Provider::Github {
+        // Try .netrc as fallback; it only holds a github.com token
         if let Some(token) = read_netrc_token() {
             jinfo!("Using .netrc for authentication");
-            let auth_value = format!("Bearer {}", token.trim());
-            header.insert(AUTHORIZATION, HeaderValue::from_str(&auth_value)?);
-            success = true;
+            success = Some(insert_token(cli, header, &token)?);
         }
     }
 
-    if success {
-        Ok(())
+    success.ok_or_else(|| GhrError::Auth("No authentication method provided".to_string()))
+}
+
+/// Mask a token for display, keeping only its last 4 characters, e.g. "****************wxyz"
+pub fn mask_token(token: &str) -> String {
+    let token = token.trim();
+    let count = token.chars().count();
+    if count <= 4 {
+        return "*".repeat(count);
+    }
+    let shown = count - 4;
+    token
+        .chars()
+        .enumerate()
+        .map(|(i, c)| if i < shown { '*' } else { c })
+        .collect()
+}
+
+/// Report for `--auth-check`: which token sources were found, with masked tokens
+pub fn auth_check(cli: &Cli) -> Vec<String> {
+    let file = cli
+        .token_file
+        .as_deref()
+        .map(|path| (path, read_token_file(path)));
+    let netrc = if cli.provider == Provider::Github {
+        read_netrc_token()
     } else {
-        Err(GhrError::Auth(
-            "No authentication method provided".to_string(),
-        ))
+        None
+    };
+    auth_report(cli.token.as_deref(), file, netrc.as_deref())
+}
+
+fn auth_report(
+    flag: Option<&str>,
+    file: Option<(&str, Result<Vec<String>>)>,
+    netrc: Option<&str>,
+) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut used = None;
+
+    match flag {
+        Some(token) => {
+            lines.push(format!("--token:      found ({})", mask_token(token)));
+            used = used.or(Some("--token"));
+        }
+        None => lines.push("--token:      not given".to_string()),
     }
+
+    match file {
+        Some((path, Ok(tokens))) => {
+            let masked: Vec<String> = tokens.iter().map(|t| mask_token(t)).collect();
+            lines.push(format!(
+                "--token-file: found in {} ({})",
+                path,
+                masked.join(", ")
+            ));
+            used = used.or(Some("--token-file"));
+        }
+        Some((path, Err(e))) => lines.push(format!("--token-file: {} unusable: {}", path, e)),
+        None => lines.push("--token-file: not given".to_string()),
+    }
+
+    match netrc {
+        Some(token) => {
+            lines.push(format!(".netrc:       found ({})", mask_token(token)));
+            used = used.or(Some(".netrc"));
+        }
+        None => lines.push(".netrc:       no github.com password".to_string()),
+    }
+
+    lines.push(format!("Using:        {}", used.unwrap_or("no token")));
+    lines
 }
 
 /// Extract token from CLI arguments
@@ -87,11 +305,113 @@ pub fn extract_token_from_cli(cli: &Cli) -> Option<String> {
 
     // Try token file
     if let Some(token_file) = &cli.token_file {
-        if let Ok(token) = std::fs::read_to_string(token_file) {
-            return Some(token.trim().to_string());
+        if let Ok(tokens) = read_token_file(token_file) {
+            return tokens.into_iter().next();
         }
     }
 
     // Try .netrc
     read_netrc_token()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_netrc_github_token() {
+        let content = "machine github.com\nlogin user\npassword ghp_abc123\n";
+        assert_eq!(
+            parse_netrc_github_token(content),
+            Some("ghp_abc123".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_netrc_crlf() {
+        let content = "machine example.com\r\npassword other\r\nmachine github.com\r\nlogin user\r\npassword ghp_abc123\r\n";
+        assert_eq!(
+            parse_netrc_github_token(content),
+            Some("ghp_abc123".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_netrc_commented_password() {
+        let content = "# personal token\nmachine github.com # work\nlogin user\npassword ghp_abc123   # expires 2027\n";
+        assert_eq!(
+            parse_netrc_github_token(content),
+            Some("ghp_abc123".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_netrc_no_github_machine() {
+        let content = "machine example.com\nlogin user\npassword secret\n# machine github.com\n";
+        assert_eq!(parse_netrc_github_token(content), None);
+    }
+
+    #[test]
+    fn test_parse_token_file() {
+        let content = "ghp_one\n\n# backup tokens\nghp_two  # ci\n  ghp_three\n";
+        assert_eq!(
+            parse_token_file(content),
+            vec!["ghp_one", "ghp_two", "ghp_three"]
+        );
+    }
+
+    #[test]
+    fn test_mask_token() {
+        assert_eq!(mask_token("ghp_abcdefgh1234"), "************1234");
+        assert_eq!(mask_token(" ghp_xyz9 \n"), "****xyz9");
+        assert_eq!(mask_token("abcd"), "****");
+        assert_eq!(mask_token(""), "");
+    }
+
+    #[test]
+    fn test_auth_report_never_shows_tokens() {
+        let flag = "ghp_flagtoken0001";
+        let file_tokens = vec![
+            "ghp_filetoken0002".to_string(),
+            "ghp_filetoken0003".to_string(),
+        ];
+        let netrc = "ghp_netrctoken0004";
+
+        let lines = auth_report(
+            Some(flag),
+            Some(("/home/me/.github_token", Ok(file_tokens.clone()))),
+            Some(netrc),
+        );
+        let report = lines.join("\n");
+        for token in [flag, netrc, &file_tokens[0], &file_tokens[1]] {
+            assert!(!report.contains(token));
+            assert!(!report.contains(&token[..token.len() - 4]));
+        }
+        assert!(report.contains("*************0001"));
+        assert!(report.contains("*************0002, *************0003"));
+        assert!(report.contains("**************0004"));
+        assert_eq!(lines.last().unwrap(), "Using:        --token");
+
+        let lines = auth_report(None, None, None);
+        assert_eq!(lines.last().unwrap(), "Using:        no token");
+    }
+
+    #[test]
+    fn test_token_pool_skips_exhausted_tokens() {
+        let pool = TokenPool::new(vec!["a".into(), "b".into(), "c".into()]);
+        let picks: Vec<&str> = (0..4).map(|_| pool.next_token_at(1_000)).collect();
+        assert_eq!(picks, vec!["a", "b", "c", "a"]);
+
+        let mut headers = HeaderMap::new();
+        headers.insert("x-ratelimit-remaining", HeaderValue::from_static("0"));
+        headers.insert("x-ratelimit-reset", HeaderValue::from_static("2000"));
+        pool.record("b", &headers);
+
+        let picks: Vec<&str> = (0..4).map(|_| pool.next_token_at(1_000)).collect();
+        assert_eq!(picks, vec!["c", "a", "c", "a"]);
+
+        // Back in rotation once the limit has reset
+        let picks: Vec<&str> = (0..3).map(|_| pool.next_token_at(2_000)).collect();
+        assert_eq!(picks, vec!["b", "c", "a"]);
+    }
+}
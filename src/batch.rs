@@ -0,0 +1,171 @@
+use crate::constants;
+use crate::errors::{GhrError, Result};
+use futures::stream::{self, StreamExt};
+use jlogger_tracing::jwarn;
+use std::future::Future;
+use std::path::Path;
+
+/// Read the repositories of a `--repos-file`: one per line, blank lines and `#` comments ignored
+pub fn read_repos_file(path: &str) -> Result<Vec<String>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| GhrError::Generic(format!("Failed to read repos file '{}': {}", path, e)))?;
+    let repos: Vec<String> = content
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or_default().trim())
+        .filter(|line| !line.is_empty())
+        .map(String::from)
+        .collect();
+
+    if repos.is_empty() {
+        return Err(GhrError::Generic(format!(
+            "No repositories listed in '{}'",
+            path
+        )));
+    }
+    Ok(repos)
+}
+
+/// Download directory of one repository of a batch: `<base>/<owner>_<repo>`
+///
+/// Characters other than ASCII letters, digits, `.`, `_` and `-` become `_`,
+/// so nested GitLab groups and hosts also give a single path component.
+pub fn repo_dir(base: Option<&str>, repo: &str) -> String {
+    let name: String = repo
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    // Never "." or "..", nor a hidden directory
+    let name = name.trim_start_matches('.');
+    Path::new(base.unwrap_or("."))
+        .join(name)
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Bound the number of concurrent repositories and the downloads per repository
+///
+/// Returns the repository limit and the per-repository download concurrency,
+/// reduced so that at most [`constants::MAX_PARALLEL_DOWNLOADS`] downloads run
+/// at once in total.
+pub fn limit_parallelism(max_repos: usize, concurrency: usize) -> (usize, usize) {
+    let max = constants::MAX_PARALLEL_DOWNLOADS;
+    let repos = max_repos.clamp(1, max);
+    let concurrency = concurrency.max(1);
+
+    if repos * concurrency <= max {
+        return (repos, concurrency);
+    }
+    let reduced = (max / repos).max(1);
+    jwarn!(
+        "Limiting downloads to {} per repository ({} repositories at a time)",
+        reduced,
+        repos
+    );
+    (repos, reduced)
+}
+
+/// Run `operation` for every repository, at most `max_concurrent` at a time
+///
+/// Results are returned in the order of `repos`.
+pub async fn run_concurrently<F, Fut>(
+    repos: Vec<String>,
+    max_concurrent: usize,
+    operation: F,
+) -> Vec<(String, Result<()>)>
+where
+    F: Fn(String) -> Fut,
+    Fut: Future<Output = Result<()>>,
+{
+    stream::iter(repos)
+        .map(|repo| {
+            let future = operation(repo.clone());
+            async move { (repo, future.await) }
+        })
+        .buffered(max_concurrent.max(1))
+        .collect()
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use wiremock::matchers::path_regex;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_read_repos_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("repos.txt");
+        std::fs::write(&path, "# tools\nowner/one\n\nowner/two  # pinned\n").unwrap();
+
+        let repos = read_repos_file(path.to_str().unwrap()).unwrap();
+        assert_eq!(repos, vec!["owner/one", "owner/two"]);
+
+        std::fs::write(&path, "# nothing yet\n").unwrap();
+        assert!(read_repos_file(path.to_str().unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_repo_dir() {
+        assert_eq!(repo_dir(Some("out"), "owner/repo"), "out/owner_repo");
+        assert_eq!(repo_dir(None, "owner/repo.js"), "./owner_repo.js");
+        assert_eq!(
+            repo_dir(Some("out"), "group/sub group/project"),
+            "out/group_sub_group_project"
+        );
+        assert_eq!(repo_dir(Some("out"), "../etc"), "out/_etc");
+    }
+
+    #[test]
+    fn test_limit_parallelism() {
+        assert_eq!(limit_parallelism(4, 5), (4, 5));
+        assert_eq!(limit_parallelism(0, 5), (1, 5));
+        assert_eq!(limit_parallelism(8, 5), (8, 4));
+        assert_eq!(limit_parallelism(100, 5), (32, 1));
+    }
+
+    #[tokio::test]
+    async fn test_run_concurrently_is_bounded() {
+        let server = MockServer::start().await;
+        Mock::given(path_regex("^/repos/owner/repo-[0-9]+/releases$"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!([]))
+                    .set_delay(std::time::Duration::from_millis(50)),
+            )
+            .expect(10)
+            .mount(&server)
+            .await;
+
+        let client = reqwest::Client::new();
+        let in_flight = AtomicUsize::new(0);
+        let peak = AtomicUsize::new(0);
+        let repos: Vec<String> = (0..10).map(|i| format!("owner/repo-{}", i)).collect();
+
+        let results = run_concurrently(repos.clone(), 3, |repo| {
+            let (client, server, in_flight, peak) = (&client, &server, &in_flight, &peak);
+            async move {
+                let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(current, Ordering::SeqCst);
+                let url = format!("{}/repos/{}/releases", server.uri(), repo);
+                let response = client.get(url).send().await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                response?.error_for_status()?;
+                Ok(())
+            }
+        })
+        .await;
+
+        let done: Vec<&String> = results.iter().map(|(repo, _)| repo).collect();
+        assert_eq!(done, repos.iter().collect::<Vec<_>>());
+        assert!(results.iter().all(|(_, result)| result.is_ok()));
+        assert_eq!(peak.load(Ordering::SeqCst), 3);
+    }
+}
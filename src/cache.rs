@@ -1,15 +1,23 @@
-use crate::errors::Result;
+use crate::errors::{GhrError, Result};
+use fs2::FileExt;
 use jlogger_tracing::jdebug;
 use serde::{de::DeserializeOwned, Serialize};
-use std::path::PathBuf;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{Duration, SystemTime};
 use tokio::fs;
 
+/// Subdirectory of the cache directory holding the lock files of entries
+const LOCK_DIR: &str = "locks";
+
 /// Cache for GitHub API responses
 pub struct Cache {
     cache_dir: PathBuf,
     ttl: Duration,
     enabled: bool,
+    /// TTL of not-found results; `None` doesn't cache them
+    negative_ttl: Option<Duration>,
 }
 
 impl Cache {
@@ -23,6 +31,7 @@ impl Cache {
             cache_dir,
             ttl: Duration::from_secs(24 * 60 * 60), // 24 hours default
             enabled,
+            negative_ttl: None,
         }
     }
 
@@ -34,6 +43,31 @@ impl Cache {
         cache
     }
 
+    /// Enabled cache in the given directory instead of the user's cache
+    #[cfg(test)]
+    pub(crate) fn in_dir(dir: &std::path::Path) -> Self {
+        let mut cache = Self::new(true);
+        cache.cache_dir = dir.to_path_buf();
+        cache
+    }
+
+    /// Create an enabled cache whose entries never expire, for offline mode
+    pub fn for_offline() -> Self {
+        Self::new(true).without_expiry()
+    }
+
+    /// Keep entries regardless of their age
+    pub(crate) fn without_expiry(mut self) -> Self {
+        self.ttl = Duration::MAX;
+        self
+    }
+
+    /// Also cache not-found results, for the given (usually short) TTL
+    pub fn with_negative_ttl(mut self, ttl: Duration) -> Self {
+        self.negative_ttl = Some(ttl);
+        self
+    }
+
     /// Get cache file path for a given key
     fn cache_path(&self, key: &str) -> PathBuf {
         // Create a safe filename from the key
@@ -41,8 +75,53 @@ impl Cache {
         self.cache_dir.join(format!("{}.json", safe_key))
     }
 
+    /// Lock file guarding writes of the entry for `key`
+    ///
+    /// The lock is taken on a separate file, since the entry itself is replaced
+    /// by each write. Lock files are kept in a directory of their own rather
+    /// than next to the entries; they are never removed while in use, as a
+    /// writer waiting on a removed file would no longer exclude new writers.
+    fn lock_path(&self, key: &str) -> PathBuf {
+        let safe_key = key.replace(['/', ':'], "_");
+        self.cache_dir
+            .join(LOCK_DIR)
+            .join(format!("{}.lock", safe_key))
+    }
+
     /// Get cached value if it exists and is not expired
+    ///
+    /// Entries that cannot be parsed, e.g. left truncated by an older version
+    /// that was killed while writing, are removed and count as a miss.
     pub async fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let data = self.read_fresh(key, self.ttl).await?;
+        match serde_json::from_str(&data) {
+            Ok(value) => Some(value),
+            Err(e) => {
+                jdebug!("Cache entry corrupt, removing: {} ({})", key, e);
+                let _ = fs::remove_file(self.cache_path(key)).await;
+                None
+            }
+        }
+    }
+
+    /// Whether a lookup for `key` recently found nothing
+    pub async fn is_known_missing(&self, key: &str) -> bool {
+        match self.negative_ttl {
+            Some(ttl) => self.read_fresh(&negative_key(key), ttl).await.is_some(),
+            None => false,
+        }
+    }
+
+    /// Remember that a lookup for `key` found nothing
+    pub async fn set_missing(&self, key: &str) -> Result<()> {
+        if self.negative_ttl.is_none() {
+            return Ok(());
+        }
+        self.set(&negative_key(key), &()).await
+    }
+
+    /// Read a cache entry if it exists and is younger than `ttl`
+    async fn read_fresh(&self, key: &str, ttl: Duration) -> Option<String> {
         if !self.enabled {
             return None;
         }
@@ -53,24 +132,27 @@ impl Cache {
             return None;
         }
 
+        // Another process is writing the entry; don't wait for it
+        if is_locked(&self.lock_path(key)) {
+            jdebug!("Cache entry locked, skipping: {}", key);
+            return None;
+        }
+
         // Check if expired
         let metadata = fs::metadata(&path).await.ok()?;
         let modified = metadata.modified().ok()?;
         let age = SystemTime::now().duration_since(modified).ok()?;
 
-        if age > self.ttl {
+        if age > ttl {
             jdebug!("Cache expired: {}", key);
             // Cleanup expired entry
             let _ = fs::remove_file(&path).await;
             return None;
         }
 
-        // Read and parse cached data
         let data = fs::read_to_string(&path).await.ok()?;
-        let result: T = serde_json::from_str(&data).ok()?;
-
         jdebug!("Cache hit: {} (age: {:?})", key, age);
-        Some(result)
+        Some(data)
     }
 
     /// Set cached value
@@ -80,11 +162,26 @@ impl Cache {
         }
 
         // Ensure cache directory exists
-        fs::create_dir_all(&self.cache_dir).await?;
+        fs::create_dir_all(self.cache_dir.join(LOCK_DIR)).await?;
 
         let path = self.cache_path(key);
         let data = serde_json::to_string(value)?;
-        fs::write(&path, data).await?;
+
+        // Concurrent writers of the entry, also in other processes, take turns;
+        // the lock is released when `_lock` is dropped
+        let _lock = lock_exclusive(self.lock_path(key)).await?;
+
+        // Write a temporary file and rename it into place, so an interrupted
+        // write never leaves a partial entry behind
+        let tmp_path = temp_path(&path);
+        if let Err(e) = fs::write(&tmp_path, data).await {
+            let _ = fs::remove_file(&tmp_path).await;
+            return Err(e.into());
+        }
+        if let Err(e) = fs::rename(&tmp_path, &path).await {
+            let _ = fs::remove_file(&tmp_path).await;
+            return Err(e.into());
+        }
 
         jdebug!("Cache set: {}", key);
         Ok(())
@@ -101,6 +198,46 @@ impl Cache {
     }
 }
 
+/// Unique temporary file next to `path`, also among concurrent writes of the same entry
+fn temp_path(path: &Path) -> PathBuf {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(
+        ".{}.{}.tmp",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    path.with_file_name(name)
+}
+
+/// Wait for an exclusive advisory lock on `path`, creating the file if needed
+async fn lock_exclusive(path: PathBuf) -> Result<File> {
+    tokio::task::spawn_blocking(move || {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&path)?;
+        file.lock_exclusive()?;
+        Ok(file)
+    })
+    .await
+    .map_err(|e| GhrError::Generic(format!("Cache lock task failed: {}", e)))?
+}
+
+/// Whether another writer holds the lock at `path`
+fn is_locked(path: &Path) -> bool {
+    match File::open(path) {
+        Ok(file) => file.try_lock_shared().is_err(),
+        Err(_) => false,
+    }
+}
+
+/// Key under which a not-found result for `key` is cached
+fn negative_key(key: &str) -> String {
+    format!("404:{}", key)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -144,4 +281,85 @@ mod tests {
         let result: Option<TestData> = cache.get("nonexistent-key").await;
         assert!(result.is_none());
     }
+
+    /// Enabled cache in a directory of its own
+    fn temp_cache(dir: &tempfile::TempDir) -> Cache {
+        Cache::in_dir(dir.path())
+    }
+
+    #[tokio::test]
+    async fn test_corrupt_entry_is_removed() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = temp_cache(&dir);
+
+        // Simulate a write that was cut short
+        let path = cache.cache_path("test-corrupt-key");
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, r#"{"value": "te"#).unwrap();
+
+        let result: Option<TestData> = cache.get("test-corrupt-key").await;
+        assert!(result.is_none());
+        assert!(!path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_sets_of_one_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = temp_cache(&dir);
+        let first = TestData {
+            value: "a".repeat(100_000),
+        };
+        let second = TestData {
+            value: "b".repeat(100_000),
+        };
+
+        let (r1, r2) = tokio::join!(
+            cache.set("test-race-key", &first),
+            cache.set("test-race-key", &second)
+        );
+        r1.unwrap();
+        r2.unwrap();
+
+        // One of the writes wins, complete
+        let result: TestData = cache.get("test-race-key").await.unwrap();
+        assert!(result == first || result == second);
+
+        // No temporary or lock files are left next to the entry
+        let mut names: Vec<String> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec![LOCK_DIR, "test-race-key.json"]);
+    }
+
+    #[tokio::test]
+    async fn test_locked_entry_is_a_miss() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = temp_cache(&dir);
+        let data = TestData {
+            value: "test".to_string(),
+        };
+        cache.set("test-locked-key", &data).await.unwrap();
+
+        let lock = lock_exclusive(cache.lock_path("test-locked-key"))
+            .await
+            .unwrap();
+        let result: Option<TestData> = cache.get("test-locked-key").await;
+        assert!(result.is_none());
+
+        drop(lock);
+        let result: Option<TestData> = cache.get("test-locked-key").await;
+        assert_eq!(result, Some(data));
+    }
+
+    #[test]
+    fn test_temp_path_is_unique() {
+        let path = Path::new("/cache/ghr/releases_owner_repo.json");
+        let first = temp_path(path);
+        let second = temp_path(path);
+        assert_ne!(first, second);
+        assert_eq!(first.parent(), path.parent());
+        assert!(first.to_string_lossy().ends_with(".tmp"));
+    }
 }
@@ -0,0 +1,534 @@
+use crate::cli::HashAlgorithm;
+use crate::errors::{GhrError, Result};
+use crate::http::HostToken;
+use futures::stream::{self, StreamExt};
+use jlogger_tracing::jdebug;
+use reqwest::Client;
+use sha2::{Digest, Sha256, Sha512};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use tokio::sync::Semaphore;
+
+/// Parse a `SHA256SUMS`-style file into a map of file name to lowercase hex digest
+///
+/// Lines have the form `<hex>  <filename>` as produced by `sha256sum`. A single
+/// space separator and the `*` binary-mode marker before the name are accepted
+/// as well. Blank lines and `#` comments are ignored.
+pub fn parse_checksums(content: &str) -> HashMap<String, String> {
+    let mut sums = HashMap::new();
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let Some((digest, name)) = line.split_once(char::is_whitespace) else {
+            jdebug!("Ignoring malformed checksum line: {}", line);
+            continue;
+        };
+
+        let name = name.trim_start();
+        let name = name.strip_prefix('*').unwrap_or(name);
+        if name.is_empty() || !digest.chars().all(|c| c.is_ascii_hexdigit()) {
+            jdebug!("Ignoring malformed checksum line: {}", line);
+            continue;
+        }
+
+        sums.insert(name.to_string(), digest.to_ascii_lowercase());
+    }
+
+    sums
+}
+
+/// Download and parse a checksums file
+///
+/// The manifest may live on any host, so pick the client with
+/// [`crate::http::client_for`] to keep the API token from being sent elsewhere.
+/// `host_token` is only added for the API host.
+pub async fn fetch_checksums(
+    client: &Client,
+    url: &str,
+    host_token: Option<&HostToken>,
+) -> Result<HashMap<String, String>> {
+    let mut request = client.get(url);
+    if let Some(host_token) = host_token {
+        request = host_token.apply(request, url);
+    }
+    let response = request.send().await?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(GhrError::GitHubApi(format!(
+            "HTTP {} while fetching checksums from '{}'",
+            status, url
+        )));
+    }
+
+    let content = response.text().await?;
+    Ok(parse_checksums(&content))
+}
+
+/// Compute the digest of a file with the given algorithm as lowercase hex
+pub fn digest_file(path: &Path, algorithm: HashAlgorithm) -> Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let digest = match algorithm {
+        HashAlgorithm::Sha256 => {
+            let mut hasher = Sha256::new();
+            std::io::copy(&mut file, &mut hasher)?;
+            hex::encode(hasher.finalize())
+        }
+        HashAlgorithm::Sha512 => {
+            let mut hasher = Sha512::new();
+            std::io::copy(&mut file, &mut hasher)?;
+            hex::encode(hasher.finalize())
+        }
+        HashAlgorithm::Blake3 => {
+            let mut hasher = blake3::Hasher::new();
+            std::io::copy(&mut file, &mut hasher)?;
+            hasher.finalize().to_hex().to_string()
+        }
+    };
+    Ok(digest)
+}
+
+/// Checksum manifest lines ("<hex>  <name>", as written by sha256sum) for the
+/// given `(name, path)` files, sorted by name
+pub fn checksum_lines(files: &[(&str, &Path)], algorithm: HashAlgorithm) -> Result<Vec<String>> {
+    digest_lines(files, algorithm, |digest, name| {
+        format!("{}  {}", digest, name)
+    })
+}
+
+/// Checksum lines labeled with their algorithm ("SHA256 (<name>) = <hex>", the
+/// BSD format of `sha256sum --tag`), sorted by name
+pub fn tagged_checksum_lines(
+    files: &[(&str, &Path)],
+    algorithm: HashAlgorithm,
+) -> Result<Vec<String>> {
+    digest_lines(files, algorithm, |digest, name| {
+        format!("{} ({}) = {}", algorithm.tag(), name, digest)
+    })
+}
+
+fn digest_lines(
+    files: &[(&str, &Path)],
+    algorithm: HashAlgorithm,
+    line: impl Fn(&str, &str) -> String,
+) -> Result<Vec<String>> {
+    let mut files = files.to_vec();
+    files.sort_by(|a, b| a.0.cmp(b.0));
+    files
+        .into_iter()
+        .map(|(name, path)| {
+            let digest = digest_file(path, algorithm).map_err(|e| {
+                GhrError::Generic(format!("Cannot compute checksum of '{}': {}", name, e))
+            })?;
+            Ok(line(&digest, name))
+        })
+        .collect()
+}
+
+/// Verify a downloaded file against its expected SHA-256 digest
+pub fn verify_file(name: &str, path: &Path, expected: &str) -> Result<()> {
+    let actual = digest_file(path, HashAlgorithm::Sha256)?;
+    if actual.eq_ignore_ascii_case(expected) {
+        jdebug!("Checksum OK: {}", name);
+        Ok(())
+    } else {
+        Err(GhrError::ChecksumMismatch {
+            asset: name.to_string(),
+            expected: expected.to_string(),
+            actual,
+        })
+    }
+}
+
+/// State of a previously downloaded file, as found by `--verify-only`
+#[derive(Debug, PartialEq, Eq)]
+pub enum FileStatus {
+    /// Size and, if known, digest match the release asset
+    Ok,
+    /// No file at the expected path
+    Missing,
+    /// The file differs from the release asset
+    Corrupt(String),
+}
+
+/// Check an existing file against the asset's size and, if known, SHA-256 digest
+///
+/// A size of zero means the size is unknown and is not compared.
+pub fn check_existing(path: &Path, size: u64, expected: Option<&str>) -> Result<FileStatus> {
+    let metadata = match std::fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(FileStatus::Missing),
+        Err(e) => return Err(e.into()),
+    };
+
+    if size > 0 && metadata.len() != size {
+        return Ok(FileStatus::Corrupt(format!(
+            "size {} bytes, expected {}",
+            metadata.len(),
+            size
+        )));
+    }
+
+    if let Some(expected) = expected {
+        let actual = digest_file(path, HashAlgorithm::Sha256)?;
+        if !actual.eq_ignore_ascii_case(expected) {
+            return Ok(FileStatus::Corrupt(format!(
+                "sha256 {}, expected {}",
+                actual, expected
+            )));
+        }
+    }
+
+    Ok(FileStatus::Ok)
+}
+
+/// Number of files to hash at the same time: one per core
+pub fn hash_parallelism() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+}
+
+/// Check existing files like [`check_existing`], hashing up to `parallelism` at once
+///
+/// Each entry is the file's path, size and expected digest. Results are in the
+/// order of `files`.
+pub async fn check_existing_all(
+    files: Vec<(PathBuf, u64, Option<String>)>,
+    parallelism: usize,
+) -> Vec<Result<FileStatus>> {
+    stream::iter(files)
+        .map(|(path, size, expected)| async move {
+            tokio::task::spawn_blocking(move || check_existing(&path, size, expected.as_deref()))
+                .await
+                .map_err(|e| GhrError::Generic(format!("Checksum task failed: {}", e)))?
+        })
+        .buffered(parallelism.max(1))
+        .collect()
+        .await
+}
+
+/// Where the expected digest of a file came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DigestSource {
+    /// A line of the `--checksums-url` manifest
+    Manifest,
+    /// The `digest` field of the asset reported by the API
+    Api,
+}
+
+impl DigestSource {
+    pub fn label(&self) -> &'static str {
+        match self {
+            DigestSource::Manifest => "checksums file",
+            DigestSource::Api => "API digest",
+        }
+    }
+}
+
+/// Result of one file in the `--parallel-hash-verify` report
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HashOutcome {
+    /// The file matches the checksum from the given source
+    Pass(DigestSource),
+    /// The file is missing, corrupt or was not downloaded, with the reason
+    Fail(String),
+    /// No checksum is listed for the file, so it was not verified
+    MissingSum,
+}
+
+impl HashOutcome {
+    /// Outcome of a `--verify-only` check, given the source of the checksum, if
+    /// one was known
+    pub fn from_status(status: FileStatus, source: Option<DigestSource>) -> Self {
+        match status {
+            FileStatus::Ok => source.map_or(HashOutcome::MissingSum, HashOutcome::Pass),
+            FileStatus::Missing => HashOutcome::Fail("file missing".to_string()),
+            FileStatus::Corrupt(reason) => HashOutcome::Fail(reason),
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            HashOutcome::Pass(_) => "PASS",
+            HashOutcome::Fail(_) => "FAIL",
+            HashOutcome::MissingSum => "MISSING-SUM",
+        }
+    }
+}
+
+/// Per-file outcomes of a checksum verification run
+#[derive(Debug, Default)]
+pub struct HashReport {
+    pub entries: Vec<(String, HashOutcome)>,
+}
+
+impl HashReport {
+    pub fn add(&mut self, file: impl Into<String>, outcome: HashOutcome) {
+        self.entries.push((file.into(), outcome));
+    }
+
+    /// Number of files that failed verification
+    pub fn failed(&self) -> usize {
+        self.entries
+            .iter()
+            .filter(|(_, outcome)| matches!(outcome, HashOutcome::Fail(_)))
+            .count()
+    }
+}
+
+/// Verify a file like [`verify_file`], hashing on the blocking thread pool
+///
+/// Hashing large files would otherwise stall the async runtime and with it
+/// the downloads still in progress. `limit` bounds how many files are hashed
+/// at the same time.
+pub async fn verify_file_blocking(
+    name: String,
+    path: PathBuf,
+    expected: String,
+    limit: &Semaphore,
+) -> Result<()> {
+    let _permit = limit
+        .acquire()
+        .await
+        .map_err(|e| GhrError::Generic(format!("Checksum verification aborted: {}", e)))?;
+
+    let start = Instant::now();
+    let asset = name.clone();
+    let result = tokio::task::spawn_blocking(move || verify_file(&name, &path, &expected))
+        .await
+        .map_err(|e| GhrError::Generic(format!("Checksum task for '{}' failed: {}", asset, e)))?;
+    jdebug!("Hashed '{}' in {:?}", asset, start.elapsed());
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HELLO_SHA256: &str = "5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03";
+
+    #[test]
+    fn test_parse_checksums() {
+        let content = format!(
+            "# release checksums\n\
+             {HELLO_SHA256}  app-linux.tar.gz\n\
+             ABCDEF0123 app-darwin.tar.gz\n\
+             0123456789abcdef *app-windows.zip\n\
+             \n\
+             not-a-digest  broken.txt\n"
+        );
+
+        let sums = parse_checksums(&content);
+        assert_eq!(sums.len(), 3);
+        assert_eq!(sums["app-linux.tar.gz"], HELLO_SHA256);
+        assert_eq!(sums["app-darwin.tar.gz"], "abcdef0123");
+        assert_eq!(sums["app-windows.zip"], "0123456789abcdef");
+    }
+
+    #[tokio::test]
+    async fn test_fetch_checksums_keeps_token_from_other_hosts() {
+        use reqwest::header::{HeaderMap, AUTHORIZATION};
+        use wiremock::matchers::path;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(path("/SHA256SUMS"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_string(format!("{HELLO_SHA256}  app.tar.gz\n")),
+            )
+            .mount(&server)
+            .await;
+
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, crate::auth::bearer("ghp_secret").unwrap());
+        let options = crate::http::ClientOptions::default();
+        let authenticated = crate::http::build_client(headers, &options).unwrap();
+        let public = crate::http::build_client(HeaderMap::new(), &options).unwrap();
+
+        let url = format!("{}/SHA256SUMS", server.uri());
+        let client =
+            crate::http::client_for(&url, "https://api.github.com", &authenticated, &public);
+        let sums = fetch_checksums(client, &url, None).await.unwrap();
+        assert_eq!(sums["app.tar.gz"], HELLO_SHA256);
+
+        let requests = server.received_requests().await.unwrap();
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].headers.get("authorization").is_none());
+    }
+
+    #[test]
+    fn test_parse_checksums_crlf() {
+        let sums = parse_checksums(&format!("{HELLO_SHA256}  hello.txt\r\n"));
+        assert_eq!(sums["hello.txt"], HELLO_SHA256);
+    }
+
+    #[test]
+    fn test_verify_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("hello.txt");
+        std::fs::write(&path, b"hello\n").unwrap();
+
+        let sums = parse_checksums(&format!("{HELLO_SHA256}  hello.txt\n"));
+        assert!(verify_file("hello.txt", &path, &sums["hello.txt"]).is_ok());
+
+        std::fs::write(&path, b"tampered\n").unwrap();
+        assert!(matches!(
+            verify_file("hello.txt", &path, &sums["hello.txt"]),
+            Err(GhrError::ChecksumMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn test_check_existing() {
+        let dir = tempfile::tempdir().unwrap();
+        let good = dir.path().join("good.txt");
+        let corrupt = dir.path().join("corrupt.txt");
+        std::fs::write(&good, b"hello\n").unwrap();
+        std::fs::write(&corrupt, b"hellO\n").unwrap();
+
+        assert_eq!(
+            check_existing(&good, 6, Some(HELLO_SHA256)).unwrap(),
+            FileStatus::Ok
+        );
+        assert!(matches!(
+            check_existing(&corrupt, 6, Some(HELLO_SHA256)).unwrap(),
+            FileStatus::Corrupt(_)
+        ));
+        assert!(matches!(
+            check_existing(&good, 7, None).unwrap(),
+            FileStatus::Corrupt(_)
+        ));
+        assert_eq!(
+            check_existing(&dir.path().join("missing.txt"), 6, Some(HELLO_SHA256)).unwrap(),
+            FileStatus::Missing
+        );
+    }
+
+    #[test]
+    fn test_checksum_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let hello = dir.path().join("hello.txt");
+        let empty = dir.path().join("empty.bin");
+        std::fs::write(&hello, "hello\n").unwrap();
+        std::fs::write(&empty, "").unwrap();
+        let files = [
+            ("hello.txt", hello.as_path()),
+            ("empty.bin", empty.as_path()),
+        ];
+
+        let lines = checksum_lines(&files, HashAlgorithm::Sha256).unwrap();
+        assert_eq!(lines[1], format!("{HELLO_SHA256}  hello.txt"));
+        // Sorted by name, two spaces between digest and name like sha256sum
+        assert_eq!(
+            lines[0],
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855  empty.bin"
+        );
+
+        let lines = checksum_lines(&files, HashAlgorithm::Sha512).unwrap();
+        assert_eq!(
+            lines[1],
+            "e7c22b994c59d9cf2b48e549b1e24666636045930d3da7c1acb299d1c3b7f931\
+             f94aae41edda2c2b207a36e10f8bcb8d45223e54878f5b316e7ce3b6bc019629  hello.txt"
+        );
+
+        let lines = checksum_lines(&files, HashAlgorithm::Blake3).unwrap();
+        assert_eq!(
+            lines[0],
+            "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262  empty.bin"
+        );
+
+        let lines = tagged_checksum_lines(&files, HashAlgorithm::Blake3).unwrap();
+        assert_eq!(
+            lines[0],
+            "BLAKE3 (empty.bin) = af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262"
+        );
+        let lines = tagged_checksum_lines(&files, HashAlgorithm::Sha256).unwrap();
+        assert_eq!(lines[1], format!("SHA256 (hello.txt) = {HELLO_SHA256}"));
+
+        let missing = dir.path().join("missing.bin");
+        assert!(
+            checksum_lines(&[("missing.bin", missing.as_path())], HashAlgorithm::Sha256).is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_verify_file_blocking_concurrent() {
+        let dir = tempfile::tempdir().unwrap();
+        let limit = Semaphore::new(2);
+
+        let mut files = Vec::new();
+        for i in 0..16 {
+            let name = format!("asset-{}.bin", i);
+            let path = dir.path().join(&name);
+            let content = vec![i as u8; 64 * 1024 + i];
+            std::fs::write(&path, &content).unwrap();
+
+            // Corrupt the expectation of one file
+            let expected = if i == 7 {
+                HELLO_SHA256.to_string()
+            } else {
+                hex::encode(Sha256::digest(&content))
+            };
+            files.push((name, path, expected));
+        }
+
+        let results = futures::future::join_all(
+            files
+                .into_iter()
+                .map(|(name, path, expected)| verify_file_blocking(name, path, expected, &limit)),
+        )
+        .await;
+
+        for (i, result) in results.iter().enumerate() {
+            if i == 7 {
+                assert!(matches!(result, Err(GhrError::ChecksumMismatch { .. })));
+            } else {
+                assert!(result.is_ok(), "asset-{} failed: {:?}", i, result);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_check_existing_all_outcomes() {
+        let dir = tempfile::tempdir().unwrap();
+        let good = dir.path().join("good.bin");
+        let bad = dir.path().join("bad.bin");
+        let unlisted = dir.path().join("unlisted.bin");
+        for path in [&good, &bad, &unlisted] {
+            std::fs::write(path, b"hello\n").unwrap();
+        }
+        let missing = dir.path().join("missing.bin");
+
+        let files = vec![
+            (good, 6, Some(HELLO_SHA256.to_string())),
+            (bad, 6, Some("00".repeat(32))),
+            (unlisted, 6, None),
+            (missing, 6, Some(HELLO_SHA256.to_string())),
+        ];
+        let sources: Vec<Option<DigestSource>> = files
+            .iter()
+            .map(|(_, _, sum)| sum.as_ref().map(|_| DigestSource::Manifest))
+            .collect();
+        let mut report = HashReport::default();
+        for (i, status) in check_existing_all(files, 2).await.into_iter().enumerate() {
+            report.add(
+                format!("file{}", i),
+                HashOutcome::from_status(status.unwrap(), sources[i]),
+            );
+        }
+
+        let labels: Vec<&str> = report.entries.iter().map(|(_, o)| o.label()).collect();
+        assert_eq!(labels, vec!["PASS", "FAIL", "MISSING-SUM", "FAIL"]);
+        assert_eq!(
+            report.entries[3].1,
+            HashOutcome::Fail("file missing".to_string())
+        );
+        assert_eq!(report.failed(), 2);
+    }
+}
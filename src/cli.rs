@@ -1,4 +1,10 @@
-use clap::{ArgAction, Parser, ValueEnum};
+use crate::download::MinRate;
+use crate::errors::{GhrError, Result};
+use crate::output::InfoSection;
+use clap::parser::ValueSource;
+use clap::{ArgAction, CommandFactory, FromArgMatches, Parser, ValueEnum};
+use std::ffi::OsString;
+use std::time::Duration;
 
 /// Output format for list and search commands
 #[derive(ValueEnum, Clone, Debug, Default)]
@@ -8,20 +14,168 @@ pub enum OutputFormat {
     Table,
     /// JSON format
     Json,
+    /// CSV format with a header row
+    Csv,
+}
+
+/// Sort order for release and asset listings
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SortKey {
+    /// Order as returned by the API; releases newest first (default)
+    #[default]
+    Date,
+    /// Download count, most downloaded first
+    Downloads,
+    /// Tag in natural order ("build-10" after "build-2"), highest first
+    Natural,
+    /// Size, largest first
+    Size,
+    /// Tag or asset name, alphabetically
+    Name,
+}
+
+/// Column of the release listing, selected with --columns
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReleaseColumn {
+    /// Position in the listing
+    No,
+    /// Tag name
+    Tag,
+    /// Release title
+    Name,
+    /// Publication date
+    Published,
+    /// Number of assets
+    Assets,
+    /// Total size of the assets
+    Size,
+    /// "release", "prerelease" or "draft"
+    Type,
+}
+
+impl ReleaseColumn {
+    /// Columns shown without --columns
+    pub const DEFAULT: &'static [ReleaseColumn] = &[
+        ReleaseColumn::No,
+        ReleaseColumn::Tag,
+        ReleaseColumn::Name,
+        ReleaseColumn::Published,
+        ReleaseColumn::Assets,
+        ReleaseColumn::Size,
+    ];
+
+    /// Header of the column
+    pub fn title(self) -> &'static str {
+        match self {
+            ReleaseColumn::No => "No",
+            ReleaseColumn::Tag => "Tag",
+            ReleaseColumn::Name => "Name",
+            ReleaseColumn::Published => "Published",
+            ReleaseColumn::Assets => "Assets",
+            ReleaseColumn::Size => "Size",
+            ReleaseColumn::Type => "Type",
+        }
+    }
+}
+
+/// Archive format used when extracting downloaded assets
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    /// Detect from the file extension (default)
+    #[default]
+    Auto,
+    /// gzip-compressed tar (.tar.gz, .tgz)
+    TarGz,
+    /// xz-compressed tar (.tar.xz, .txz)
+    TarXz,
+    /// zstd-compressed tar (.tar.zst, .tzst)
+    TarZst,
+}
+
+/// Digest algorithm for --print-checksum
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    /// SHA-256, as in SHA256SUMS files
+    Sha256,
+    /// SHA-512, as in SHA512SUMS files
+    Sha512,
+    /// BLAKE3, as in b3sum output
+    Blake3,
+}
+
+impl HashAlgorithm {
+    /// Name labeling the digests of this algorithm in BSD-style checksum lines
+    pub fn tag(self) -> &'static str {
+        match self {
+            HashAlgorithm::Sha256 => "SHA256",
+            HashAlgorithm::Sha512 => "SHA512",
+            HashAlgorithm::Blake3 => "BLAKE3",
+        }
+    }
+}
+
+/// Service hosting the repository's releases
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Provider {
+    /// GitHub or GitHub Enterprise (default)
+    #[default]
+    Github,
+    /// GitLab.com or a self-managed GitLab instance
+    Gitlab,
+}
+
+/// Minimum TLS version for HTTPS connections
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TlsVersion {
+    /// TLS 1.2
+    #[value(name = "1.2")]
+    V1_2,
+    /// TLS 1.3
+    #[value(name = "1.3")]
+    V1_3,
+}
+
+impl From<TlsVersion> for reqwest::tls::Version {
+    fn from(version: TlsVersion) -> Self {
+        match version {
+            TlsVersion::V1_2 => reqwest::tls::Version::TLS_1_2,
+            TlsVersion::V1_3 => reqwest::tls::Version::TLS_1_3,
+        }
+    }
 }
 
 /// CLI arguments
-#[derive(Parser)]
+#[derive(Parser, Clone)]
 #[command(
     name = "Github release fetcher",
     version,
     about = "A tool to retrieve and download github release package."
 )]
 pub struct Cli {
-    /// GitHub Repository in the format "owner/repo" (required for release operations)
+    /// GitHub Repository in the format "owner/repo" (required for release operations),
+    /// or "host/owner/repo" to also select the GitHub instance.
+    /// Defaults to the `origin` remote when run inside a GitHub checkout
     #[arg(long, short = 'r')]
     pub repo: Option<String>,
 
+    /// File listing repositories ("owner/repo", one per line) to run the same
+    /// operation on
+    #[arg(long = "repos-file", value_name = "PATH", conflicts_with_all = ["repo", "pick"])]
+    pub repos_file: Option<String>,
+
+    /// With --repos-file, number of repositories processed at the same time
+    #[arg(long = "max-concurrent-repos", value_name = "N", default_value_t = 1)]
+    pub max_concurrent_repos: usize,
+
+    /// With --repos-file, download every repository into the directory itself instead
+    /// of an "<owner>_<repo>" subdirectory each
+    #[arg(long = "flat", requires = "repos_file")]
+    pub flat: bool,
+
+    /// Repository owner; when given, --repo takes just the repository name
+    #[arg(long = "owner", requires = "repo")]
+    pub owner: Option<String>,
+
     /// Token for GitHub API authentication
     #[arg(short = 't', long = "token")]
     pub token: Option<String>,
@@ -30,15 +184,144 @@ pub struct Cli {
     #[arg(short = 'T', long = "token-file")]
     pub token_file: Option<String>,
 
-    /// Specific version to download (or "latest" for the most recent release)
+    /// Show which token sources were found, with the tokens masked, and exit
+    #[arg(long = "auth-check")]
+    pub auth_check: bool,
+
+    /// Specific version to download, or "latest" (most recent release), "latest-stable"
+    /// (most recent non-prerelease) or "pre-latest" (most recent prerelease). A glob such
+    /// as "v1.*" downloads every matching release into per-tag directories
     #[arg(short = 'd', long = "download")]
     pub download: Option<String>,
 
+    /// When the release given to --download has no asset matching the filters, try up to
+    /// N older releases and download from the first one that does
+    #[arg(long = "fallback-scan", value_name = "N", requires = "download")]
+    pub fallback_scan: Option<usize>,
+
+    /// When "--download latest-stable" finds no stable release, download the latest
+    /// prerelease instead of failing
+    #[arg(long = "prerelease-fallback", requires = "download")]
+    pub prerelease_fallback: bool,
+
+    /// Download the release tagged REF, or the source tarball of REF (tag, branch or
+    /// commit SHA) when there is no such release
+    #[arg(
+        long = "ref",
+        value_name = "REF",
+        conflicts_with_all = ["download", "since_release", "download_index"]
+    )]
+    pub git_ref: Option<String>,
+
+    /// Download assets of every release published after this tag, into per-tag directories
+    #[arg(
+        long = "since-release",
+        value_name = "TAG",
+        conflicts_with = "download"
+    )]
+    pub since_release: Option<String>,
+
+    /// Mirror every release: download the matching assets of all releases, across all
+    /// pages of the release list, into per-tag directories
+    #[arg(
+        long = "all-releases",
+        conflicts_with_all = ["download", "since_release", "download_index", "git_ref"]
+    )]
+    pub all_releases: bool,
+
+    /// Maximum number of releases mirrored by --all-releases, newest first
+    #[arg(
+        long = "max-releases",
+        value_name = "N",
+        default_value_t = crate::constants::DEFAULT_MAX_RELEASES,
+        requires = "all_releases"
+    )]
+    pub max_releases: usize,
+
+    /// Don't ask for confirmation before mirroring with --all-releases
+    #[arg(short = 'y', long = "assume-yes")]
+    pub assume_yes: bool,
+
+    /// Download assets of a release by their index as shown by --list-assets,
+    /// e.g. "v1.2.3:2,4"
+    #[arg(
+        long = "download-index",
+        value_name = "TAG:N[,N...]",
+        conflicts_with_all = ["download", "since_release"]
+    )]
+    pub download_index: Option<String>,
+
     /// String used to filter the name of assets to download, multiple filters can be separated by
     /// commas.
     #[arg(short = 'f', long = "filter")]
     pub filter: Option<String>,
 
+    /// Only include assets of at least this size, e.g. "10MB" or "1.5MiB"
+    #[arg(long = "min-size", value_name = "SIZE", value_parser = crate::filters::parse_size)]
+    pub min_size: Option<u64>,
+
+    /// Only include assets of at most this size, e.g. "500KB" or "2GiB"
+    #[arg(long = "max-size", value_name = "SIZE", value_parser = crate::filters::parse_size)]
+    pub max_size: Option<u64>,
+
+    /// Fail when no asset matches the filters, instead of exiting successfully
+    #[arg(long = "require-match", alias = "exit-on-no-match")]
+    pub require_match: bool,
+
+    /// Refuse to download when the filters select more than N assets
+    #[arg(long = "max-assets", value_name = "N")]
+    pub max_assets: Option<usize>,
+
+    /// Also download assets whose upload has not completed yet
+    #[arg(long = "include-incomplete")]
+    pub include_incomplete: bool,
+
+    /// Interactively pick the assets to download with a fuzzy finder (requires a terminal)
+    #[arg(long = "pick", alias = "select-asset-interactive")]
+    pub pick: bool,
+
+    /// Also download the .sha256, .sig, .asc and .minisig files published for each
+    /// selected asset
+    #[arg(long = "with-sidecars")]
+    pub with_sidecars: bool,
+
+    /// URL of a SHA256SUMS-style file to verify downloaded assets against
+    #[arg(long = "checksums-url", value_name = "URL")]
+    pub checksums_url: Option<String>,
+
+    /// Download an asset again, up to N times, when it does not match its checksum
+    #[arg(
+        long = "retry-on-checksum-mismatch",
+        value_name = "N",
+        default_value_t = 0,
+        requires = "checksums_url"
+    )]
+    pub retry_on_checksum_mismatch: u32,
+
+    /// Write an overall JSON report of the run (counts, total size, elapsed time and the
+    /// status of each asset) to this file
+    #[arg(long = "summary-json", value_name = "PATH")]
+    pub summary_json: Option<std::path::PathBuf>,
+
+    /// Write the metadata of each downloaded asset (name, size, download count, content
+    /// type, source URL and verified checksum) to a `<file>.json` next to it
+    #[arg(long = "assets-json")]
+    pub assets_json: bool,
+
+    /// Extract downloaded archives (.tar.gz, .tar.xz, .tar.zst) into a directory named after each archive
+    #[arg(long = "extract")]
+    pub extract: bool,
+
+    /// Archive format to assume when extracting; all downloaded assets are treated as
+    /// this format unless "auto"
+    #[arg(long = "archive-format", value_enum, default_value_t = ArchiveFormat::Auto, requires = "extract")]
+    pub archive_format: ArchiveFormat,
+
+    /// Extract symbolic and hard links that point outside the extraction directory
+    /// instead of failing; only for trusted archives
+    #[arg(long = "allow-symlinks", requires = "extract")]
+    pub allow_symlinks: bool,
+
     /// Search for repositories using pattern:
     /// - "username/keyword": Search repos owned by username containing keyword
     /// - "username/": List all repos owned by username
@@ -46,18 +329,154 @@ pub struct Cli {
     #[arg(short = 's', long = "search")]
     pub search: Option<String>,
 
+    /// Fetch and show the topics of each search result (one extra request per result)
+    #[arg(long = "with-topics", requires = "search")]
+    pub with_topics: bool,
+
     /// Show information about a specific version, multiple versions can be separated by commas.
     #[arg(short = 'i', long = "info")]
     pub info: Option<String>,
 
-    /// Number of packages to fetch
+    /// Open the discussion of each release shown by --info in the browser
+    #[arg(long = "open-discussion", requires = "info")]
+    pub open_discussion: bool,
+
+    /// Print only the release notes of each release shown by --info
+    #[arg(long = "notes-only", requires = "info", conflicts_with = "assets_only")]
+    pub notes_only: bool,
+
+    /// Print only the asset table of each release shown by --info
+    #[arg(long = "assets-only", requires = "info")]
+    pub assets_only: bool,
+
+    /// Limit the release notes shown by --info to N characters
+    #[arg(long = "truncate-notes", value_name = "N")]
+    pub truncate_notes: Option<usize>,
+
+    /// List the latest releases of the repository
+    #[arg(
+        long = "list",
+        conflicts_with_all = ["download", "since_release", "download_index", "all_releases", "info", "list_assets", "list_branches", "repo_info"]
+    )]
+    pub list: bool,
+
+    /// List the assets of a release with their index, for use with --download-index
+    #[arg(long = "list-assets", value_name = "TAG")]
+    pub list_assets: Option<String>,
+
+    /// List the branches of the repository with their head commit SHA
+    #[arg(long = "list-branches")]
+    pub list_branches: bool,
+
+    /// Show the repository's metadata: description, stars, default branch, visibility,
+    /// size, license, open issues and latest release
+    #[arg(long = "repo-info")]
+    pub repo_info: bool,
+
+    /// For a repository without releases, treat its tags as releases: --list shows
+    /// them and --download fetches the source archive of the selected tag
+    #[arg(long = "tags-as-releases")]
+    pub tags_as_releases: bool,
+
+    /// Number of packages to fetch; with --list or --list-branches, 0 lists all of them
+    /// across all pages
     #[arg(short = 'n', long = "num", default_value_t = crate::constants::DEFAULT_NUM_RELEASES)]
     pub num: usize,
 
+    /// Hide releases whose total asset download count is below this threshold
+    #[arg(long = "min-downloads", alias = "download-count-min", value_name = "N")]
+    pub min_downloads: Option<u64>,
+
+    /// Only list releases whose tag, name or notes match this regex (case-insensitive)
+    #[arg(long = "release-grep", value_name = "PATTERN")]
+    pub release_grep: Option<String>,
+
+    /// Only list releases created by this account (login, case-insensitive)
+    #[arg(long = "author", value_name = "LOGIN")]
+    pub author: Option<String>,
+
+    /// List only the newest of releases created from the same commit
+    #[arg(long = "dedupe-by-commit", alias = "dedupe-releases-by-commit")]
+    pub dedupe_by_commit: bool,
+
+    /// Sort order for the release listing or --list-assets
+    #[arg(long = "sort", value_enum, default_value_t = SortKey::Date)]
+    pub sort: SortKey,
+
+    /// Reverse the sort order of the listing
+    #[arg(long = "reverse")]
+    pub reverse: bool,
+
+    /// Show release dates as their age, e.g. "3 days ago", in table listings
+    #[arg(long = "relative-dates")]
+    pub relative_dates: bool,
+
+    /// Sort the release listing by tag in natural order; same as "--sort natural"
+    #[arg(long = "tag-sort-natural", conflicts_with = "sort")]
+    pub tag_sort_natural: bool,
+
     /// Maximum number of concurrent downloads
     #[arg(short = 'j', long = "concurrency", default_value_t = crate::constants::DEFAULT_CONCURRENCY)]
     pub concurrency: usize,
 
+    /// Timeout in seconds for downloading each asset (no timeout by default)
+    #[arg(long = "timeout", value_name = "SECS")]
+    pub timeout: Option<u64>,
+
+    /// Abort (and retry) a download whose transfer rate stays below this many bytes per
+    /// second, e.g. "50KB", for the --min-rate-window
+    #[arg(long = "min-rate", alias = "min-rate-abort", value_name = "SIZE", value_parser = crate::filters::parse_size)]
+    pub min_rate: Option<u64>,
+
+    /// Seconds over which the transfer rate is averaged for --min-rate
+    #[arg(
+        long = "min-rate-window",
+        value_name = "SECS",
+        default_value_t = crate::constants::DEFAULT_MIN_RATE_WINDOW_SECS,
+        requires = "min_rate"
+    )]
+    pub min_rate_window: u64,
+
+    /// Timeout in seconds for GitHub API metadata requests (no timeout by default)
+    #[arg(long = "api-timeout", value_name = "SECS")]
+    pub api_timeout: Option<u64>,
+
+    /// Maximum number of retries for failed API requests
+    #[arg(long = "max-retries", value_name = "N", default_value_t = crate::constants::retry::MAX_RETRIES)]
+    pub max_retries: u32,
+
+    /// Maximum delay in seconds between two retries
+    #[arg(long = "retry-max-delay", value_name = "SECS", default_value_t = crate::constants::retry::MAX_DELAY_SECS)]
+    pub retry_max_delay: u64,
+
+    /// Give up retrying once this many seconds have passed since the first attempt
+    #[arg(long = "retry-budget", value_name = "SECS")]
+    pub retry_budget: Option<u64>,
+
+    /// Total number of download retries allowed across all assets of the run; once used
+    /// up, downloads fail on their next error
+    #[arg(long = "global-retry-budget", value_name = "N")]
+    pub global_retry_budget: Option<u32>,
+
+    /// Download assets with identical content (by API digest) only once, hard-linking or
+    /// copying them to the other locations after verifying their checksums
+    #[arg(long = "dedupe", alias = "dedupe-assets-by-checksum")]
+    pub dedupe: bool,
+
+    /// Show a single aggregate progress bar with throughput and ETA for all downloads
+    #[arg(long = "total-progress")]
+    pub total_progress: bool,
+
+    /// Print nothing while downloading but a final summary with counts, total size,
+    /// elapsed time and the assets that failed
+    #[arg(long = "summary-only")]
+    pub summary_only: bool,
+
+    /// When downloads fail, print only how many failed and the first few errors
+    /// instead of one line per failed asset
+    #[arg(long = "quiet-errors")]
+    pub quiet_errors: bool,
+
     /// Clone a repository with optional ref (branch/tag/sha1)
     /// Format: <url>[:<ref>] where url can be:
     ///   - https://github.com/owner/repo
@@ -66,12 +485,69 @@ pub struct Cli {
     #[arg(short = 'c', long = "clone", value_name = "URL[:REF]")]
     pub clone: Option<String>,
 
+    /// Clone the repository's submodules as well, using the same token for those on github.com
+    #[arg(long = "recurse-submodules", requires = "clone")]
+    pub recurse_submodules: bool,
+
+    /// Check out the ref given with --clone as a detached HEAD, also when it is a branch
+    #[arg(long = "detach", requires = "clone")]
+    pub detach: bool,
+
     /// Directory for operation (clone destination or download location)
     /// - For clone: defaults to repository name
     /// - For download: defaults to current directory
     #[arg(value_name = "DIRECTORY")]
     pub directory: Option<String>,
 
+    /// Print the release page URL and the download URL of each matching asset instead of
+    /// downloading
+    #[arg(long = "print-urls")]
+    pub print_urls: bool,
+
+    /// Download directory template; "{owner}", "{repo}" and "{tag}" are replaced per release,
+    /// e.g. "downloads/{repo}/{tag}"
+    #[arg(
+        long = "output-dir",
+        value_name = "TEMPLATE",
+        conflicts_with = "directory"
+    )]
+    pub output_dir: Option<String>,
+
+    /// Set the modification time of downloaded files to the asset's upload time
+    #[arg(long = "preserve-timestamps")]
+    pub preserve_timestamps: bool,
+
+    /// Check previously downloaded files against the release's assets (size and
+    /// checksum) instead of downloading; reports files that are missing or corrupt
+    #[arg(long = "verify-only", conflicts_with_all = ["dry_run", "print_urls"])]
+    pub verify_only: bool,
+
+    /// Print "<hex>  <name>" digest lines of the downloaded assets (or, with --verify-only,
+    /// of the existing files) to stdout without verifying them; comma-separated for
+    /// several algorithms, e.g. sha256,blake3, which labels each line "SHA256 (<name>) = <hex>"
+    #[arg(
+        long = "print-checksum",
+        value_name = "ALGO",
+        value_enum,
+        value_delimiter = ','
+    )]
+    pub print_checksum: Vec<HashAlgorithm>,
+
+    /// After downloading, write a SHA256SUMS manifest of the downloaded files to the
+    /// destination directory and sign it with --signing-key into SHA256SUMS.minisig
+    #[arg(long = "sign-output", requires = "signing_key")]
+    pub sign_output: bool,
+
+    /// Minisign secret key for --sign-output; the password of an encrypted key is
+    /// read from GHR_SIGNING_KEY_PASSWORD or prompted for
+    #[arg(long = "signing-key", value_name = "PATH", requires = "sign_output")]
+    pub signing_key: Option<std::path::PathBuf>,
+
+    /// Hash files in parallel and finish with a report of each file's checksum result
+    /// (PASS, FAIL or MISSING-SUM); use with --checksums-url or --verify-only
+    #[arg(long = "parallel-hash-verify")]
+    pub parallel_hash_verify: bool,
+
     /// Preview what will be downloaded or cloned without executing
     #[arg(long = "dry-run")]
     pub dry_run: bool,
@@ -80,18 +556,463 @@ pub struct Cli {
     #[arg(long = "format", value_enum, default_value_t = OutputFormat::Table)]
     pub format: OutputFormat,
 
+    /// Comma-separated columns of the release listing in table and CSV output, in order
+    /// (no, tag, name, published, assets, size, type)
+    #[arg(
+        long = "columns",
+        value_enum,
+        value_delimiter = ',',
+        value_name = "COLUMNS"
+    )]
+    pub columns: Option<Vec<ReleaseColumn>>,
+
+    /// List releases as one terse line each ("TAG  (type)  published  assets:N")
+    /// instead of the table
+    #[arg(long = "oneline")]
+    pub oneline: bool,
+
+    /// With --list, print only the number of listed releases (after filters and
+    /// --num) to stdout; use --num 0 to count all releases
+    #[arg(long = "count-only", requires = "list", conflicts_with = "oneline")]
+    pub count_only: bool,
+
+    /// With --format csv, print sizes human-readable (e.g. "3.2 MiB") instead of in bytes
+    #[arg(long = "pretty-size")]
+    pub pretty_size: bool,
+
+    /// With --format json, print JSON on a single line instead of pretty-printed
+    #[arg(long = "json-compact")]
+    pub json_compact: bool,
+
+    /// With --format json, output only a stable subset of release fields
+    /// (tag, name, published_at, prerelease, draft, asset_count, total_size)
+    #[arg(long = "compact", alias = "list-releases-json-compact")]
+    pub compact: bool,
+
+    /// Service hosting the releases; with "gitlab", --repo is the project path and
+    /// --api-url defaults to https://gitlab.com/api/v4
+    #[arg(long = "provider", value_enum, default_value_t = Provider::Github)]
+    pub provider: Provider,
+
     /// GitHub API base URL (for GitHub Enterprise)
     #[arg(long = "api-url", default_value = crate::constants::GITHUB_API_BASE)]
     pub api_url: String,
 
+    /// When the repository doesn't exist, search the owner's repositories for
+    /// similar names and suggest them (one extra API request)
+    #[arg(long = "suggest")]
+    pub suggest: bool,
+
+    /// Maximum number of redirects to follow when downloading
+    #[arg(long = "max-redirects", value_name = "N", default_value_t = crate::constants::DEFAULT_MAX_REDIRECTS)]
+    pub max_redirects: usize,
+
+    /// Minimum TLS version to accept; connections negotiating an older version fail
+    #[arg(
+        long = "min-tls",
+        value_enum,
+        value_name = "VERSION",
+        alias = "strict-tls-version"
+    )]
+    pub min_tls: Option<TlsVersion>,
+
+    /// Connect over IPv4 only (workaround for networks with broken IPv6)
+    #[arg(long = "ipv4-only", alias = "connect-only-ipv4")]
+    pub ipv4_only: bool,
+
+    /// Don't ask for compressed API responses, e.g. to inspect the traffic when debugging
+    #[arg(long = "no-compression")]
+    pub no_compression: bool,
+
+    /// Fail instead of continuing when the repository has been renamed or transferred
+    #[arg(long = "no-follow-rename")]
+    pub no_follow_rename: bool,
+
+    /// Never access the network; serve API responses from the cache only, regardless of age
+    #[arg(long = "offline", conflicts_with = "no_cache")]
+    pub offline: bool,
+
     /// Enable response caching (24 hour TTL)
-    #[arg(long = "cache")]
+    #[arg(long = "cache", conflicts_with = "no_cache")]
     pub cache: bool,
 
+    /// With caching enabled, also cache not-found results for repositories and releases
+    #[arg(long = "cache-negative")]
+    pub cache_negative: bool,
+
+    /// Time in seconds to cache not-found results with --cache-negative
+    #[arg(long = "cache-negative-ttl", value_name = "SECS", default_value_t = crate::constants::DEFAULT_NEGATIVE_CACHE_TTL_SECS)]
+    pub cache_negative_ttl: u64,
+
+    /// Disable response caching for this run, even if enabled in the config file
+    #[arg(long = "no-cache")]
+    pub no_cache: bool,
+
     /// Download a specific file from a repository which requires a token.
     #[arg(short = 'g', long = "get-file")]
     pub get_file: Option<String>,
 
+    /// Read defaults from this configuration file instead of ~/.config/ghr/config.toml
+    #[arg(long = "config", value_name = "PATH")]
+    pub config: Option<std::path::PathBuf>,
+
     #[arg(short = 'v', long = "verbose", action = ArgAction::Count)]
     pub verbose: u8,
+
+    /// Settings given on the command line, filled in by `Cli::parse_args`
+    #[arg(skip)]
+    pub explicit: ExplicitArgs,
+}
+
+/// Which settings with a default value were given on the command line
+///
+/// The configuration file only fills in the others, even where a flag repeats
+/// the default.
+#[derive(Clone, Debug, Default)]
+pub struct ExplicitArgs {
+    pub api_url: bool,
+    pub concurrency: bool,
+}
+
+impl Cli {
+    /// Parse the command line, recording which settings were given on it
+    pub fn parse_args() -> Self {
+        Self::parse_args_from(std::env::args_os())
+    }
+
+    /// `parse_args` for the given arguments, exiting with the usage on errors
+    pub fn parse_args_from<I, T>(args: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        let matches = Self::command().get_matches_from(args);
+        let mut cli = Self::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+        let given = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+        cli.explicit = ExplicitArgs {
+            api_url: given("api_url"),
+            concurrency: given("concurrency"),
+        };
+        cli
+    }
+}
+
+impl Cli {
+    /// Sort order for the release listing, taking `--tag-sort-natural` into account
+    pub fn sort_key(&self) -> SortKey {
+        if self.tag_sort_natural {
+            SortKey::Natural
+        } else {
+            self.sort
+        }
+    }
+
+    /// Whether an operation on the repository was selected; listing releases
+    /// has to be asked for with `--list`
+    pub fn has_repo_action(&self) -> bool {
+        self.list
+            || self.download.is_some()
+            || self.since_release.is_some()
+            || self.download_index.is_some()
+            || self.all_releases
+            || self.git_ref.is_some()
+            || self.info.is_some()
+            || self.list_assets.is_some()
+            || self.list_branches
+            || self.repo_info
+    }
+
+    /// Fail with the usage when a repository is given without anything to do with it
+    ///
+    /// A mistyped mode flag would otherwise silently fall back to a listing.
+    pub fn check_mode(&self) -> Result<()> {
+        let other_mode = self.search.is_some() || self.clone.is_some() || self.get_file.is_some();
+        if other_mode || self.has_repo_action() {
+            return Ok(());
+        }
+        Err(GhrError::MissingArgument(format!(
+            "no operation selected. Use --list to list releases, or --download, --info, \
+             --list-assets, --list-branches, --repo-info, --since-release, --download-index, \
+             --all-releases or --ref\n\n{}",
+            Cli::command().render_usage()
+        )))
+    }
+
+    /// Columns of the release listing
+    pub fn release_columns(&self) -> &[ReleaseColumn] {
+        self.columns.as_deref().unwrap_or(ReleaseColumn::DEFAULT)
+    }
+
+    /// Section of each release printed by --info
+    pub fn info_section(&self) -> InfoSection {
+        if self.notes_only {
+            InfoSection::Notes
+        } else if self.assets_only {
+            InfoSection::Assets
+        } else {
+            InfoSection::All
+        }
+    }
+
+    /// Name of the selected mode, as given in the `--summary-json` report
+    pub fn mode_name(&self) -> &'static str {
+        if self.all_releases {
+            "all-releases"
+        } else if self.since_release.is_some() {
+            "since-release"
+        } else if self.download_index.is_some() {
+            "download-index"
+        } else if self.git_ref.is_some() {
+            "ref"
+        } else if self.download.is_some() {
+            "download"
+        } else if self.info.is_some() {
+            "info"
+        } else if self.list_assets.is_some() {
+            "list-assets"
+        } else if self.list_branches {
+            "list-branches"
+        } else if self.repo_info {
+            "repo-info"
+        } else if self.search.is_some() {
+            "search"
+        } else if self.clone.is_some() {
+            "clone"
+        } else if self.get_file.is_some() {
+            "get-file"
+        } else {
+            "list"
+        }
+    }
+
+    /// Minimum transfer rate of downloads, from `--min-rate` and `--min-rate-window`
+    pub fn min_rate(&self) -> Option<MinRate> {
+        self.min_rate.map(|bytes_per_sec| MinRate {
+            bytes_per_sec,
+            window: Duration::from_secs(self.min_rate_window),
+        })
+    }
+}
+
+/// Combine `--owner` and `--repo` into the "owner/repo" form
+///
+/// Without `--owner`, `--repo` is returned as given.
+pub fn combine_owner_repo(owner: Option<&str>, repo: Option<&str>) -> Result<Option<String>> {
+    match (owner, repo) {
+        (None, repo) => Ok(repo.map(String::from)),
+        (Some(_), Some(repo)) if repo.contains('/') => Err(GhrError::Generic(format!(
+            "--repo '{}' must be a repository name without owner when --owner is given",
+            repo
+        ))),
+        (Some(owner), Some(repo)) => Ok(Some(format!("{}/{}", owner, repo))),
+        (Some(_), None) => Err(GhrError::MissingArgument(
+            "--repo is required with --owner".to_string(),
+        )),
+    }
+}
+
+/// Split a `host/owner/repo` repository argument into its host and "owner/repo"
+///
+/// The first segment counts as a host when it looks like one (contains a dot
+/// or a port), as in `github.mycorp.com/owner/repo`.
+pub fn split_repo_host(repo: &str) -> Option<(&str, &str)> {
+    let (host, rest) = repo.split_once('/')?;
+    let is_host = host.contains('.') || host.contains(':');
+    (is_host && rest.split('/').count() == 2).then_some((host, rest))
+}
+
+/// API base URL of a GitHub (or GitLab) instance given by its host name
+pub fn api_url_for_host(provider: Provider, host: &str) -> String {
+    match (provider, host) {
+        (Provider::Github, "github.com" | "api.github.com") => {
+            crate::constants::GITHUB_API_BASE.to_string()
+        }
+        // GitHub Enterprise Server serves the REST API under /api/v3
+        (Provider::Github, host) => format!("https://{}/api/v3", host),
+        (Provider::Gitlab, host) => format!("https://{}/api/v4", host),
+    }
+}
+
+/// API base URL from the environment: `GITHUB_API_URL`, or else derived from `GH_HOST`
+///
+/// Takes the variable lookup as a parameter so it can be tested without
+/// touching the process environment.
+pub fn api_url_from_env(var: impl Fn(&str) -> Option<String>) -> Option<String> {
+    let non_empty = |name| var(name).filter(|value| !value.trim().is_empty());
+    if let Some(url) = non_empty("GITHUB_API_URL") {
+        return Some(url.trim().trim_end_matches('/').to_string());
+    }
+    non_empty("GH_HOST").map(|host| api_url_for_host(Provider::Github, host.trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_and_no_cache_conflict() {
+        let result = Cli::try_parse_from(["ghr", "-r", "owner/repo", "--cache", "--no-cache"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_combine_owner_repo() {
+        assert_eq!(
+            combine_owner_repo(None, Some("owner/repo")).unwrap(),
+            Some("owner/repo".to_string())
+        );
+        assert_eq!(
+            combine_owner_repo(Some("owner"), Some("repo")).unwrap(),
+            Some("owner/repo".to_string())
+        );
+        assert_eq!(combine_owner_repo(None, None).unwrap(), None);
+        assert!(combine_owner_repo(Some("owner"), Some("other/repo")).is_err());
+        assert!(combine_owner_repo(Some("owner"), None).is_err());
+    }
+
+    #[test]
+    fn test_mode_required() {
+        let parse = |args: &[&str]| Cli::try_parse_from(["ghr"].iter().chain(args)).unwrap();
+
+        let err = parse(&["-r", "owner/repo"]).check_mode().unwrap_err();
+        assert!(matches!(err, GhrError::MissingArgument(_)));
+        assert!(err.to_string().contains("Usage: "));
+
+        assert!(parse(&["-r", "owner/repo", "--list"]).check_mode().is_ok());
+        assert!(parse(&["-r", "owner/repo", "-d", "latest"])
+            .check_mode()
+            .is_ok());
+        assert!(parse(&["-r", "owner/repo", "-i", "v1.0.0"])
+            .check_mode()
+            .is_ok());
+        assert!(parse(&["-s", "owner/"]).check_mode().is_ok());
+        assert!(
+            Cli::try_parse_from(["ghr", "-r", "owner/repo", "--list", "-d", "latest"]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_info_sections() {
+        let parse =
+            |args: &[&str]| Cli::try_parse_from(["ghr", "-r", "owner/repo"].iter().chain(args));
+
+        assert_eq!(
+            parse(&["-i", "v1"]).unwrap().info_section(),
+            InfoSection::All
+        );
+        assert_eq!(
+            parse(&["-i", "v1", "--notes-only"]).unwrap().info_section(),
+            InfoSection::Notes
+        );
+        assert_eq!(
+            parse(&["-i", "v1", "--assets-only"])
+                .unwrap()
+                .info_section(),
+            InfoSection::Assets
+        );
+        assert!(parse(&["-i", "v1", "--notes-only", "--assets-only"]).is_err());
+        assert!(parse(&["--notes-only"]).is_err());
+    }
+
+    #[test]
+    fn test_columns() {
+        let cli = Cli::try_parse_from(["ghr", "-r", "owner/repo", "--list"]).unwrap();
+        assert_eq!(cli.release_columns(), ReleaseColumn::DEFAULT);
+
+        let cli = Cli::try_parse_from([
+            "ghr",
+            "-r",
+            "owner/repo",
+            "--list",
+            "--columns",
+            "type,tag,size",
+        ])
+        .unwrap();
+        assert_eq!(
+            cli.release_columns(),
+            [ReleaseColumn::Type, ReleaseColumn::Tag, ReleaseColumn::Size]
+        );
+
+        assert!(
+            Cli::try_parse_from(["ghr", "-r", "owner/repo", "--list", "--columns", "tag,sha"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_owner_requires_repo() {
+        assert!(Cli::try_parse_from(["ghr", "--owner", "owner"]).is_err());
+
+        let cli = Cli::try_parse_from(["ghr", "--owner", "owner", "-r", "repo"]).unwrap();
+        assert_eq!(cli.owner.as_deref(), Some("owner"));
+        assert_eq!(cli.repo.as_deref(), Some("repo"));
+    }
+
+    #[test]
+    fn test_split_repo_host() {
+        assert_eq!(
+            split_repo_host("github.mycorp.com/owner/repo"),
+            Some(("github.mycorp.com", "owner/repo"))
+        );
+        assert_eq!(
+            split_repo_host("ghe.local:8443/owner/repo"),
+            Some(("ghe.local:8443", "owner/repo"))
+        );
+        assert_eq!(split_repo_host("owner/repo"), None);
+        assert_eq!(split_repo_host("my.org/repo"), None);
+        assert_eq!(split_repo_host("owner/repo/extra"), None);
+    }
+
+    #[test]
+    fn test_api_url_from_env() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |name: &str| {
+                vars.iter()
+                    .find(|(key, _)| *key == name)
+                    .map(|(_, value)| value.to_string())
+            }
+        };
+
+        assert_eq!(
+            api_url_from_env(env(&[(
+                "GITHUB_API_URL",
+                "https://ghe.example.com/api/v3/"
+            )])),
+            Some("https://ghe.example.com/api/v3".to_string())
+        );
+        assert_eq!(
+            api_url_from_env(env(&[("GH_HOST", "ghe.example.com")])),
+            Some("https://ghe.example.com/api/v3".to_string())
+        );
+        assert_eq!(
+            api_url_from_env(env(&[("GH_HOST", "github.com")])),
+            Some(crate::constants::GITHUB_API_BASE.to_string())
+        );
+        // The full URL wins over the host
+        assert_eq!(
+            api_url_from_env(env(&[
+                ("GH_HOST", "other.example.com"),
+                ("GITHUB_API_URL", "https://ghe.example.com/api/v3"),
+            ])),
+            Some("https://ghe.example.com/api/v3".to_string())
+        );
+        assert_eq!(api_url_from_env(env(&[("GH_HOST", "")])), None);
+        assert_eq!(api_url_from_env(env(&[])), None);
+    }
+
+    #[test]
+    fn test_api_url_for_host() {
+        assert_eq!(
+            api_url_for_host(Provider::Github, "github.com"),
+            "https://api.github.com"
+        );
+        assert_eq!(
+            api_url_for_host(Provider::Github, "github.mycorp.com"),
+            "https://github.mycorp.com/api/v3"
+        );
+        assert_eq!(
+            api_url_for_host(Provider::Gitlab, "gitlab.mycorp.com"),
+            "https://gitlab.mycorp.com/api/v4"
+        );
+    }
 }
@@ -0,0 +1,187 @@
+use crate::cli::Cli;
+use crate::errors::{GhrError, Result};
+use jlogger_tracing::jdebug;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+/// Defaults loaded from the user configuration file
+///
+/// Every setting is optional; command-line flags always take precedence.
+///
+/// ```toml
+/// cache = true
+/// api_url = "https://ghe.example.com/api/v3"
+/// token_file = "/home/me/.github_token"
+/// concurrency = 8
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Enable response caching
+    pub cache: Option<bool>,
+    /// GitHub API base URL
+    pub api_url: Option<String>,
+    /// File containing the GitHub API token
+    pub token_file: Option<String>,
+    /// Maximum number of concurrent downloads
+    pub concurrency: Option<usize>,
+}
+
+impl Config {
+    /// Default configuration file location (e.g. ~/.config/ghr/config.toml)
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("ghr").join("config.toml"))
+    }
+
+    /// Load the configuration given with `--config`, or else the one at the default
+    /// location, if present
+    pub fn load_default_or(path: Option<&Path>) -> Result<Self> {
+        Self::load_either(path, Self::default_path())
+    }
+
+    fn load_either(path: Option<&Path>, default_path: Option<PathBuf>) -> Result<Self> {
+        match (path, default_path) {
+            // A file named explicitly must exist
+            (Some(path), _) if !path.exists() => Err(GhrError::Config(format!(
+                "Config file '{}' not found",
+                path.display()
+            ))),
+            (Some(path), _) => Self::load(path),
+            (None, Some(path)) if path.exists() => Self::load(&path),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    /// Load the configuration from a file
+    pub fn load(path: &Path) -> Result<Self> {
+        jdebug!("Loading config from {}", path.display());
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| GhrError::Config(format!("Failed to read '{}': {}", path.display(), e)))?;
+        Self::parse(&content)
+            .map_err(|e| GhrError::Config(format!("Invalid config '{}': {}", path.display(), e)))
+    }
+
+    /// Parse configuration file content
+    pub fn parse(content: &str) -> Result<Self> {
+        toml::from_str(content).map_err(|e| GhrError::Config(e.to_string()))
+    }
+
+    /// Fill CLI settings that were not given on the command line from the configuration
+    pub fn apply(&self, cli: &mut Cli) {
+        cli.cache = cache_enabled(cli.cache, cli.no_cache, self);
+
+        if !cli.explicit.api_url {
+            if let Some(api_url) = &self.api_url {
+                cli.api_url = api_url.clone();
+            }
+        }
+
+        if cli.token.is_none() && cli.token_file.is_none() {
+            cli.token_file = self.token_file.clone();
+        }
+
+        if !cli.explicit.concurrency {
+            if let Some(concurrency) = self.concurrency {
+                cli.concurrency = concurrency;
+            }
+        }
+    }
+}
+
+/// Decide whether caching is enabled for this run
+///
+/// `--no-cache` always wins, then `--cache`, then the configuration file.
+pub fn cache_enabled(cli_cache: bool, cli_no_cache: bool, config: &Config) -> bool {
+    if cli_no_cache {
+        return false;
+    }
+    cli_cache || config.cache.unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config() {
+        let config = Config::parse(
+            "cache = true\napi_url = \"https://ghe.example.com/api/v3\"\nconcurrency = 8\n",
+        )
+        .unwrap();
+        assert_eq!(config.cache, Some(true));
+        assert_eq!(
+            config.api_url.as_deref(),
+            Some("https://ghe.example.com/api/v3")
+        );
+        assert_eq!(config.concurrency, Some(8));
+        assert_eq!(config.token_file, None);
+    }
+
+    #[test]
+    fn test_parse_config_unknown_key() {
+        assert!(Config::parse("caching = true\n").is_err());
+    }
+
+    #[test]
+    fn test_explicit_config_replaces_default() {
+        let dir = tempfile::tempdir().unwrap();
+        let default_path = dir.path().join("config.toml");
+        let project_path = dir.path().join("ghr.toml");
+        std::fs::write(&default_path, "concurrency = 2\ncache = true\n").unwrap();
+        std::fs::write(&project_path, "concurrency = 8\n").unwrap();
+
+        let config = Config::load_either(Some(&project_path), Some(default_path.clone())).unwrap();
+        assert_eq!(config.concurrency, Some(8));
+        // Nothing is taken from the default file
+        assert_eq!(config.cache, None);
+
+        let config = Config::load_either(None, Some(default_path.clone())).unwrap();
+        assert_eq!(config.concurrency, Some(2));
+
+        let missing = dir.path().join("missing.toml");
+        assert!(matches!(
+            Config::load_either(Some(&missing), Some(default_path)),
+            Err(GhrError::Config(_))
+        ));
+    }
+
+    #[test]
+    fn test_no_cache_wins_over_config() {
+        let config = Config::parse("cache = true\n").unwrap();
+        assert!(cache_enabled(false, false, &config));
+        assert!(!cache_enabled(false, true, &config));
+    }
+
+    #[test]
+    fn test_flags_win_over_config() {
+        let config =
+            Config::parse("api_url = \"https://ghe.example.com/api/v3\"\nconcurrency = 8\n")
+                .unwrap();
+
+        let mut cli = Cli::parse_args_from(["ghr", "-r", "owner/repo"]);
+        config.apply(&mut cli);
+        assert_eq!(cli.api_url, "https://ghe.example.com/api/v3");
+        assert_eq!(cli.concurrency, 8);
+
+        // Flags repeating the default are still given
+        let mut cli = Cli::parse_args_from([
+            "ghr",
+            "-r",
+            "owner/repo",
+            "--api-url",
+            crate::constants::GITHUB_API_BASE,
+            "-j",
+            &crate::constants::DEFAULT_CONCURRENCY.to_string(),
+        ]);
+        config.apply(&mut cli);
+        assert_eq!(cli.api_url, crate::constants::GITHUB_API_BASE);
+        assert_eq!(cli.concurrency, crate::constants::DEFAULT_CONCURRENCY);
+    }
+
+    #[test]
+    fn test_cache_flag_without_config() {
+        let config = Config::default();
+        assert!(!cache_enabled(false, false, &config));
+        assert!(cache_enabled(true, false, &config));
+    }
+}
@@ -1,6 +1,9 @@
 /// GitHub API base URL
 pub const GITHUB_API_BASE: &str = "https://api.github.com";
 
+/// GitLab API base URL, used with `--provider gitlab`
+pub const GITLAB_API_BASE: &str = "https://gitlab.com/api/v4";
+
 /// GitHub API version
 pub const GITHUB_API_VERSION: &str = "2022-11-28";
 
@@ -10,9 +13,54 @@ pub const USER_AGENT: &str = concat!("ghr/", env!("CARGO_PKG_VERSION"));
 /// Default concurrency for parallel downloads
 pub const DEFAULT_CONCURRENCY: usize = 5;
 
+/// Upper bound for downloads running at the same time across all repositories
+/// of a --repos-file batch
+pub const MAX_PARALLEL_DOWNLOADS: usize = 32;
+
+/// Default maximum number of redirects followed by downloads
+pub const DEFAULT_MAX_REDIRECTS: usize = 10;
+
+/// Default window in seconds over which the --min-rate transfer rate is averaged
+pub const DEFAULT_MIN_RATE_WINDOW_SECS: u64 = 10;
+
+/// Default time in seconds to cache not-found results with --cache-negative
+pub const DEFAULT_NEGATIVE_CACHE_TTL_SECS: u64 = 300;
+
 /// Default number of releases to fetch
 pub const DEFAULT_NUM_RELEASES: usize = 10;
 
+/// Default upper bound for the number of releases mirrored by --all-releases
+pub const DEFAULT_MAX_RELEASES: usize = 100;
+
+/// Number of releases requested per page when paginating (the API maximum)
+pub const RELEASES_PER_PAGE: usize = 100;
+
+/// Number of branches requested per page when paginating (the API maximum)
+pub const BRANCHES_PER_PAGE: usize = 100;
+
+/// Number of failed downloads listed with --quiet-errors
+pub const QUIET_ERRORS_EXAMPLES: usize = 3;
+
+/// Repositories of the owner compared against a missing one with --suggest
+pub const SUGGEST_CANDIDATES: usize = 100;
+
+/// Most similarly named repositories suggested for a missing one
+pub const MAX_SUGGESTIONS: usize = 3;
+
+/// Jaro-Winkler similarity from which a repository name counts as a likely typo
+pub const SUGGEST_MIN_SIMILARITY: f64 = 0.8;
+
+/// Checksum manifest written with --sign-output, signed into `<name>.minisig`
+pub const SIGNED_MANIFEST_NAME: &str = "SHA256SUMS";
+
+/// Environment variable with the password of an encrypted --signing-key, read
+/// instead of prompting for it
+pub const SIGNING_KEY_PASSWORD_ENV: &str = "GHR_SIGNING_KEY_PASSWORD";
+
+/// Number of assets requested per page of a release's asset list (the API maximum);
+/// a release object embedding this many assets may have more
+pub const ASSETS_PER_PAGE: usize = 100;
+
 /// API endpoints
 pub mod endpoints {
     use super::GITHUB_API_BASE;
@@ -28,6 +76,20 @@ pub mod endpoints {
         format!("{}/repos/{}/{}/releases", base_url, owner, repo)
     }
 
+    /// Get one page of releases, starting at page 1
+    pub fn releases_page_with_base(
+        base_url: &str,
+        owner: &str,
+        repo: &str,
+        per_page: usize,
+        page: usize,
+    ) -> String {
+        format!(
+            "{}/repos/{}/{}/releases?per_page={}&page={}",
+            base_url, owner, repo, per_page, page
+        )
+    }
+
     /// Get a specific release by tag
     #[allow(dead_code)]
     pub fn release_by_tag(owner: &str, repo: &str, tag: &str) -> String {
@@ -64,6 +126,20 @@ pub mod endpoints {
         format!("{}/repos/{}/{}/branches/{}", base_url, owner, repo, branch)
     }
 
+    /// List one page of branches of a repository, starting at page 1
+    pub fn branches_page_with_base(
+        base_url: &str,
+        owner: &str,
+        repo: &str,
+        per_page: usize,
+        page: usize,
+    ) -> String {
+        format!(
+            "{}/repos/{}/{}/branches?per_page={}&page={}",
+            base_url, owner, repo, per_page, page
+        )
+    }
+
     /// Get tag information
     #[allow(dead_code)]
     pub fn tag(owner: &str, repo: &str, tag: &str) -> String {
@@ -118,6 +194,70 @@ pub mod endpoints {
             base_url, owner, repo, per_page
         )
     }
+
+    /// Get the topics of a repository
+    pub fn topics_with_base(base_url: &str, owner: &str, repo: &str) -> String {
+        format!("{}/repos/{}/{}/topics", base_url, owner, repo)
+    }
+
+    /// Get the source tarball of a ref (branch, tag or commit)
+    pub fn tarball_with_base(base_url: &str, owner: &str, repo: &str, git_ref: &str) -> String {
+        format!(
+            "{}/repos/{}/{}/tarball/{}",
+            base_url,
+            owner,
+            repo,
+            urlencoding::encode(git_ref)
+        )
+    }
+}
+
+/// GitLab API endpoints
+pub mod gitlab_endpoints {
+    /// Project ID for the API: the URL-encoded "namespace/project" path
+    fn project_id(repo: &str) -> String {
+        urlencoding::encode(repo).into_owned()
+    }
+
+    /// Get releases of a project
+    pub fn releases_with_base(base_url: &str, repo: &str) -> String {
+        format!("{}/projects/{}/releases", base_url, project_id(repo))
+    }
+
+    /// Get one page of releases of a project, starting at page 1
+    pub fn releases_page_with_base(
+        base_url: &str,
+        repo: &str,
+        per_page: usize,
+        page: usize,
+    ) -> String {
+        format!(
+            "{}?per_page={}&page={}",
+            releases_with_base(base_url, repo),
+            per_page,
+            page
+        )
+    }
+
+    /// Get a specific release of a project by tag
+    pub fn release_by_tag_with_base(base_url: &str, repo: &str, tag: &str) -> String {
+        format!(
+            "{}/projects/{}/releases/{}",
+            base_url,
+            project_id(repo),
+            urlencoding::encode(tag)
+        )
+    }
+
+    /// Get the source tarball of a ref (branch, tag or commit)
+    pub fn archive_with_base(base_url: &str, repo: &str, git_ref: &str) -> String {
+        format!(
+            "{}/projects/{}/repository/archive.tar.gz?sha={}",
+            base_url,
+            project_id(repo),
+            urlencoding::encode(git_ref)
+        )
+    }
 }
 
 /// HTTP headers
@@ -125,6 +265,9 @@ pub mod headers {
     /// Accept header for GitHub API v3
     pub const ACCEPT_API_V3: &str = "application/vnd.github.v3+json";
 
+    /// Token header for the GitLab API, lowercase for `HeaderName::from_static`
+    pub const GITLAB_TOKEN: &str = "private-token";
+
     /// Accept header for downloading assets
     pub const ACCEPT_OCTET_STREAM: &str = "application/octet-stream";
 }
@@ -136,4 +279,7 @@ pub mod retry {
 
     /// Base delay in seconds for exponential backoff
     pub const BASE_DELAY_SECS: u64 = 2;
+
+    /// Maximum delay in seconds between two attempts
+    pub const MAX_DELAY_SECS: u64 = 30;
 }
@@ -0,0 +1,1393 @@
+use crate::checksum::{self, DigestSource};
+use crate::constants;
+use crate::errors::{GhrError, Result};
+use crate::http::{HostToken, RetryPolicy};
+use crate::models::Asset;
+use crate::util::humanize_bytes;
+use filetime::FileTime;
+use futures::stream::{self, StreamExt};
+use jlogger_tracing::{jdebug, jinfo, jwarn};
+use reqwest::header::ACCEPT;
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::fs;
+use tokio::sync::Semaphore;
+use tokio::time::{sleep, Instant};
+use tracing::Instrument;
+
+/// Asset scheduled for download
+#[derive(Debug, Clone)]
+pub struct DownloadTask {
+    pub name: String,
+    pub url: String,
+    pub output_path: PathBuf,
+    pub size: u64,
+    /// Content digest reported by the API, used to detect identical assets
+    pub digest: Option<String>,
+    /// Modification time to give the downloaded file (with --preserve-timestamps)
+    pub modified: Option<SystemTime>,
+}
+
+/// Set the modification time of a downloaded file
+pub fn set_modified(path: &Path, modified: SystemTime) -> Result<()> {
+    filetime::set_file_mtime(path, FileTime::from_system_time(modified)).map_err(GhrError::Io)
+}
+
+/// File name for the source archive of `repo_name` at `git_ref`, e.g. "repo-v1.0.0.tar.gz"
+pub fn source_archive_name(repo_name: &str, git_ref: &str) -> Result<String> {
+    Ok(format!(
+        "{}-{}.tar.gz",
+        sanitize_component(repo_name)?,
+        sanitize_component(git_ref)?
+    ))
+}
+
+/// Render an `--output-dir` template, substituting `{owner}`, `{repo}` and `{tag}`
+///
+/// Substituted values are sanitized so that they always stay a single path
+/// component, e.g. a tag of "../../etc" cannot escape the template's directory.
+pub fn render_output_dir(template: &str, owner: &str, repo: &str, tag: &str) -> Result<PathBuf> {
+    let rendered = template
+        .replace("{owner}", &sanitize_component(owner)?)
+        .replace("{repo}", &sanitize_component(repo)?)
+        .replace("{tag}", &sanitize_component(tag)?);
+    Ok(PathBuf::from(rendered))
+}
+
+/// Make a value safe to use as a single path component
+///
+/// Path separators are replaced with '_'; values that would still refer to
+/// the current or parent directory are rejected.
+pub fn sanitize_component(value: &str) -> Result<String> {
+    let sanitized: String = value
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | '\0' => '_',
+            c => c,
+        })
+        .collect();
+
+    if sanitized.is_empty() || sanitized == "." || sanitized == ".." {
+        return Err(GhrError::Generic(format!(
+            "'{}' cannot be used as a directory name",
+            value
+        )));
+    }
+    Ok(sanitized)
+}
+
+/// Asset whose content is identical to another task's and is linked instead
+#[derive(Debug, Clone)]
+pub struct DuplicateTask {
+    pub task: DownloadTask,
+    /// Output path of the task that downloads the identical content
+    pub source: PathBuf,
+}
+
+/// Split off tasks whose digest matches an earlier task
+///
+/// Returns the tasks to download and the duplicates to link from their
+/// downloaded counterpart. Tasks without a digest are always downloaded.
+pub fn split_duplicates(tasks: Vec<DownloadTask>) -> (Vec<DownloadTask>, Vec<DuplicateTask>) {
+    let mut seen: HashMap<String, PathBuf> = HashMap::new();
+    let mut unique = Vec::new();
+    let mut duplicates = Vec::new();
+
+    for task in tasks {
+        match task.digest.as_ref().and_then(|d| seen.get(d)) {
+            Some(source) => duplicates.push(DuplicateTask {
+                source: source.clone(),
+                task,
+            }),
+            None => {
+                if let Some(digest) = &task.digest {
+                    seen.insert(digest.clone(), task.output_path.clone());
+                }
+                unique.push(task);
+            }
+        }
+    }
+
+    (unique, duplicates)
+}
+
+impl DownloadTask {
+    /// SHA-256 digest the asset should have: the published checksum if one is
+    /// listed, otherwise the API digest
+    pub fn expected_sha256(&self, checksums: Option<&HashMap<String, String>>) -> Option<String> {
+        self.expected_sha256_with_source(checksums)
+            .map(|(digest, _)| digest)
+    }
+
+    /// Like [`DownloadTask::expected_sha256`], also telling where the digest came from
+    pub fn expected_sha256_with_source(
+        &self,
+        checksums: Option<&HashMap<String, String>>,
+    ) -> Option<(String, DigestSource)> {
+        if let Some(sum) = checksums.and_then(|sums| sums.get(&self.name)) {
+            return Some((sum.clone(), DigestSource::Manifest));
+        }
+        self.digest
+            .as_deref()
+            .and_then(|digest| digest.strip_prefix("sha256:"))
+            .map(|digest| (digest.to_string(), DigestSource::Api))
+    }
+}
+
+/// Link each duplicate to its downloaded counterpart in `downloaded`
+///
+/// The downloaded file is first verified against the duplicate's own expected
+/// digest, so a link never skips the verification a download would get. A
+/// duplicate whose counterpart does not match is downloaded instead.
+pub async fn link_duplicates(
+    client: Arc<Client>,
+    duplicates: Vec<DuplicateTask>,
+    downloaded: &[Result<DownloadTask>],
+    options: &DownloadOptions,
+) -> Vec<Result<DownloadTask>> {
+    let hash_limit = Semaphore::new(checksum::hash_parallelism());
+    let mut results = Vec::new();
+    let mut fallback = Vec::new();
+
+    for duplicate in duplicates {
+        let source_ok = downloaded
+            .iter()
+            .any(|result| matches!(result, Ok(task) if task.output_path == duplicate.source));
+        if !source_ok {
+            results.push(Err(GhrError::Generic(format!(
+                "'{}' not linked: download of identical '{}' failed",
+                duplicate.task.output_path.display(),
+                duplicate.source.display()
+            ))
+            .for_asset(&duplicate.task.name)));
+            continue;
+        }
+
+        if let Some(expected) = duplicate.task.expected_sha256(options.checksums.as_deref()) {
+            let verified = checksum::verify_file_blocking(
+                duplicate.task.name.clone(),
+                duplicate.source.clone(),
+                expected,
+                &hash_limit,
+            )
+            .await;
+            match verified {
+                Ok(()) => {}
+                Err(e @ GhrError::ChecksumMismatch { .. }) => {
+                    jwarn!(
+                        "'{}' does not match identical '{}' ({}); downloading it instead",
+                        duplicate.task.output_path.display(),
+                        duplicate.source.display(),
+                        e
+                    );
+                    fallback.push(duplicate.task);
+                    continue;
+                }
+                Err(e) => {
+                    results.push(Err(e.for_asset(&duplicate.task.name)));
+                    continue;
+                }
+            }
+        }
+
+        match link_or_copy(&duplicate.source, &duplicate.task.output_path) {
+            Ok(()) => {
+                jinfo!(
+                    "Linked '{}' to identical '{}'",
+                    duplicate.task.output_path.display(),
+                    duplicate.source.display()
+                );
+                results.push(Ok(duplicate.task));
+            }
+            Err(e) => results.push(Err(e.for_asset(&duplicate.task.name))),
+        }
+    }
+
+    if !fallback.is_empty() {
+        results.extend(download_assets(client, fallback, options).await);
+    }
+    results
+}
+
+/// Hard-link `source` to `dest`, copying when linking fails (e.g. across filesystems)
+pub fn link_or_copy(source: &Path, dest: &Path) -> Result<()> {
+    if dest.exists() {
+        std::fs::remove_file(dest)?;
+    }
+    if let Err(e) = std::fs::hard_link(source, dest) {
+        jdebug!(
+            "Hard link {} -> {} failed ({}), copying instead",
+            dest.display(),
+            source.display(),
+            e
+        );
+        std::fs::copy(source, dest)?;
+    }
+    Ok(())
+}
+
+/// Metadata of a downloaded asset, written next to it with --assets-json
+#[derive(Debug, Serialize)]
+pub struct AssetRecord<'a> {
+    #[serde(flatten)]
+    pub asset: &'a Asset,
+    /// URL the asset was downloaded from
+    pub source_url: &'a str,
+    /// SHA-256 digest the download was verified against, with --checksums-url
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<&'a str>,
+}
+
+/// Path of the metadata file of a downloaded asset: its own path with ".json" appended
+pub fn asset_json_path(output_path: &Path) -> PathBuf {
+    let mut path = output_path.as_os_str().to_owned();
+    path.push(".json");
+    PathBuf::from(path)
+}
+
+/// Write the metadata of a downloaded asset to its `<dest>.json` file
+pub fn write_asset_json(output_path: &Path, record: &AssetRecord<'_>) -> Result<PathBuf> {
+    let path = asset_json_path(output_path);
+    std::fs::write(&path, serde_json::to_string_pretty(record)? + "\n")?;
+    Ok(path)
+}
+
+/// Fail when more than `limit` assets are about to be downloaded
+pub fn check_asset_limit(tasks: &[DownloadTask], limit: usize) -> Result<()> {
+    if tasks.len() <= limit {
+        return Ok(());
+    }
+    Err(GhrError::TooManyAssets {
+        count: tasks.len(),
+        limit,
+        examples: tasks.iter().take(3).map(|task| task.name.clone()).collect(),
+    })
+}
+
+/// Progress of one asset, reported to a [`ProgressCallback`]
+#[derive(Debug, Clone, Copy)]
+pub enum ProgressEvent<'a> {
+    /// Bytes received so far and the asset's size (0 when unknown); the count
+    /// starts again from zero when the download is retried
+    Bytes { downloaded: u64, size: u64 },
+    /// The asset was downloaded, and verified if checksums were given
+    Finished,
+    /// The asset could not be downloaded
+    Failed(&'a GhrError),
+}
+
+/// Called with an asset's name as it downloads, ending with
+/// [`ProgressEvent::Finished`] or [`ProgressEvent::Failed`] for each asset
+pub type ProgressCallback = Arc<dyn Fn(&str, ProgressEvent<'_>) + Send + Sync>;
+
+/// Options controlling a batch of asset downloads
+#[derive(Clone)]
+pub struct DownloadOptions {
+    /// Maximum number of concurrent downloads
+    pub concurrency: usize,
+    /// Timeout for each asset request, including reading the body
+    pub timeout: Option<Duration>,
+    /// Retries of requests answered with a transient server error
+    pub retry: RetryPolicy,
+    /// Number of times an asset whose checksum does not match is downloaded again
+    pub checksum_retries: u32,
+    /// Retries shared by all downloads of the run, on top of each asset's own limits
+    pub retry_budget: Option<Arc<RetryBudget>>,
+    /// Abort an asset whose transfer rate stays below this minimum
+    pub min_rate: Option<MinRate>,
+    /// Expected SHA-256 digests by asset name; each asset is verified as soon as
+    /// it has been downloaded
+    pub checksums: Option<Arc<HashMap<String, String>>>,
+    /// Receives the progress of each asset, e.g. to draw progress bars
+    pub progress: Option<ProgressCallback>,
+    /// Token sent with requests to the API host only (GitLab)
+    pub host_token: Option<HostToken>,
+}
+
+/// Number of retries shared by all downloads of a run (--global-retry-budget)
+///
+/// Once used up, downloads fail on their next error instead of retrying, so
+/// that an outage doesn't stall the run while every asset retries on its own.
+#[derive(Debug)]
+pub struct RetryBudget {
+    remaining: AtomicU32,
+}
+
+impl RetryBudget {
+    pub fn new(retries: u32) -> Self {
+        Self {
+            remaining: AtomicU32::new(retries),
+        }
+    }
+
+    /// Use up one retry; `false` once none are left
+    fn try_take(&self) -> bool {
+        let taken = self
+            .remaining
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1))
+            .is_ok();
+        if !taken {
+            jdebug!("Global retry budget used up, not retrying");
+        }
+        taken
+    }
+}
+
+/// Whether the shared budget, if any, allows one more retry
+fn budget_allows(budget: Option<&RetryBudget>) -> bool {
+    match budget {
+        Some(budget) => budget.try_take(),
+        None => true,
+    }
+}
+
+/// Lowest acceptable transfer rate of a download, averaged over `window`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MinRate {
+    pub bytes_per_sec: u64,
+    pub window: Duration,
+}
+
+/// Moving average of a download's transfer rate, to detect stalled transfers
+///
+/// A transfer counts as stalled once a full window has passed with the
+/// average rate over that window below the minimum.
+struct RateMonitor {
+    min_rate: MinRate,
+    /// Bytes received so far at each sample time, oldest first
+    samples: VecDeque<(Instant, u64)>,
+}
+
+impl RateMonitor {
+    fn new(min_rate: MinRate, start: Instant) -> Self {
+        Self {
+            min_rate,
+            samples: VecDeque::from([(start, 0)]),
+        }
+    }
+
+    /// Record that `received` bytes have arrived by `now`, returning the average
+    /// rate in bytes per second if it is below the minimum
+    fn stalled(&mut self, now: Instant, received: u64) -> Option<u64> {
+        self.samples.push_back((now, received));
+        // Keep the newest sample that is at least a window old as the baseline
+        while self.samples.len() > 1
+            && now.duration_since(self.samples[1].0) >= self.min_rate.window
+        {
+            self.samples.pop_front();
+        }
+
+        let (since, baseline) = self.samples[0];
+        let elapsed = now.duration_since(since);
+        if elapsed < self.min_rate.window {
+            return None;
+        }
+        let rate = ((received - baseline) as f64 / elapsed.as_secs_f64()) as u64;
+        (rate < self.min_rate.bytes_per_sec).then_some(rate)
+    }
+}
+
+/// Download all tasks concurrently, returning the completed task or the error for each
+///
+/// Errors are [`GhrError::DownloadFailed`], naming the asset they belong to.
+pub async fn download_assets(
+    client: Arc<Client>,
+    tasks: Vec<DownloadTask>,
+    options: &DownloadOptions,
+) -> Vec<Result<DownloadTask>> {
+    // Hash on at most as many threads as there are cores, while downloads continue
+    let hash_limit = Semaphore::new(checksum::hash_parallelism());
+
+    let results: Vec<Result<DownloadTask>> = stream::iter(tasks)
+        .map(|task| {
+            let client = Arc::clone(&client);
+            let hash_limit = &hash_limit;
+            // Attribute log lines of interleaved downloads to their asset
+            let span = tracing::debug_span!("download", asset = %task.name);
+            async move {
+                let name = task.name.clone();
+                let result = async {
+                    let mut attempts = 0;
+                    loop {
+                        let task = download_asset(&client, task.clone(), options).await?;
+                        let Some(sums) = &options.checksums else {
+                            return Ok(task);
+                        };
+                        match verify_download(&task, sums, hash_limit).await {
+                            // Flaky mirrors sometimes serve a truncated or stale body
+                            Err(e @ GhrError::ChecksumMismatch { .. })
+                                if attempts < options.checksum_retries
+                                    && budget_allows(options.retry_budget.as_deref()) =>
+                            {
+                                attempts += 1;
+                                jwarn!(
+                                    "{}; downloading again ({}/{})",
+                                    e,
+                                    attempts,
+                                    options.checksum_retries
+                                );
+                            }
+                            Err(e) => return Err(e),
+                            Ok(()) => return Ok(task),
+                        }
+                    }
+                };
+                let result = result.await.map_err(|e: GhrError| e.for_asset(&name));
+                if let Some(progress) = &options.progress {
+                    match &result {
+                        Ok(_) => progress(&name, ProgressEvent::Finished),
+                        Err(e) => progress(&name, ProgressEvent::Failed(e)),
+                    }
+                }
+                result
+            }
+            .instrument(span)
+        })
+        .buffer_unordered(options.concurrency)
+        .collect()
+        .await;
+
+    results
+}
+
+/// Verify a downloaded asset against its listed digest or, for an asset the
+/// checksums file leaves out, against the API digest
+async fn verify_download(
+    task: &DownloadTask,
+    sums: &HashMap<String, String>,
+    hash_limit: &Semaphore,
+) -> Result<()> {
+    match task.expected_sha256(Some(sums)) {
+        Some(expected) => {
+            checksum::verify_file_blocking(
+                task.name.clone(),
+                task.output_path.clone(),
+                expected,
+                hash_limit,
+            )
+            .await
+        }
+        None => {
+            jwarn!(
+                "No checksum listed for '{}', skipping verification",
+                task.name
+            );
+            Ok(())
+        }
+    }
+}
+
+/// Whether a download failed with a server error that may go away on its own,
+/// e.g. an overloaded CDN; client errors (4xx) are final
+fn is_transient(status: StatusCode) -> bool {
+    matches!(
+        status,
+        StatusCode::INTERNAL_SERVER_ERROR
+            | StatusCode::BAD_GATEWAY
+            | StatusCode::SERVICE_UNAVAILABLE
+            | StatusCode::GATEWAY_TIMEOUT
+    )
+}
+
+/// Send a download request, retrying with backoff while the server answers with
+/// a transient error status
+///
+/// Network errors are returned right away. The last response is returned
+/// once the policy gives up, for the caller to report its status.
+async fn send_with_retry(
+    request: impl Fn() -> RequestBuilder,
+    policy: &RetryPolicy,
+    budget: Option<&RetryBudget>,
+) -> Result<Response> {
+    let start = Instant::now();
+    let mut attempts = 0;
+
+    loop {
+        let response = request().send().await.map_err(GhrError::Network)?;
+        let status = response.status();
+        if is_transient(status) {
+            let delay = policy
+                .delay_for(attempts, start.elapsed())
+                .filter(|_| budget_allows(budget));
+            if let Some(delay) = delay {
+                jdebug!(
+                    "HTTP {} from {}, retry attempt {} after {:?}",
+                    status,
+                    response.url(),
+                    attempts + 1,
+                    delay
+                );
+                sleep(delay).await;
+                attempts += 1;
+                continue;
+            }
+        }
+        return Ok(response);
+    }
+}
+
+/// Download a single asset with progress tracking
+async fn download_asset(
+    client: &Client,
+    task: DownloadTask,
+    options: &DownloadOptions,
+) -> Result<DownloadTask> {
+    let retry = &options.retry;
+    let budget = options.retry_budget.as_deref();
+    let min_rate = options.min_rate;
+    let DownloadTask {
+        name,
+        url,
+        output_path,
+        size,
+        modified,
+        ..
+    } = &task;
+    let size = *size;
+    let report = |downloaded: u64| {
+        if let Some(progress) = &options.progress {
+            progress(name, ProgressEvent::Bytes { downloaded, size });
+        }
+    };
+    report(0);
+
+    jdebug!("Download URL: {}", url);
+
+    // Download with progress tracking
+    let request = || {
+        let mut request = client
+            .get(url)
+            .header(ACCEPT, constants::headers::ACCEPT_OCTET_STREAM);
+        if let Some(host_token) = &options.host_token {
+            request = host_token.apply(request, url);
+        }
+        match options.timeout {
+            Some(timeout) => request.timeout(timeout),
+            None => request,
+        }
+    };
+    let start = Instant::now();
+    let mut attempts = 0;
+    let bytes_vec = loop {
+        let response = send_with_retry(request, retry, budget).await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(GhrError::HttpStatus(status).for_asset(name));
+        }
+
+        let rate = match read_body(response, &report, min_rate).await? {
+            Body::Complete(bytes) => break bytes,
+            Body::Stalled(rate) => rate,
+        };
+        // Slow CDNs may trickle bytes forever without hitting the timeout
+        let window = min_rate.map(|min_rate| min_rate.window).unwrap_or_default();
+        let reason = format!(
+            "transfer rate {}/s below the minimum for {:?}",
+            humanize_bytes(rate),
+            window
+        );
+        let delay = retry
+            .delay_for(attempts, start.elapsed())
+            .filter(|_| budget_allows(budget));
+        match delay {
+            Some(delay) => {
+                jwarn!("{}: {}, retrying after {:?}", name, reason, delay);
+                sleep(delay).await;
+                attempts += 1;
+            }
+            None => {
+                return Err(GhrError::Generic(reason).for_asset(name));
+            }
+        }
+    };
+
+    // Write next to the destination and rename once complete, so the final
+    // path never holds a partial file
+    let part_path = part_path(output_path);
+    if let Err(e) = write_part(&part_path, &bytes_vec, *modified).await {
+        let _ = fs::remove_file(&part_path).await;
+        return Err(e);
+    }
+    fs::rename(&part_path, output_path)
+        .await
+        .map_err(GhrError::Io)?;
+
+    Ok(task)
+}
+
+/// Body of a download response, unless the transfer stalled
+enum Body {
+    Complete(Vec<u8>),
+    /// Aborted with the average rate, in bytes per second, that fell below the minimum
+    Stalled(u64),
+}
+
+/// Read a response body, reporting the bytes received so far to `report`
+///
+/// With a minimum rate, the rate is also checked while no data arrives, so
+/// that a transfer that stops altogether is caught as well.
+async fn read_body(
+    response: Response,
+    report: &impl Fn(u64),
+    min_rate: Option<MinRate>,
+) -> Result<Body> {
+    let mut downloaded: u64 = 0;
+    let mut bytes_vec = Vec::new();
+    let mut stream = response.bytes_stream();
+    let mut monitor = min_rate.map(|min_rate| RateMonitor::new(min_rate, Instant::now()));
+    let check_interval = min_rate
+        .map(|min_rate| (min_rate.window / 4).max(Duration::from_millis(10)))
+        .unwrap_or_default();
+
+    report(0);
+
+    loop {
+        let next = match &mut monitor {
+            Some(monitor) => match tokio::time::timeout(check_interval, stream.next()).await {
+                Ok(next) => next,
+                Err(_) => {
+                    if let Some(rate) = monitor.stalled(Instant::now(), downloaded) {
+                        return Ok(Body::Stalled(rate));
+                    }
+                    continue;
+                }
+            },
+            None => stream.next().await,
+        };
+        let Some(chunk_result) = next else {
+            break;
+        };
+
+        let chunk = chunk_result.map_err(GhrError::Network)?;
+        downloaded += chunk.len() as u64;
+        bytes_vec.extend_from_slice(&chunk);
+        report(downloaded);
+        if let Some(monitor) = &mut monitor {
+            if let Some(rate) = monitor.stalled(Instant::now(), downloaded) {
+                return Ok(Body::Stalled(rate));
+            }
+        }
+    }
+
+    Ok(Body::Complete(bytes_vec))
+}
+
+/// Temporary path a download is written to before being renamed into place
+fn part_path(output_path: &Path) -> PathBuf {
+    let mut name = output_path.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    output_path.with_file_name(name)
+}
+
+async fn write_part(path: &Path, bytes: &[u8], modified: Option<SystemTime>) -> Result<()> {
+    fs::write(path, bytes).await.map_err(GhrError::Io)?;
+    if let Some(modified) = modified {
+        set_modified(path, modified)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(dir: &Path, tag: &str, name: &str, digest: Option<&str>) -> DownloadTask {
+        DownloadTask {
+            name: name.to_string(),
+            url: format!("https://example.com/{}/{}", tag, name),
+            output_path: dir.join(tag).join(name),
+            size: 5,
+            digest: digest.map(String::from),
+            modified: None,
+        }
+    }
+
+    /// Options for downloading one asset at a time with the given retries
+    fn options(retry: RetryPolicy) -> DownloadOptions {
+        DownloadOptions {
+            concurrency: 1,
+            timeout: None,
+            retry,
+            checksum_retries: 0,
+            retry_budget: None,
+            min_rate: None,
+            checksums: None,
+            progress: None,
+            host_token: None,
+        }
+    }
+
+    #[test]
+    fn test_source_archive_name() {
+        assert_eq!(
+            source_archive_name("repo", "v1.0.0").unwrap(),
+            "repo-v1.0.0.tar.gz"
+        );
+        assert_eq!(
+            source_archive_name("repo", "feature/x").unwrap(),
+            "repo-feature_x.tar.gz"
+        );
+        assert!(source_archive_name("repo", "..").is_err());
+    }
+
+    #[test]
+    fn test_write_asset_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let output_path = dir.path().join("app.tar.gz");
+        std::fs::write(&output_path, b"data").unwrap();
+        let asset = Asset {
+            id: 7,
+            name: "app.tar.gz".to_string(),
+            browser_download_url: "https://example.com/v1.0.0/app.tar.gz".to_string(),
+            size: 4,
+            download_count: 12,
+            content_type: Some("application/gzip".to_string()),
+            ..Default::default()
+        };
+        let sha256 = "3a6eb0790f39ac87c94f3856b2dd2c5d110e6811602261a9a923d3bb23adc8b7";
+
+        let path = write_asset_json(
+            &output_path,
+            &AssetRecord {
+                asset: &asset,
+                source_url: "https://api.example.com/assets/7",
+                sha256: Some(sha256),
+            },
+        )
+        .unwrap();
+        assert_eq!(path, dir.path().join("app.tar.gz.json"));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(json["name"], "app.tar.gz");
+        assert_eq!(json["size"], 4);
+        assert_eq!(json["download_count"], 12);
+        assert_eq!(json["content_type"], "application/gzip");
+        assert_eq!(json["source_url"], "https://api.example.com/assets/7");
+        assert_eq!(json["sha256"], sha256);
+
+        // Unverified downloads have no checksum
+        let path = write_asset_json(
+            &output_path,
+            &AssetRecord {
+                asset: &asset,
+                source_url: &asset.browser_download_url,
+                sha256: None,
+            },
+        )
+        .unwrap();
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        assert!(json.get("sha256").is_none());
+    }
+
+    #[test]
+    fn test_set_modified() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("app.tar.gz");
+        std::fs::write(&path, b"data").unwrap();
+
+        let asset = crate::models::Asset {
+            updated_at: Some("2024-03-01T12:34:56Z".to_string()),
+            ..Default::default()
+        };
+        let modified = asset.modified_at().unwrap();
+        set_modified(&path, modified).unwrap();
+
+        let mtime = std::fs::metadata(&path).unwrap().modified().unwrap();
+        assert_eq!(mtime, modified);
+    }
+
+    #[test]
+    fn test_dedupe_shared_asset_across_releases() {
+        let dir = tempfile::tempdir().unwrap();
+        let tasks = vec![
+            task(dir.path(), "v2", "LICENSE", Some("sha256:aaa")),
+            task(dir.path(), "v2", "app-v2.tar.gz", Some("sha256:bbb")),
+            task(dir.path(), "v1", "LICENSE", Some("sha256:aaa")),
+            task(dir.path(), "v1", "app-v1.tar.gz", None),
+        ];
+
+        let (unique, duplicates) = split_duplicates(tasks);
+        assert_eq!(unique.len(), 3);
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(
+            duplicates[0].task.output_path,
+            dir.path().join("v1/LICENSE")
+        );
+        assert_eq!(duplicates[0].source, dir.path().join("v2/LICENSE"));
+
+        // Simulate the download of the first copy, then link the duplicate
+        std::fs::create_dir_all(dir.path().join("v1")).unwrap();
+        std::fs::create_dir_all(dir.path().join("v2")).unwrap();
+        std::fs::write(&duplicates[0].source, b"MIT\n").unwrap();
+        link_or_copy(&duplicates[0].source, &duplicates[0].task.output_path).unwrap();
+        assert_eq!(
+            std::fs::read(&duplicates[0].task.output_path).unwrap(),
+            b"MIT\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_link_duplicates_verifies_source() {
+        use sha2::{Digest, Sha256};
+        use wiremock::matchers::path;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(path("/v1/NOTICE"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(b"v1 notice\n".to_vec()))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("v1")).unwrap();
+        std::fs::create_dir_all(dir.path().join("v2")).unwrap();
+        let mit = hex::encode(Sha256::digest(b"MIT\n"));
+        let digest = format!("sha256:{}", mit);
+        let mut tasks = vec![
+            task(dir.path(), "v2", "LICENSE", Some(&digest)),
+            task(dir.path(), "v2", "NOTICE", Some("sha256:aaa")),
+            task(dir.path(), "v1", "LICENSE", Some(&digest)),
+            task(dir.path(), "v1", "NOTICE", Some("sha256:aaa")),
+        ];
+        tasks[3].url = format!("{}/v1/NOTICE", server.uri());
+        let (unique, duplicates) = split_duplicates(tasks);
+        assert_eq!(duplicates.len(), 2);
+
+        // The downloaded NOTICE does not have the digest the API reported
+        std::fs::write(dir.path().join("v2/LICENSE"), b"MIT\n").unwrap();
+        std::fs::write(dir.path().join("v2/NOTICE"), b"v2 notice\n").unwrap();
+        let downloaded: Vec<Result<DownloadTask>> = unique.into_iter().map(Ok).collect();
+
+        let results = link_duplicates(
+            Arc::new(Client::new()),
+            duplicates,
+            &downloaded,
+            &options(RetryPolicy::default()),
+        )
+        .await;
+        assert!(results.iter().all(|r| r.is_ok()));
+        assert_eq!(
+            std::fs::read(dir.path().join("v1/LICENSE")).unwrap(),
+            b"MIT\n"
+        );
+        assert_eq!(
+            std::fs::read(dir.path().join("v1/NOTICE")).unwrap(),
+            b"v1 notice\n"
+        );
+    }
+
+    #[test]
+    fn test_check_asset_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let tasks: Vec<DownloadTask> = (1..=5)
+            .map(|i| task(dir.path(), ".", &format!("app-{}.tar.gz", i), None))
+            .collect();
+
+        assert!(check_asset_limit(&tasks, 5).is_ok());
+        let err = check_asset_limit(&tasks, 4).unwrap_err();
+        assert!(matches!(
+            err,
+            GhrError::TooManyAssets {
+                count: 5,
+                limit: 4,
+                ..
+            }
+        ));
+        assert_eq!(
+            err.to_string(),
+            "5 assets selected, more than the limit of 4 (e.g. app-1.tar.gz, app-2.tar.gz, app-3.tar.gz). Narrow the filters or raise --max-assets"
+        );
+    }
+
+    #[test]
+    fn test_render_output_dir() {
+        assert_eq!(
+            render_output_dir("downloads/{repo}/{tag}", "owner", "app", "v1.2.3").unwrap(),
+            PathBuf::from("downloads/app/v1.2.3")
+        );
+        assert_eq!(
+            render_output_dir("{owner}-{repo}", "owner", "app", "v1").unwrap(),
+            PathBuf::from("owner-app")
+        );
+        // Tags with slashes stay a single directory
+        assert_eq!(
+            render_output_dir("out/{tag}", "owner", "app", "release/1.0").unwrap(),
+            PathBuf::from("out/release_1.0")
+        );
+    }
+
+    #[test]
+    fn test_render_output_dir_rejects_traversal() {
+        let dir = render_output_dir("out/{tag}", "owner", "app", "../../etc").unwrap();
+        assert_eq!(dir, PathBuf::from("out/.._.._etc"));
+        assert!(dir
+            .components()
+            .all(|c| matches!(c, std::path::Component::Normal(_))));
+
+        assert!(render_output_dir("out/{tag}", "owner", "app", "..").is_err());
+        assert!(render_output_dir("out/{tag}", "owner", "app", ".").is_err());
+        assert!(render_output_dir("out/{tag}", "owner", "app", "").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_download_renames_part_file_when_complete() {
+        use wiremock::matchers::path;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(path("/app.tar.gz"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(b"complete".to_vec()))
+            .mount(&server)
+            .await;
+        Mock::given(path("/missing.tar.gz"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let client = Client::new();
+
+        let mut ok = task(dir.path(), ".", "app.tar.gz", None);
+        ok.url = format!("{}/app.tar.gz", server.uri());
+        let output_path = ok.output_path.clone();
+        download_asset(&client, ok, &options(RetryPolicy::default()))
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(&output_path).unwrap(), b"complete");
+        assert!(!part_path(&output_path).exists());
+
+        let mut failed = task(dir.path(), ".", "missing.tar.gz", None);
+        failed.url = format!("{}/missing.tar.gz", server.uri());
+        let output_path = failed.output_path.clone();
+        assert!(
+            download_asset(&client, failed, &options(RetryPolicy::default()))
+                .await
+                .is_err()
+        );
+        assert!(!output_path.exists());
+        assert!(!part_path(&output_path).exists());
+    }
+
+    #[test]
+    fn test_rate_monitor() {
+        let min_rate = MinRate {
+            bytes_per_sec: 1_000,
+            window: Duration::from_secs(10),
+        };
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        let mut monitor = RateMonitor::new(min_rate, start);
+
+        // Nothing is judged before a full window has passed
+        assert_eq!(monitor.stalled(at(5), 0), None);
+        assert_eq!(monitor.stalled(at(10), 20_000), None);
+        // A burst keeps the average up for a while, then the trickle shows
+        assert_eq!(monitor.stalled(at(15), 20_100), None);
+        assert_eq!(monitor.stalled(at(20), 20_200), Some(20));
+    }
+
+    #[tokio::test]
+    async fn test_download_aborts_slow_trickle() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // Serve a large body one byte at a time, far below the minimum rate
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let connections = Arc::new(AtomicUsize::new(0));
+        let served = Arc::clone(&connections);
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                served.fetch_add(1, Ordering::SeqCst);
+                tokio::spawn(async move {
+                    let mut request = [0; 1024];
+                    let _ = socket.read(&mut request).await;
+                    let _ = socket
+                        .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 100000\r\n\r\n")
+                        .await;
+                    while socket.write_all(b"x").await.is_ok() {
+                        sleep(Duration::from_millis(50)).await;
+                    }
+                });
+            }
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let min_rate = MinRate {
+            bytes_per_sec: 1_000,
+            window: Duration::from_millis(200),
+        };
+        let policy = RetryPolicy {
+            max_retries: 1,
+            base_delay: Duration::from_millis(10),
+            max_delay: Duration::from_millis(10),
+            budget: None,
+        };
+
+        let mut slow = task(dir.path(), ".", "app.tar.gz", None);
+        slow.url = format!("http://{}/app.tar.gz", addr);
+        let output_path = slow.output_path.clone();
+        let options = DownloadOptions {
+            min_rate: Some(min_rate),
+            ..options(policy)
+        };
+        let result = download_asset(&Client::new(), slow, &options).await;
+
+        match result {
+            Err(GhrError::DownloadFailed { asset, source }) => {
+                assert_eq!(asset, "app.tar.gz");
+                assert!(
+                    source.to_string().contains("below the minimum"),
+                    "{}",
+                    source
+                );
+            }
+            other => panic!("expected a stalled download, got {:?}", other),
+        }
+        // Retried once before giving up
+        assert_eq!(connections.load(Ordering::SeqCst), 2);
+        assert!(!output_path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_download_retries_server_errors_only() {
+        use wiremock::matchers::path;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        // The first request hits an unavailable CDN node, the retry succeeds
+        Mock::given(path("/app.tar.gz"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(path("/app.tar.gz"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(b"complete".to_vec()))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(path("/forbidden.tar.gz"))
+            .respond_with(ResponseTemplate::new(403))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let client = Client::new();
+        let policy = RetryPolicy {
+            max_retries: 3,
+            base_delay: Duration::from_millis(10),
+            max_delay: Duration::from_millis(10),
+            budget: None,
+        };
+
+        let mut ok = task(dir.path(), ".", "app.tar.gz", None);
+        ok.url = format!("{}/app.tar.gz", server.uri());
+        let output_path = ok.output_path.clone();
+        download_asset(&client, ok, &options(policy)).await.unwrap();
+        assert_eq!(std::fs::read(&output_path).unwrap(), b"complete");
+
+        // Client errors fail on the first attempt
+        let mut forbidden = task(dir.path(), ".", "forbidden.tar.gz", None);
+        forbidden.url = format!("{}/forbidden.tar.gz", server.uri());
+        assert!(download_asset(&client, forbidden, &options(policy))
+            .await
+            .is_err());
+
+        server.verify().await;
+    }
+
+    #[test]
+    fn test_is_transient() {
+        for code in [500, 502, 503, 504] {
+            assert!(is_transient(StatusCode::from_u16(code).unwrap()));
+        }
+        for code in [400, 401, 403, 404, 429, 501] {
+            assert!(!is_transient(StatusCode::from_u16(code).unwrap()));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_download_errors_name_the_asset() {
+        use wiremock::matchers::path;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(path("/app.tar.gz"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(b"complete".to_vec()))
+            .mount(&server)
+            .await;
+        Mock::given(path("/missing.tar.gz"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut ok = task(dir.path(), ".", "app.tar.gz", None);
+        ok.url = format!("{}/app.tar.gz", server.uri());
+        let mut missing = task(dir.path(), ".", "missing.tar.gz", None);
+        missing.url = format!("{}/missing.tar.gz", server.uri());
+        // Nothing listens on port 1
+        let mut unreachable = task(dir.path(), ".", "unreachable.tar.gz", None);
+        unreachable.url = "http://127.0.0.1:1/unreachable.tar.gz".to_string();
+
+        let options = options(RetryPolicy::default());
+        let results = download_assets(
+            Arc::new(Client::new()),
+            vec![ok, missing, unreachable],
+            &options,
+        )
+        .await;
+
+        let mut failures: Vec<(String, Option<u16>)> = results
+            .into_iter()
+            .filter_map(|result| match result {
+                Err(GhrError::DownloadFailed { asset, source }) => {
+                    Some((asset, source.http_status()))
+                }
+                Err(e) => panic!("unexpected error: {}", e),
+                Ok(_) => None,
+            })
+            .collect();
+        failures.sort();
+        assert_eq!(
+            failures,
+            vec![
+                ("missing.tar.gz".to_string(), Some(404)),
+                ("unreachable.tar.gz".to_string(), None),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_host_token_only_sent_to_api_host() {
+        use reqwest::header::{HeaderName, HeaderValue};
+        use wiremock::matchers::path;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let gitlab = MockServer::start().await;
+        let elsewhere = MockServer::start().await;
+        for server in [&gitlab, &elsewhere] {
+            Mock::given(path("/app.tar.gz"))
+                .respond_with(ResponseTemplate::new(200).set_body_bytes(b"complete".to_vec()))
+                .mount(server)
+                .await;
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let options = DownloadOptions {
+            host_token: Some(HostToken::new(
+                &format!("{}/api/v4", gitlab.uri()),
+                HeaderName::from_static(constants::headers::GITLAB_TOKEN),
+                HeaderValue::from_static("glpat-secret"),
+            )),
+            ..options(RetryPolicy::default())
+        };
+        for (tag, server) in [("a", &gitlab), ("b", &elsewhere)] {
+            let mut app = task(dir.path(), tag, "app.tar.gz", None);
+            app.url = format!("{}/app.tar.gz", server.uri());
+            std::fs::create_dir_all(app.output_path.parent().unwrap()).unwrap();
+            download_asset(&Client::new(), app, &options).await.unwrap();
+        }
+
+        let token = |requests: Vec<wiremock::Request>| {
+            requests[0]
+                .headers
+                .get("private-token")
+                .map(|value| value.to_str().unwrap().to_string())
+        };
+        assert_eq!(
+            token(gitlab.received_requests().await.unwrap()).as_deref(),
+            Some("glpat-secret")
+        );
+        assert_eq!(token(elsewhere.received_requests().await.unwrap()), None);
+    }
+
+    #[tokio::test]
+    async fn test_retry_budget_is_shared() {
+        use wiremock::matchers::any;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(any())
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let tasks = ["app.tar.gz", "app.zip"]
+            .iter()
+            .map(|name| {
+                let mut task = task(dir.path(), ".", name, None);
+                task.url = format!("{}/{}", server.uri(), name);
+                task
+            })
+            .collect();
+        // On their own, each asset would be requested 4 times
+        let retry = RetryPolicy {
+            max_retries: 3,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(1),
+            budget: None,
+        };
+        let options = DownloadOptions {
+            concurrency: 2,
+            retry_budget: Some(Arc::new(RetryBudget::new(2))),
+            ..options(retry)
+        };
+        let results = download_assets(Arc::new(Client::new()), tasks, &options).await;
+        assert!(results.iter().all(|r| r.is_err()));
+
+        // One request per asset plus the 2 retries of the budget
+        assert_eq!(server.received_requests().await.unwrap().len(), 4);
+    }
+
+    #[tokio::test]
+    #[tracing_test::traced_test]
+    async fn test_download_logs_name_the_asset() {
+        use wiremock::matchers::any;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(any())
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(b"data".to_vec()))
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let tasks = ["app.tar.gz", "app.zip"]
+            .iter()
+            .map(|name| {
+                let mut task = task(dir.path(), ".", name, None);
+                task.url = format!("{}/{}", server.uri(), name);
+                task
+            })
+            .collect();
+        let options = DownloadOptions {
+            concurrency: 2,
+            ..options(RetryPolicy::default())
+        };
+        let results = download_assets(Arc::new(Client::new()), tasks, &options).await;
+        assert!(results.iter().all(|r| r.is_ok()));
+
+        logs_assert(|lines: &[&str]| {
+            for name in ["app.tar.gz", "app.zip"] {
+                let url = format!("Download URL: {}/{}", server.uri(), name);
+                let context = format!("download{{asset={}}}", name);
+                if !lines
+                    .iter()
+                    .any(|line| line.contains(&url) && line.contains(&context))
+                {
+                    return Err(format!("no '{}' line in span {}", url, context));
+                }
+            }
+            Ok(())
+        });
+    }
+
+    #[tokio::test]
+    async fn test_download_again_on_checksum_mismatch() {
+        use sha2::{Digest, Sha256};
+        use wiremock::matchers::path;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        // A truncated body first, the complete one afterwards
+        Mock::given(path("/app.tar.gz"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(b"compl".to_vec()))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(path("/app.tar.gz"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(b"complete".to_vec()))
+            .mount(&server)
+            .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        let client = Arc::new(Client::new());
+        let expected = hex::encode(Sha256::digest(b"complete"));
+        let checksums = HashMap::from([("app.tar.gz".to_string(), expected)]);
+        let mut options = DownloadOptions {
+            checksums: Some(Arc::new(checksums)),
+            ..options(RetryPolicy::default())
+        };
+        let app = || {
+            let mut app = task(dir.path(), ".", "app.tar.gz", None);
+            app.url = format!("{}/app.tar.gz", server.uri());
+            app
+        };
+
+        // Without retries the corrupt first download fails the asset
+        let results = download_assets(client.clone(), vec![app()], &options).await;
+        let err = results.into_iter().next().unwrap().unwrap_err();
+        // The mismatch stays the error's source, and names the asset only once
+        let source = std::error::Error::source(&err).unwrap();
+        assert!(source
+            .to_string()
+            .starts_with("Checksum mismatch for 'app.tar.gz'"));
+        assert_eq!(err.to_string(), source.to_string());
+        assert_eq!(err.exit_code(), crate::errors::EXIT_FAILURE);
+
+        server.reset().await;
+        Mock::given(path("/app.tar.gz"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(b"compl".to_vec()))
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(path("/app.tar.gz"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(b"complete".to_vec()))
+            .mount(&server)
+            .await;
+
+        options.checksum_retries = 2;
+        let results = download_assets(client, vec![app()], &options).await;
+        let task = results.into_iter().next().unwrap().unwrap();
+        assert_eq!(std::fs::read(&task.output_path).unwrap(), b"complete");
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_unlisted_asset_verified_against_api_digest() {
+        use sha2::{Digest, Sha256};
+        use wiremock::matchers::path;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(path("/app.deb"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(b"compl".to_vec()))
+            .mount(&server)
+            .await;
+
+        // The checksums file doesn't list app.deb, but the API reported its digest
+        let dir = tempfile::tempdir().unwrap();
+        let options = DownloadOptions {
+            checksums: Some(Arc::new(HashMap::new())),
+            ..options(RetryPolicy::default())
+        };
+        let digest = format!("sha256:{}", hex::encode(Sha256::digest(b"complete")));
+        let mut app = task(dir.path(), ".", "app.deb", Some(&digest));
+        app.url = format!("{}/app.deb", server.uri());
+        assert_eq!(
+            app.expected_sha256_with_source(options.checksums.as_deref())
+                .map(|(_, source)| source),
+            Some(DigestSource::Api)
+        );
+
+        let results = download_assets(Arc::new(Client::new()), vec![app], &options).await;
+        let err = results.into_iter().next().unwrap().unwrap_err();
+        let GhrError::DownloadFailed { source, .. } = err else {
+            panic!("unexpected error: {}", err);
+        };
+        assert!(matches!(*source, GhrError::ChecksumMismatch { .. }));
+    }
+}
@@ -7,9 +7,25 @@ pub enum GhrError {
     #[error("GitHub API error: {0}")]
     GitHubApi(String),
 
-    /// Repository not found or access denied
-    #[error("Repository '{owner}/{repo}' not found or access denied")]
-    RepositoryNotFound { owner: String, repo: String },
+    /// GitLab API returned an error response
+    #[error("GitLab API error: {0}")]
+    GitLabApi(String),
+
+    /// Repository not found or access denied; `suggestions` are similarly named
+    /// repositories of the owner, looked up with --suggest
+    #[error(
+        "Repository '{owner}/{repo}' not found or access denied{}",
+        did_you_mean(.suggestions)
+    )]
+    RepositoryNotFound {
+        owner: String,
+        repo: String,
+        suggestions: Vec<String>,
+    },
+
+    /// Repository was renamed or transferred and renames are not followed
+    #[error("Repository '{from}' has moved to '{to}'. Use --repo {to} instead")]
+    RepositoryMoved { from: String, to: String },
 
     /// Release not found
     #[error("Release with tag '{tag}' not found")]
@@ -27,6 +43,10 @@ pub enum GhrError {
     #[error("Network error: {0}")]
     Network(#[from] reqwest::Error),
 
+    /// Response body ended before the JSON was complete, e.g. a dropped connection
+    #[error("Truncated response from {url}")]
+    TruncatedResponse { url: String },
+
     /// IO error (file operations, etc.)
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
@@ -35,6 +55,10 @@ pub enum GhrError {
     #[error("Authentication failed: {0}")]
     Auth(String),
 
+    /// Repository argument with characters GitHub does not allow in names
+    #[error("Invalid repository '{name}': {reason}")]
+    InvalidRepoName { name: String, reason: String },
+
     /// Invalid URL format
     #[error("Invalid URL format: {url}")]
     InvalidUrl { url: String },
@@ -51,14 +75,73 @@ pub enum GhrError {
     #[error("Invalid search pattern: {0}")]
     InvalidSearchPattern(String),
 
+    /// Search API rejected the query (HTTP 422)
+    #[error("Search rejected by GitHub: {0}")]
+    SearchRejected(String),
+
+    /// Request needed while in offline mode
+    #[error("Offline mode: {0}")]
+    Offline(String),
+
     /// Missing required argument
     #[error("Missing required argument: {0}")]
     MissingArgument(String),
 
     /// No releases found in repository
-    #[error("No releases found in repository")]
+    #[error(
+        "No releases found in repository. It may publish tags only; \
+         use --ref <TAG> to download the source archive of a tag, or \
+         --tags-as-releases to treat its tags as releases"
+    )]
     NoReleases,
 
+    /// Filters matched none of the release assets (with --require-match)
+    #[error("No assets matched the filters. Available assets: {}", .available.join(", "))]
+    NoMatchingAssets { available: Vec<String> },
+
+    /// An asset could not be downloaded; `source` is the error it failed with
+    #[error("{}", download_failure(.asset, .source))]
+    DownloadFailed {
+        asset: String,
+        #[source]
+        source: Box<GhrError>,
+    },
+
+    /// Several assets could not be downloaded
+    #[error("Download failed with {} error(s)", .0.len())]
+    DownloadsFailed(Vec<GhrError>),
+
+    /// Server answered a download with an error status
+    #[error("HTTP {0}")]
+    HttpStatus(reqwest::StatusCode),
+
+    /// More assets selected than allowed by --max-assets
+    #[error(
+        "{count} assets selected, more than the limit of {limit} (e.g. {}). Narrow the filters or raise --max-assets",
+        .examples.join(", ")
+    )]
+    TooManyAssets {
+        count: usize,
+        limit: usize,
+        examples: Vec<String>,
+    },
+
+    /// Downloaded asset does not match its published checksum
+    #[error("Checksum mismatch for '{asset}': expected {expected}, got {actual}")]
+    ChecksumMismatch {
+        asset: String,
+        expected: String,
+        actual: String,
+    },
+
+    /// Archive extraction failed
+    #[error("Extraction error: {0}")]
+    Extract(String),
+
+    /// Signing key could not be loaded
+    #[error("Signing error: {0}")]
+    Signing(String),
+
     /// Header value error
     #[error("Invalid header value: {0}")]
     InvalidHeaderValue(#[from] reqwest::header::InvalidHeaderValue),
@@ -71,15 +154,93 @@ pub enum GhrError {
     #[error("Invalid glob pattern: {0}")]
     GlobError(#[from] globset::Error),
 
+    /// Configuration file could not be read or parsed
+    #[error("Configuration error: {0}")]
+    Config(String),
+
     /// JSON parsing/serialization error
     #[error("JSON error: {0}")]
     JsonError(#[from] serde_json::Error),
 
+    /// CSV serialization error
+    #[error("CSV error: {0}")]
+    CsvError(#[from] csv::Error),
+
     /// Generic error for simple string messages
     #[error("{0}")]
     Generic(String),
 }
 
+/// Exit status of failed runs
+pub const EXIT_FAILURE: u8 = 1;
+
+/// Exit status when the repository, release or ref doesn't exist (2 is taken by
+/// usage errors)
+pub const EXIT_NOT_FOUND: u8 = 3;
+
+/// ". Did you mean: a, b?" for a non-empty list of suggestions, else nothing
+fn did_you_mean(suggestions: &[String]) -> String {
+    if suggestions.is_empty() {
+        String::new()
+    } else {
+        format!(". Did you mean: {}?", suggestions.join(", "))
+    }
+}
+
+/// Message of a failed download, naming the asset once
+fn download_failure(asset: &str, source: &GhrError) -> String {
+    match source {
+        GhrError::ChecksumMismatch { .. } => source.to_string(),
+        _ => format!("Failed to download '{}': {}", asset, source),
+    }
+}
+
+impl GhrError {
+    /// Whether stdout was closed by the reader, e.g. when piped into `head`
+    pub fn is_broken_pipe(&self) -> bool {
+        matches!(self, GhrError::Io(e) if e.kind() == std::io::ErrorKind::BrokenPipe)
+    }
+
+    /// Exit status for a run that failed with this error
+    pub fn exit_code(&self) -> u8 {
+        match self {
+            GhrError::RepositoryNotFound { .. }
+            | GhrError::ReleaseNotFound { .. }
+            | GhrError::RefNotFound { .. }
+            | GhrError::NoReleases => EXIT_NOT_FOUND,
+            GhrError::DownloadFailed { source, .. } => source.exit_code(),
+            // A not-found among the failures says more than the generic status
+            GhrError::DownloadsFailed(errors) => errors
+                .iter()
+                .map(GhrError::exit_code)
+                .find(|code| *code != EXIT_FAILURE)
+                .unwrap_or(EXIT_FAILURE),
+            _ => EXIT_FAILURE,
+        }
+    }
+
+    /// HTTP status the server answered with, if this error comes from one
+    pub fn http_status(&self) -> Option<u16> {
+        match self {
+            GhrError::HttpStatus(status) => Some(status.as_u16()),
+            GhrError::Network(e) => e.status().map(|status| status.as_u16()),
+            GhrError::DownloadFailed { source, .. } => source.http_status(),
+            _ => None,
+        }
+    }
+
+    /// Attribute this error to the download of `asset`
+    pub fn for_asset(self, asset: &str) -> Self {
+        match self {
+            GhrError::DownloadFailed { .. } => self,
+            error => GhrError::DownloadFailed {
+                asset: asset.to_string(),
+                source: Box::new(error),
+            },
+        }
+    }
+}
+
 /// Custom result type for gh_release
 pub type Result<T> = std::result::Result<T, GhrError>;
 
@@ -96,3 +257,35 @@ impl From<String> for GhrError {
         GhrError::Generic(s)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_download_failures_keep_exit_code() {
+        assert_eq!(
+            GhrError::NoReleases.for_asset("app.tar.gz").exit_code(),
+            EXIT_NOT_FOUND
+        );
+        assert_eq!(
+            GhrError::Generic("boom".to_string())
+                .for_asset("app.tar.gz")
+                .exit_code(),
+            EXIT_FAILURE
+        );
+
+        let errors = GhrError::DownloadsFailed(vec![
+            GhrError::Generic("boom".to_string()).for_asset("app.zip"),
+            GhrError::NoReleases.for_asset("app.tar.gz"),
+        ]);
+        assert_eq!(errors.to_string(), "Download failed with 2 error(s)");
+        assert_eq!(errors.exit_code(), EXIT_NOT_FOUND);
+
+        let errors = GhrError::DownloadsFailed(vec![
+            GhrError::Generic("boom".to_string()).for_asset("app.zip"),
+            GhrError::Generic("bang".to_string()).for_asset("app.tar.gz"),
+        ]);
+        assert_eq!(errors.exit_code(), EXIT_FAILURE);
+    }
+}
@@ -0,0 +1,452 @@
+use crate::cli::ArchiveFormat;
+use crate::errors::{GhrError, Result};
+use jlogger_tracing::{jdebug, jwarn};
+use std::collections::VecDeque;
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::{Component, Path, PathBuf};
+
+/// Recognized archive extensions and their formats
+const EXTENSIONS: &[(&str, ArchiveFormat)] = &[
+    (".tar.gz", ArchiveFormat::TarGz),
+    (".tgz", ArchiveFormat::TarGz),
+    (".tar.xz", ArchiveFormat::TarXz),
+    (".txz", ArchiveFormat::TarXz),
+    (".tar.zst", ArchiveFormat::TarZst),
+    (".tzst", ArchiveFormat::TarZst),
+];
+
+/// Links followed while resolving a path before giving up, like the kernel's ELOOP limit
+const MAX_LINK_HOPS: usize = 40;
+
+/// Detect the archive format of a file from its name
+pub fn detect_format(name: &str) -> Option<ArchiveFormat> {
+    let lower = name.to_ascii_lowercase();
+    EXTENSIONS
+        .iter()
+        .find(|(ext, _)| lower.ends_with(ext))
+        .map(|(_, format)| *format)
+}
+
+/// Name of the directory an archive is extracted into (the name without its archive extension)
+pub fn extraction_dir_name(name: &str) -> String {
+    let lower = name.to_ascii_lowercase();
+    for (ext, _) in EXTENSIONS {
+        if lower.ends_with(ext) && lower.len() > ext.len() {
+            return name[..name.len() - ext.len()].to_string();
+        }
+    }
+    format!("{}.extracted", name)
+}
+
+/// Resolve the format to use for an archive, honoring a forced `--archive-format`
+pub fn resolve_format(name: &str, requested: ArchiveFormat) -> Option<ArchiveFormat> {
+    match requested {
+        ArchiveFormat::Auto => detect_format(name),
+        forced => Some(forced),
+    }
+}
+
+/// Extract an archive into a new directory next to it
+///
+/// The archive is unpacked into `<parent>/<name without extension>`. If the
+/// archive is truncated or corrupt the partially extracted directory is
+/// removed again. Returns the extraction directory.
+///
+/// Symbolic and hard links pointing outside the extraction directory are
+/// rejected unless `allow_links` (--allow-symlinks).
+pub fn extract_archive(
+    archive: &Path,
+    format: ArchiveFormat,
+    allow_links: bool,
+) -> Result<PathBuf> {
+    let name = archive
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or_else(|| {
+            GhrError::Extract(format!("Invalid archive path '{}'", archive.display()))
+        })?;
+    let parent = archive.parent().unwrap_or_else(|| Path::new(""));
+    let dest = parent.join(extraction_dir_name(&name));
+
+    if dest.exists() {
+        return Err(GhrError::Extract(format!(
+            "Cannot extract '{}': '{}' already exists",
+            name,
+            dest.display()
+        )));
+    }
+
+    jdebug!(
+        "Extracting '{}' as {:?} into '{}'",
+        name,
+        format,
+        dest.display()
+    );
+    std::fs::create_dir_all(&dest)?;
+
+    if let Err(e) = unpack(archive, &dest, format, allow_links) {
+        if let Err(cleanup) = std::fs::remove_dir_all(&dest) {
+            jwarn!(
+                "Failed to remove partial extraction '{}': {}",
+                dest.display(),
+                cleanup
+            );
+        }
+        return Err(GhrError::Extract(format!(
+            "Failed to extract '{}' (the archive may be truncated or corrupt): {}",
+            name, e
+        )));
+    }
+
+    Ok(dest)
+}
+
+/// Unpack a tar archive compressed with `format` into `dest`
+fn unpack(
+    archive: &Path,
+    dest: &Path,
+    format: ArchiveFormat,
+    allow_links: bool,
+) -> std::io::Result<()> {
+    let file = BufReader::new(File::open(archive)?);
+    let reader: Box<dyn Read> = match format {
+        ArchiveFormat::TarGz => Box::new(flate2::read::GzDecoder::new(file)),
+        ArchiveFormat::TarXz => Box::new(xz2::read::XzDecoder::new(file)),
+        ArchiveFormat::TarZst => Box::new(zstd::stream::read::Decoder::new(file)?),
+        ArchiveFormat::Auto => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "archive format must be resolved before unpacking",
+            ))
+        }
+    };
+
+    let mut archive = tar::Archive::new(reader);
+    // Like `Archive::unpack`, directories are unpacked last, so that a
+    // read-only directory doesn't prevent unpacking its files
+    let mut directories = Vec::new();
+    let mut symlinks = Vec::new();
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !allow_links {
+            check_link(&entry, dest)?;
+            if entry.header().entry_type().is_symlink() {
+                symlinks.push((
+                    entry.path()?.into_owned(),
+                    entry.link_name()?.unwrap_or_default().into_owned(),
+                ));
+            }
+        }
+        if entry.header().entry_type().is_dir() {
+            directories.push(entry);
+        } else {
+            // Refuses entries whose own path leads outside `dest`
+            entry.unpack_in(dest)?;
+        }
+    }
+    for mut directory in directories {
+        directory.unpack_in(dest)?;
+    }
+
+    // A link extracted later can turn the path of an earlier one into a
+    // detour outside `dest`, e.g. `evil -> a/b/..` followed by `a/b -> ..`
+    for (path, target) in symlinks {
+        if !resolves_inside(dest, &path) {
+            return Err(link_error(&path, &target));
+        }
+    }
+    Ok(())
+}
+
+/// Fail for a symbolic or hard link entry whose target lies outside the extraction directory
+///
+/// The target is resolved through the links extracted into `dest` so far, so
+/// a chain of links that each look harmless on their own is rejected as well.
+fn check_link<R: Read>(entry: &tar::Entry<R>, dest: &Path) -> std::io::Result<()> {
+    let kind = entry.header().entry_type();
+    if !kind.is_symlink() && !kind.is_hard_link() {
+        return Ok(());
+    }
+    let path = entry.path()?;
+    let target = entry.link_name()?.unwrap_or_default();
+
+    // Symbolic links are relative to their directory, hard links to the archive root
+    let resolved = if kind.is_symlink() {
+        path.parent().unwrap_or_else(|| Path::new("")).join(&target)
+    } else {
+        target.to_path_buf()
+    };
+    if target.is_absolute() || escapes_root(&resolved) || !resolves_inside(dest, &resolved) {
+        return Err(link_error(&path, &target));
+    }
+    Ok(())
+}
+
+fn link_error(path: &Path, target: &Path) -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::PermissionDenied,
+        format!(
+            "link '{}' -> '{}' points outside the extraction directory; \
+             use --allow-symlinks for trusted archives",
+            path.display(),
+            target.display()
+        ),
+    )
+}
+
+/// Whether a path relative to `root` stays inside it when followed through
+/// the symbolic links that exist on disk
+///
+/// Components that don't exist yet are taken as plain directories.
+fn resolves_inside(root: &Path, path: &Path) -> bool {
+    let mut current = root.to_path_buf();
+    let mut pending: VecDeque<OsString> = path
+        .components()
+        .map(|c| c.as_os_str().to_os_string())
+        .collect();
+    let mut hops = 0;
+
+    while let Some(component) = pending.pop_front() {
+        match Path::new(&component).components().next() {
+            Some(Component::Normal(name)) => {
+                let next = current.join(name);
+                match std::fs::read_link(&next) {
+                    Ok(target) => {
+                        hops += 1;
+                        if target.has_root() || hops > MAX_LINK_HOPS {
+                            return false;
+                        }
+                        // The target is relative to the link's directory
+                        for c in target.components().rev() {
+                            pending.push_front(c.as_os_str().to_os_string());
+                        }
+                    }
+                    Err(_) => current = next,
+                }
+            }
+            Some(Component::ParentDir) => {
+                if !current.pop() {
+                    return false;
+                }
+            }
+            Some(Component::CurDir) | None => {}
+            Some(Component::RootDir | Component::Prefix(_)) => return false,
+        }
+        if !current.starts_with(root) {
+            return false;
+        }
+    }
+    true
+}
+
+/// Whether a relative path leads above its starting directory, e.g. "a/../../b"
+fn escapes_root(path: &Path) -> bool {
+    let mut depth = 0usize;
+    for component in path.components() {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::ParentDir if depth == 0 => return true,
+            Component::ParentDir => depth -= 1,
+            Component::CurDir => {}
+            Component::RootDir | Component::Prefix(_) => return true,
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    /// Build an uncompressed tar containing a single file
+    fn tar_bytes(path: &str, content: &[u8]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, path, content).unwrap();
+        builder.into_inner().unwrap()
+    }
+
+    #[test]
+    fn test_detect_format() {
+        assert_eq!(detect_format("app.tar.gz"), Some(ArchiveFormat::TarGz));
+        assert_eq!(detect_format("app.TGZ"), Some(ArchiveFormat::TarGz));
+        assert_eq!(detect_format("app.tar.xz"), Some(ArchiveFormat::TarXz));
+        assert_eq!(detect_format("app.tar.zst"), Some(ArchiveFormat::TarZst));
+        assert_eq!(detect_format("app.zip"), None);
+        assert_eq!(detect_format("app.deb"), None);
+    }
+
+    #[test]
+    fn test_extraction_dir_name() {
+        assert_eq!(extraction_dir_name("app-1.0.tar.zst"), "app-1.0");
+        assert_eq!(extraction_dir_name("app.txz"), "app");
+        assert_eq!(extraction_dir_name("app.bin"), "app.bin.extracted");
+    }
+
+    #[test]
+    fn test_extract_tar_zst() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("app.tar.zst");
+        let compressed = zstd::encode_all(&tar_bytes("bin/app", b"zstd payload")[..], 3).unwrap();
+        std::fs::write(&archive, compressed).unwrap();
+
+        let dest = extract_archive(&archive, ArchiveFormat::TarZst, false).unwrap();
+        assert_eq!(dest, dir.path().join("app"));
+        assert_eq!(
+            std::fs::read(dest.join("bin/app")).unwrap(),
+            b"zstd payload"
+        );
+    }
+
+    #[test]
+    fn test_extract_tar_xz() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("app.tar.xz");
+        let mut encoder = xz2::write::XzEncoder::new(Vec::new(), 6);
+        encoder
+            .write_all(&tar_bytes("README", b"xz payload"))
+            .unwrap();
+        std::fs::write(&archive, encoder.finish().unwrap()).unwrap();
+
+        let dest = extract_archive(&archive, ArchiveFormat::TarXz, false).unwrap();
+        assert_eq!(std::fs::read(dest.join("README")).unwrap(), b"xz payload");
+    }
+
+    /// Build a gzipped tar containing a file and a link entry of `kind`
+    fn link_archive(kind: tar::EntryType, link: &str, target: &str) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(4);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, "bin/app", &b"data"[..])
+            .unwrap();
+
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(kind);
+        header.set_size(0);
+        header.set_mode(0o777);
+        builder.append_link(&mut header, link, target).unwrap();
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&builder.into_inner().unwrap()).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn test_extract_rejects_escaping_links() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("app.tar.gz");
+
+        for (kind, target) in [
+            (tar::EntryType::Symlink, "../../../etc/passwd"),
+            (tar::EntryType::Symlink, "/etc/passwd"),
+            (tar::EntryType::Link, "../outside"),
+        ] {
+            std::fs::write(&archive, link_archive(kind, "bin/evil", target)).unwrap();
+            let err = extract_archive(&archive, ArchiveFormat::TarGz, false).unwrap_err();
+            assert!(err.to_string().contains("--allow-symlinks"), "{}", err);
+            assert!(!dir.path().join("app").exists());
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_extract_links_inside_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("app.tar.gz");
+
+        std::fs::write(
+            &archive,
+            link_archive(tar::EntryType::Symlink, "app", "bin/app"),
+        )
+        .unwrap();
+        let dest = extract_archive(&archive, ArchiveFormat::TarGz, false).unwrap();
+        assert_eq!(std::fs::read(dest.join("app")).unwrap(), b"data");
+        std::fs::remove_dir_all(&dest).unwrap();
+
+        // Trusted archives may link anywhere
+        std::fs::write(
+            &archive,
+            link_archive(tar::EntryType::Symlink, "bin/evil", "../../outside"),
+        )
+        .unwrap();
+        let dest = extract_archive(&archive, ArchiveFormat::TarGz, true).unwrap();
+        assert_eq!(
+            std::fs::read_link(dest.join("bin/evil")).unwrap(),
+            Path::new("../../outside")
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_extract_rejects_chained_links() {
+        use tar::EntryType;
+
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("app.tar.gz");
+        let chain = |links: &[(&str, &str)], write_through: bool| {
+            let mut builder = tar::Builder::new(Vec::new());
+            for (link, target) in links {
+                let mut header = tar::Header::new_gnu();
+                header.set_entry_type(EntryType::Symlink);
+                header.set_size(0);
+                header.set_mode(0o777);
+                builder.append_link(&mut header, link, target).unwrap();
+            }
+            if write_through {
+                let mut header = tar::Header::new_gnu();
+                header.set_size(4);
+                header.set_mode(0o644);
+                header.set_cksum();
+                builder
+                    .append_data(&mut header, "evil/x", &b"data"[..])
+                    .unwrap();
+            }
+            let mut encoder =
+                flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(&builder.into_inner().unwrap()).unwrap();
+            encoder.finish().unwrap()
+        };
+
+        // Each link stays inside on its own, but `evil` resolves to the parent
+        // of the extraction directory; in either order of the two links
+        for (links, write_through) in [
+            ([("a/b", ".."), ("evil", "a/b/..")], true),
+            ([("evil", "a/b/.."), ("a/b", "..")], false),
+        ] {
+            std::fs::write(&archive, chain(&links, write_through)).unwrap();
+            let err = extract_archive(&archive, ArchiveFormat::TarGz, false).unwrap_err();
+            assert!(err.to_string().contains("--allow-symlinks"), "{}", err);
+            assert!(!dir.path().join("app").exists());
+            assert!(!dir.path().join("x").exists());
+        }
+    }
+
+    #[test]
+    fn test_escapes_root() {
+        assert!(!escapes_root(Path::new("bin/../lib/x")));
+        assert!(!escapes_root(Path::new("./bin")));
+        assert!(escapes_root(Path::new("bin/../../x")));
+        assert!(escapes_root(Path::new("../x")));
+    }
+
+    #[test]
+    fn test_extract_truncated_archive_cleans_up() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("app.tar.zst");
+        let compressed = zstd::encode_all(&tar_bytes("bin/app", &[7u8; 4096])[..], 3).unwrap();
+        std::fs::write(&archive, &compressed[..compressed.len() / 2]).unwrap();
+
+        let result = extract_archive(&archive, ArchiveFormat::TarZst, false);
+        assert!(matches!(result, Err(GhrError::Extract(_))));
+        assert!(!dir.path().join("app").exists());
+    }
+}
@@ -1,5 +1,7 @@
-use crate::errors::Result;
+use crate::errors::{GhrError, Result};
+use crate::models::{Asset, Release};
 use globset::{Glob, GlobMatcher};
+use jlogger_tracing::jwarn;
 use regex::Regex;
 
 /// Filter type for asset filtering
@@ -66,6 +68,106 @@ pub fn apply_filters(name: &str, filters: &[FilterType]) -> bool {
     filters.iter().all(|f| f.matches(name))
 }
 
+/// Parse a human-readable size such as "500", "10MB" or "1.5GiB" into bytes
+///
+/// KB/MB/GB are decimal (powers of 1000), KiB/MiB/GiB are binary (powers of
+/// 1024). Units are case-insensitive and may be separated by a space.
+pub fn parse_size(s: &str) -> Result<u64> {
+    let invalid = || {
+        GhrError::Generic(format!(
+            "Invalid size '{}'. Expected e.g. 500, 10MB or 1.5GiB",
+            s
+        ))
+    };
+
+    let trimmed = s.trim();
+    let split = trimmed
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(trimmed.len());
+    let (number, unit) = trimmed.split_at(split);
+
+    let value: f64 = number.parse().map_err(|_| invalid())?;
+    let multiplier: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "kb" => 1_000,
+        "mb" => 1_000_000,
+        "gb" => 1_000_000_000,
+        "kib" => 1 << 10,
+        "mib" => 1 << 20,
+        "gib" => 1 << 30,
+        _ => return Err(invalid()),
+    };
+
+    Ok((value * multiplier as f64).round() as u64)
+}
+
+/// Inclusive asset size range from --min-size/--max-size
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SizeRange {
+    pub min: Option<u64>,
+    pub max: Option<u64>,
+}
+
+impl SizeRange {
+    /// Check if the given size in bytes lies within the range
+    pub fn contains(&self, size: u64) -> bool {
+        !matches!(self.min, Some(min) if size < min) && !matches!(self.max, Some(max) if size > max)
+    }
+}
+
+/// Error for filters that matched nothing, listing the assets that were available
+pub fn no_match_error(releases: &[&Release]) -> GhrError {
+    GhrError::NoMatchingAssets {
+        available: releases
+            .iter()
+            .flat_map(|r| r.assets.iter().map(|a| a.name.clone()))
+            .collect(),
+    }
+}
+
+/// Drop assets that are still being uploaded, warning about each one
+pub fn skip_incomplete(assets: Vec<&Asset>) -> Vec<&Asset> {
+    assets
+        .into_iter()
+        .filter(|asset| {
+            if !asset.is_uploaded() {
+                jwarn!(
+                    "Skipping asset '{}': upload not complete (state: {})",
+                    asset.name,
+                    asset.state
+                );
+            }
+            asset.is_uploaded()
+        })
+        .collect()
+}
+
+/// Extensions of verification files published next to an asset, e.g. "app.tar.gz.sig"
+const SIDECAR_EXTENSIONS: [&str; 4] = ["sha256", "sig", "asc", "minisig"];
+
+/// Add the sidecar assets (checksums, signatures) of the selected assets
+///
+/// Sidecars are only added when the release has them and are not listed twice.
+pub fn with_sidecars<'a>(release: &'a Release, assets: Vec<&'a Asset>) -> Vec<&'a Asset> {
+    let sidecars: Vec<&Asset> = assets
+        .iter()
+        .flat_map(|asset| {
+            SIDECAR_EXTENSIONS
+                .iter()
+                .map(move |ext| format!("{}.{}", asset.name, ext))
+        })
+        .filter_map(|name| release.assets.iter().find(|a| a.name == name))
+        .collect();
+
+    let mut assets = assets;
+    for sidecar in sidecars {
+        if !assets.iter().any(|a| a.name == sidecar.name) {
+            assets.push(sidecar);
+        }
+    }
+    assets
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -119,4 +221,119 @@ mod tests {
         let filters = vec![];
         assert!(apply_filters("any-file.txt", &filters));
     }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("500").unwrap(), 500);
+        assert_eq!(parse_size("500B").unwrap(), 500);
+        assert_eq!(parse_size("10MB").unwrap(), 10_000_000);
+        assert_eq!(parse_size("10mb").unwrap(), 10_000_000);
+        assert_eq!(parse_size("1.5 GB").unwrap(), 1_500_000_000);
+        assert_eq!(parse_size("2KiB").unwrap(), 2048);
+        assert_eq!(parse_size("1.5MiB").unwrap(), 1_572_864);
+        assert_eq!(parse_size("1GiB").unwrap(), 1_073_741_824);
+
+        assert!(parse_size("").is_err());
+        assert!(parse_size("MB").is_err());
+        assert!(parse_size("10XB").is_err());
+        assert!(parse_size("-5MB").is_err());
+    }
+
+    #[test]
+    fn test_size_range() {
+        let range = SizeRange {
+            min: Some(1_000),
+            max: Some(10_000),
+        };
+        assert!(!range.contains(999));
+        assert!(range.contains(1_000));
+        assert!(range.contains(10_000));
+        assert!(!range.contains(10_001));
+
+        let at_least = SizeRange {
+            min: Some(1_000),
+            max: None,
+        };
+        assert!(at_least.contains(u64::MAX));
+        assert!(SizeRange::default().contains(0));
+    }
+
+    #[test]
+    fn test_skip_incomplete() {
+        let asset = |name: &str, state: &str| Asset {
+            name: name.to_string(),
+            state: state.to_string(),
+            ..Default::default()
+        };
+        let assets = [
+            asset("app-linux.tar.gz", "uploaded"),
+            asset("app-macos.zip", "starting"),
+            asset("app-windows.zip", ""),
+        ];
+
+        let kept = skip_incomplete(assets.iter().collect());
+        let names: Vec<&str> = kept.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, vec!["app-linux.tar.gz", "app-windows.zip"]);
+    }
+
+    #[test]
+    fn test_no_match_error_lists_candidates() {
+        let release = Release {
+            assets: ["app-linux.tar.gz", "app-macos.zip"]
+                .iter()
+                .map(|name| Asset {
+                    name: name.to_string(),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        };
+
+        let error = no_match_error(&[&release]);
+        assert!(matches!(error, GhrError::NoMatchingAssets { .. }));
+        assert_eq!(
+            error.to_string(),
+            "No assets matched the filters. Available assets: app-linux.tar.gz, app-macos.zip"
+        );
+    }
+
+    #[test]
+    fn test_with_sidecars() {
+        let asset = |name: &str| Asset {
+            name: name.to_string(),
+            ..Default::default()
+        };
+        let release = Release {
+            assets: vec![
+                asset("app-linux.tar.gz"),
+                asset("app-linux.tar.gz.sha256"),
+                asset("app-linux.tar.gz.minisig"),
+                asset("app-windows.zip"),
+                asset("app-windows.zip.asc"),
+            ],
+            ..Default::default()
+        };
+
+        let names = |assets: Vec<&Asset>| -> Vec<String> {
+            assets.iter().map(|a| a.name.clone()).collect()
+        };
+
+        // Present sidecars are added, absent ones (.sig, .asc) ignored
+        let selected = vec![&release.assets[0]];
+        assert_eq!(
+            names(with_sidecars(&release, selected)),
+            vec![
+                "app-linux.tar.gz",
+                "app-linux.tar.gz.sha256",
+                "app-linux.tar.gz.minisig"
+            ]
+        );
+
+        // Sidecars already matched by the filters are not duplicated
+        let selected = vec![&release.assets[3], &release.assets[4]];
+        assert_eq!(
+            names(with_sidecars(&release, selected)),
+            vec!["app-windows.zip", "app-windows.zip.asc"]
+        );
+    }
 }
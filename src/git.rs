@@ -1,6 +1,6 @@
 use crate::cli::Cli;
 use crate::errors::{GhrError, Result};
-use crate::models::CloneSpec;
+use crate::models::{CloneSpec, RefKind, ResolvedRef};
 use jlogger_tracing::{jdebug, jinfo, jwarn};
 
 /// Parse clone URL and extract owner, repo, and optional ref
@@ -61,7 +61,7 @@ pub fn parse_clone_url(url: &str) -> Result<CloneSpec> {
     } else if url_part.contains('/') {
         // Short format: owner/repo
         let parts: Vec<&str> = url_part.split('/').collect();
-        if parts.len() < 2 {
+        if parts.len() != 2 {
             return Err(GhrError::InvalidUrl {
                 url: url_part.to_string(),
             });
@@ -81,6 +81,7 @@ pub fn parse_clone_url(url: &str) -> Result<CloneSpec> {
             url: url_part.to_string(),
         });
     }
+    validate_repo(&owner, &repo)?;
 
     Ok(CloneSpec {
         owner,
@@ -90,6 +91,69 @@ pub fn parse_clone_url(url: &str) -> Result<CloneSpec> {
     })
 }
 
+/// Check owner and repository names against the characters GitHub allows
+///
+/// Owners (users and organizations) consist of letters, digits and '-';
+/// repository names may also contain '.' and '_', but cannot be "." or "..".
+pub fn validate_repo(owner: &str, repo: &str) -> Result<()> {
+    let invalid = |reason: String| GhrError::InvalidRepoName {
+        name: format!("{}/{}", owner, repo),
+        reason,
+    };
+
+    if owner.is_empty() {
+        return Err(invalid("owner is empty".to_string()));
+    }
+    if let Some(c) = owner
+        .chars()
+        .find(|c| !(c.is_ascii_alphanumeric() || *c == '-'))
+    {
+        return Err(invalid(format!(
+            "owner contains '{}'; only letters, digits and '-' are allowed",
+            c
+        )));
+    }
+
+    if repo.is_empty() {
+        return Err(invalid("repository name is empty".to_string()));
+    }
+    if repo == "." || repo == ".." {
+        return Err(invalid(format!("'{}' is not a repository name", repo)));
+    }
+    if let Some(c) = repo
+        .chars()
+        .find(|c| !(c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-')))
+    {
+        return Err(invalid(format!(
+            "repository name contains '{}'; only letters, digits, '.', '_' and '-' are allowed",
+            c
+        )));
+    }
+    Ok(())
+}
+
+/// Parse a GitHub "owner/repo" argument, dropping a trailing ".git"
+///
+/// Returns the normalized "owner/repo".
+pub fn parse_repo(full_name: &str) -> Result<String> {
+    let full_name = full_name.trim();
+    let Some((owner, repo)) = full_name.split_once('/') else {
+        return Err(GhrError::InvalidRepoName {
+            name: full_name.to_string(),
+            reason: "expected 'owner/repo'".to_string(),
+        });
+    };
+    let repo = repo.strip_suffix(".git").unwrap_or(repo);
+    if repo.contains('/') {
+        return Err(GhrError::InvalidRepoName {
+            name: full_name.to_string(),
+            reason: "expected 'owner/repo'".to_string(),
+        });
+    }
+    validate_repo(owner, repo)?;
+    Ok(format!("{}/{}", owner, repo))
+}
+
 /// Extract repository name from URL for default directory name
 pub fn get_repo_name(url: &str) -> String {
     // Try to parse the URL first
@@ -110,6 +174,47 @@ pub fn get_repo_name(url: &str) -> String {
     "cloned-repo".to_string()
 }
 
+/// Convert a git remote URL into an "owner/repo" string
+///
+/// Accepts the forms understood by `parse_clone_url` plus `ssh://` URLs,
+/// which is what `git remote get-url` reports for most GitHub checkouts.
+pub fn repo_from_remote_url(url: &str) -> Result<String> {
+    let url = url.trim();
+    let normalized = if let Some(path) = url.strip_prefix("ssh://git@github.com/") {
+        format!("git@github.com:{}", path)
+    } else {
+        url.to_string()
+    };
+
+    let spec = parse_clone_url(&normalized)?;
+    Ok(format!("{}/{}", spec.owner, spec.repo))
+}
+
+/// Infer "owner/repo" from the `origin` remote of the git checkout in the current directory
+///
+/// Returns None when git is unavailable, the directory is not a git checkout,
+/// or the remote does not point at GitHub.
+pub async fn detect_origin_repo() -> Option<String> {
+    let output = tokio::process::Command::new("git")
+        .args(["remote", "get-url", "origin"])
+        .output()
+        .await
+        .ok()?;
+
+    if !output.status.success() {
+        return None;
+    }
+
+    let url = String::from_utf8_lossy(&output.stdout);
+    match repo_from_remote_url(&url) {
+        Ok(repo) => Some(repo),
+        Err(e) => {
+            jdebug!("Ignoring origin remote '{}': {}", url.trim(), e);
+            None
+        }
+    }
+}
+
 /// Check if git is installed and available in PATH
 pub async fn check_git_installed() -> Result<()> {
     let output = tokio::process::Command::new("git")
@@ -169,7 +274,7 @@ pub fn get_raw_file_url(plain_download_url: &str) -> Result<String> {
     // Verify it's a blob URL
     if parts[3] != "blob" {
         return Err(GhrError::InvalidUrl {
-            url: format!("URL must contain '/blob/' segment. Expected format: https://github.com/{{owner}}/{{repo}}/blob/{{ref}}/{{path}}")
+            url: "URL must contain '/blob/' segment. Expected format: https://github.com/{owner}/{repo}/blob/{ref}/{path}".to_string()
         });
     }
 
@@ -185,11 +290,135 @@ pub fn get_raw_file_url(plain_download_url: &str) -> Result<String> {
     ))
 }
 
+/// Options of `--clone` besides the repository and ref
+#[derive(Debug, Clone, Default)]
+pub struct CloneOptions {
+    /// Clone and check out submodules as well (--recurse-submodules)
+    pub recurse_submodules: bool,
+    /// Check out branches as a detached HEAD too (--detach)
+    pub detach: bool,
+    /// Token the clone URL was built with, also used for github.com submodules
+    pub token: Option<String>,
+}
+
+impl CloneOptions {
+    /// `-c` options passing the token to submodule fetches from github.com
+    ///
+    /// Given on the command line, the token is not stored in the submodules'
+    /// git config.
+    fn auth_config(&self) -> Vec<String> {
+        match &self.token {
+            Some(token) if self.recurse_submodules => vec![
+                "-c".to_string(),
+                format!(
+                    "url.https://{}@github.com/.insteadOf=https://github.com/",
+                    token
+                ),
+            ],
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Build the git arguments of the clone itself
+pub fn clone_args(clone_url: &str, target_dir: &str, options: &CloneOptions) -> Vec<String> {
+    let mut args = options.auth_config();
+    args.push("clone".to_string());
+    if options.recurse_submodules {
+        args.push("--recurse-submodules".to_string());
+    }
+    args.push(clone_url.to_string());
+    args.push(target_dir.to_string());
+    args
+}
+
+/// Build the git arguments used to check out a resolved ref after cloning
+///
+/// Branches are checked out as a local branch tracking the remote branch, tags
+/// and commits as a detached HEAD. With `detach`, branches are checked out
+/// detached as well. Fully qualified ref names are used so that a branch and a
+/// tag with the same name cannot be confused.
+pub fn checkout_args(resolved: &ResolvedRef, detach: bool) -> Vec<String> {
+    match resolved.kind {
+        RefKind::Branch if detach => vec![
+            "-c".to_string(),
+            "advice.detachedHead=false".to_string(),
+            "checkout".to_string(),
+            "--detach".to_string(),
+            format!("refs/remotes/origin/{}", resolved.name),
+        ],
+        RefKind::Branch => vec![
+            "checkout".to_string(),
+            "-B".to_string(),
+            resolved.name.clone(),
+            "--track".to_string(),
+            format!("refs/remotes/origin/{}", resolved.name),
+        ],
+        RefKind::Tag => vec![
+            "-c".to_string(),
+            "advice.detachedHead=false".to_string(),
+            "checkout".to_string(),
+            resolved.canonical(),
+        ],
+        RefKind::Commit => vec![
+            "-c".to_string(),
+            "advice.detachedHead=false".to_string(),
+            "checkout".to_string(),
+            "--detach".to_string(),
+            resolved.canonical(),
+        ],
+    }
+}
+
+/// Messages git prints on stderr when the remote refused the credentials, or
+/// needed some and had none
+const AUTH_FAILURE_MARKERS: &[&[u8]] = &[
+    b"authentication failed",
+    b"could not read username",
+    b"could not read password",
+    b"invalid username or password",
+    b"permission denied (publickey)",
+];
+
+/// Whether git's stderr reports an authentication failure
+///
+/// Works on the raw bytes, so output that is not valid UTF-8 is still
+/// recognized.
+fn is_auth_failure(stderr: &[u8]) -> bool {
+    let stderr = stderr.to_ascii_lowercase();
+    AUTH_FAILURE_MARKERS
+        .iter()
+        .any(|marker| stderr.windows(marker.len()).any(|window| window == *marker))
+}
+
+/// Error of a failed git command, `action` naming the step, e.g. "clone"
+///
+/// Authentication failures become `GhrError::Auth` with a hint on the token.
+fn git_failure(action: &str, stderr: &[u8], has_token: bool) -> GhrError {
+    let message = String::from_utf8_lossy(stderr);
+    let message = message.trim();
+
+    if is_auth_failure(stderr) {
+        let hint = if has_token {
+            "check that the token is valid and has access to the repository"
+        } else {
+            "provide a token with --token or --token-file"
+        };
+        return GhrError::Auth(format!(
+            "git {} was refused ({}): {}",
+            action, hint, message
+        ));
+    }
+
+    GhrError::GitCommand(format!("Git {} failed: {}", action, message))
+}
+
 /// Execute git clone command
 pub async fn execute_git_clone(
     clone_url: &str,
     target_dir: &str,
-    ref_name: Option<&str>,
+    resolved_ref: Option<&ResolvedRef>,
+    options: &CloneOptions,
 ) -> Result<()> {
     // Check target directory doesn't exist
     if std::path::Path::new(target_dir).exists() {
@@ -199,23 +428,19 @@ pub async fn execute_git_clone(
         )));
     }
 
+    let has_token = options.token.is_some();
+
     // Execute git clone
     jinfo!("Executing: git clone <url> {}", target_dir);
     let output = tokio::process::Command::new("git")
-        .arg("clone")
-        .arg(clone_url)
-        .arg(target_dir)
+        .args(clone_args(clone_url, target_dir, options))
         .output()
         .await
         .map_err(|e| GhrError::GitCommand(format!("Failed to execute git clone: {}", e)))?;
 
     if !output.status.success() {
-        let error = String::from_utf8_lossy(&output.stderr);
         cleanup_partial_clone(target_dir);
-        return Err(GhrError::GitCommand(format!(
-            "Git clone failed: {}",
-            error.trim()
-        )));
+        return Err(git_failure("clone", &output.stderr, has_token));
     }
 
     // Show git output
@@ -227,29 +452,48 @@ pub async fn execute_git_clone(
     }
 
     // Checkout specific ref if provided
-    if let Some(ref_name) = ref_name {
-        jinfo!("Checking out ref '{}'...", ref_name);
+    if let Some(resolved) = resolved_ref {
+        jinfo!(
+            "Checking out {} '{}'...",
+            resolved.kind,
+            resolved.canonical()
+        );
         let output = tokio::process::Command::new("git")
             .arg("-C")
             .arg(target_dir)
-            .arg("checkout")
-            .arg(ref_name)
+            .args(checkout_args(resolved, options.detach))
             .output()
             .await
             .map_err(|e| GhrError::GitCommand(format!("Failed to execute git checkout: {}", e)))?;
 
         if !output.status.success() {
-            let error = String::from_utf8_lossy(&output.stderr);
             cleanup_partial_clone(target_dir);
-            return Err(GhrError::GitCommand(format!(
-                "Git checkout failed: {}",
-                error.trim()
-            )));
+            return Err(git_failure("checkout", &output.stderr, has_token));
         }
 
         if !output.stderr.is_empty() {
             eprintln!("{}", String::from_utf8_lossy(&output.stderr));
         }
+
+        // The clone checked out the submodules of the default branch only
+        if options.recurse_submodules {
+            jinfo!("Updating submodules...");
+            let output = tokio::process::Command::new("git")
+                .arg("-C")
+                .arg(target_dir)
+                .args(options.auth_config())
+                .args(["submodule", "update", "--init", "--recursive"])
+                .output()
+                .await
+                .map_err(|e| {
+                    GhrError::GitCommand(format!("Failed to execute git submodule: {}", e))
+                })?;
+
+            if !output.status.success() {
+                cleanup_partial_clone(target_dir);
+                return Err(git_failure("submodule update", &output.stderr, has_token));
+            }
+        }
     }
 
     Ok(())
@@ -346,6 +590,56 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_parse_clone_url_dotted_names() {
+        let spec = parse_clone_url("my-org/socket.io-client.git:v4.0.0").unwrap();
+        assert_eq!(spec.owner, "my-org");
+        assert_eq!(spec.repo, "socket.io-client");
+        assert_eq!(spec.ref_name, Some("v4.0.0".to_string()));
+
+        let spec = parse_clone_url("https://github.com/owner/.github").unwrap();
+        assert_eq!(spec.repo, ".github");
+    }
+
+    #[test]
+    fn test_parse_clone_url_invalid_characters() {
+        for url in [
+            "own er/repo",
+            "owner_name/repo",
+            "owner/re po",
+            "owner/repo!",
+            "https://github.com/owner/..",
+        ] {
+            assert!(
+                matches!(parse_clone_url(url), Err(GhrError::InvalidRepoName { .. })),
+                "{}",
+                url
+            );
+        }
+        assert!(parse_clone_url("owner/sub/repo").is_err());
+    }
+
+    #[test]
+    fn test_parse_repo() {
+        assert_eq!(parse_repo("owner/repo").unwrap(), "owner/repo");
+        assert_eq!(parse_repo("my-org/repo.js.git").unwrap(), "my-org/repo.js");
+        assert_eq!(
+            parse_repo("Owner-1/my_repo-2.0").unwrap(),
+            "Owner-1/my_repo-2.0"
+        );
+
+        let err = parse_repo("owner.name/repo").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid repository 'owner.name/repo': owner contains '.'; only letters, digits and '-' are allowed"
+        );
+        assert!(parse_repo("owner/repo?tab=readme").is_err());
+        assert!(parse_repo("owner").is_err());
+        assert!(parse_repo("owner/").is_err());
+        assert!(parse_repo("/repo").is_err());
+        assert!(parse_repo("owner/sub/repo").is_err());
+    }
+
     #[test]
     fn test_get_repo_name_https() {
         assert_eq!(get_repo_name("https://github.com/owner/my-repo"), "my-repo");
@@ -371,6 +665,162 @@ mod tests {
         assert_eq!(get_repo_name("owner/repo:main"), "repo");
     }
 
+    #[test]
+    fn test_checkout_args_branch() {
+        let resolved = ResolvedRef {
+            kind: RefKind::Branch,
+            name: "main".to_string(),
+        };
+        assert_eq!(
+            checkout_args(&resolved, false),
+            vec![
+                "checkout",
+                "-B",
+                "main",
+                "--track",
+                "refs/remotes/origin/main"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_checkout_args_tag() {
+        let resolved = ResolvedRef {
+            kind: RefKind::Tag,
+            name: "v1.0.0".to_string(),
+        };
+        assert_eq!(
+            checkout_args(&resolved, false),
+            vec![
+                "-c",
+                "advice.detachedHead=false",
+                "checkout",
+                "refs/tags/v1.0.0"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_checkout_args_commit() {
+        let resolved = ResolvedRef {
+            kind: RefKind::Commit,
+            name: "abc1234".to_string(),
+        };
+        assert_eq!(
+            checkout_args(&resolved, false),
+            vec![
+                "-c",
+                "advice.detachedHead=false",
+                "checkout",
+                "--detach",
+                "abc1234"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_git_failure_maps_auth_errors() {
+        let stderr = b"Cloning into 'repo'...\nfatal: could not read Username for 'https://github.com': No such device or address\n";
+        let err = git_failure("clone", stderr, false);
+        assert!(matches!(err, GhrError::Auth(_)));
+        assert!(err.to_string().contains("--token"));
+
+        // Non-UTF-8 bytes (e.g. a localized message) don't hide the marker
+        let mut stderr = b"remote: \xff\xfe\n".to_vec();
+        stderr.extend_from_slice(
+            b"fatal: Authentication failed for 'https://github.com/owner/repo.git/'",
+        );
+        let err = git_failure("clone", &stderr, true);
+        assert!(matches!(err, GhrError::Auth(_)));
+        assert!(err.to_string().contains("has access to the repository"));
+
+        let err = git_failure(
+            "checkout",
+            b"error: pathspec 'nope' did not match any file(s) known to git",
+            true,
+        );
+        assert!(matches!(err, GhrError::GitCommand(_)));
+        assert!(err
+            .to_string()
+            .starts_with("Git command failed: Git checkout failed:"));
+    }
+
+    #[test]
+    fn test_checkout_args_detached_branch() {
+        let resolved = ResolvedRef {
+            kind: RefKind::Branch,
+            name: "main".to_string(),
+        };
+        assert_eq!(
+            checkout_args(&resolved, true),
+            vec![
+                "-c",
+                "advice.detachedHead=false",
+                "checkout",
+                "--detach",
+                "refs/remotes/origin/main"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_clone_args() {
+        let url = "https://github.com/owner/repo.git";
+        assert_eq!(
+            clone_args(url, "repo", &CloneOptions::default()),
+            vec!["clone", url, "repo"]
+        );
+
+        let options = CloneOptions {
+            recurse_submodules: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            clone_args(url, "repo", &options),
+            vec!["clone", "--recurse-submodules", url, "repo"]
+        );
+
+        // Submodules on github.com are fetched with the token as well
+        let options = CloneOptions {
+            recurse_submodules: true,
+            token: Some("token123".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            clone_args(url, "repo", &options),
+            vec![
+                "-c",
+                "url.https://token123@github.com/.insteadOf=https://github.com/",
+                "clone",
+                "--recurse-submodules",
+                url,
+                "repo"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_repo_from_remote_url() {
+        let cases = [
+            "https://github.com/owner/repo",
+            "https://github.com/owner/repo.git",
+            "http://github.com/owner/repo.git",
+            "git@github.com:owner/repo.git",
+            "ssh://git@github.com/owner/repo.git",
+            "  https://github.com/owner/repo.git\n",
+        ];
+
+        for url in cases {
+            assert_eq!(repo_from_remote_url(url).unwrap(), "owner/repo", "{}", url);
+        }
+    }
+
+    #[test]
+    fn test_repo_from_remote_url_not_github() {
+        assert!(repo_from_remote_url("https://gitlab.com/owner/repo.git").is_err());
+        assert!(repo_from_remote_url("/srv/git/repo.git").is_err());
+    }
+
     #[test]
     fn test_construct_clone_url() {
         let url = construct_clone_url("owner", "repo", Some("token123"));
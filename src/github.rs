@@ -1,35 +1,50 @@
 use crate::cache::Cache;
 use crate::constants;
 use crate::errors::{GhrError, Result};
-use crate::models::{Release, Repository, RepositoryInfo, SearchResponse, Tag};
-use jlogger_tracing::{jdebug, jinfo};
-use reqwest::Client;
-use tokio::time::{sleep, Duration};
+use crate::http::{collect_pages, read_json, ApiClient, RetryPolicy};
+use crate::models::{
+    ApiErrorResponse, Asset, Branch, RefKind, Release, Repository, RepositoryInfo, ResolvedRef,
+    SearchResponse, Tag, Topics,
+};
+use futures::stream::{self, StreamExt};
+use jlogger_tracing::{jdebug, jinfo, jtrace};
+use tokio::time::{sleep, Instant};
 
 /// Retry an async operation with exponential backoff
 /// Only retries on network-related errors, not on logical errors like 404
-async fn retry_with_backoff<F, T, Fut>(operation: F) -> Result<T>
+pub(crate) async fn retry_with_backoff<F, T, Fut>(policy: &RetryPolicy, operation: F) -> Result<T>
 where
     F: Fn() -> Fut,
     Fut: std::future::Future<Output = Result<T>>,
 {
-    let max_retries = constants::retry::MAX_RETRIES;
+    let start = Instant::now();
     let mut attempts = 0;
 
     loop {
         match operation().await {
-            Ok(result) => return Ok(result),
+            Ok(result) => {
+                if attempts > 0 {
+                    jtrace!("Request succeeded after {} retries", attempts);
+                }
+                return Ok(result);
+            }
             Err(e) => {
-                // Only retry on network errors, not on logical errors
-                let should_retry = matches!(e, GhrError::Network(_));
+                // Only retry on network errors and truncated responses, not on
+                // logical errors
+                let should_retry =
+                    matches!(e, GhrError::Network(_) | GhrError::TruncatedResponse { .. });
+                let delay = if should_retry {
+                    policy.delay_for(attempts, start.elapsed())
+                } else {
+                    None
+                };
 
-                if should_retry && attempts < max_retries {
-                    let delay =
-                        Duration::from_secs(constants::retry::BASE_DELAY_SECS * 2u64.pow(attempts));
+                if let Some(delay) = delay {
                     jdebug!("Retry attempt {} after {:?}: {}", attempts + 1, delay, e);
                     sleep(delay).await;
                     attempts += 1;
                 } else {
+                    jtrace!("Request failed after {} retries: {}", attempts, e);
                     return Err(e);
                 }
             }
@@ -40,7 +55,7 @@ where
 /// Fetch release information from GitHub
 #[allow(dead_code)]
 pub async fn get_release_info(
-    client: &Client,
+    client: &ApiClient,
     repo: &str,
     tag: Option<&str>,
 ) -> Result<Vec<Release>> {
@@ -50,7 +65,7 @@ pub async fn get_release_info(
 /// Fetch release information from GitHub with custom base URL
 #[allow(dead_code)]
 pub async fn get_release_info_with_base(
-    client: &Client,
+    client: &ApiClient,
     base_url: &str,
     repo: &str,
     tag: Option<&str>,
@@ -60,7 +75,7 @@ pub async fn get_release_info_with_base(
 
 /// Fetch release information from GitHub with optional caching
 pub async fn get_release_info_with_cache(
-    client: &Client,
+    client: &ApiClient,
     base_url: &str,
     repo: &str,
     tag: Option<&str>,
@@ -83,11 +98,26 @@ pub async fn get_release_info_with_cache(
         format!("releases:{}:{}", repo, base_url)
     };
 
+    let not_found = || match tag {
+        Some(tag) => GhrError::ReleaseNotFound {
+            tag: tag.to_string(),
+        },
+        None => GhrError::RepositoryNotFound {
+            owner: owner.to_string(),
+            repo: repo_name.to_string(),
+            suggestions: Vec::new(),
+        },
+    };
+
     // Try cache first
     if let Some(cache) = cache {
         if let Some(cached) = cache.get::<Vec<Release>>(&cache_key).await {
             return Ok(cached);
         }
+        if cache.is_known_missing(&cache_key).await {
+            jdebug!("Cached not-found result for {}", cache_key);
+            return Err(not_found());
+        }
     }
 
     let url = if let Some(tag) = tag {
@@ -96,9 +126,12 @@ pub async fn get_release_info_with_cache(
         constants::endpoints::releases_with_base(base_url, owner, repo_name)
     };
 
-    let result = retry_with_backoff(|| async {
-        let response = client.get(&url).send().await?;
+    let result = retry_with_backoff(client.retry_policy(), || async {
+        let response = client.fetch(&url).await?;
 
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(not_found());
+        }
         if !response.status().is_success() {
             return Err(GhrError::GitHubApi(format!(
                 "Failed to fetch releases: HTTP {}",
@@ -108,15 +141,31 @@ pub async fn get_release_info_with_cache(
 
         if tag.is_some() {
             // Single release
-            let release: Release = response.json().await?;
+            let release: Release = read_json(response).await?;
             Ok(vec![release])
         } else {
             // Multiple releases
-            let releases: Vec<Release> = response.json().await?;
+            let releases: Vec<Release> = read_json(response).await?;
             Ok(releases)
         }
     })
-    .await?;
+    .await;
+    let result = match result {
+        Ok(mut releases) => complete_assets(client, &mut releases)
+            .await
+            .map(|_| releases),
+        Err(e) => Err(e),
+    };
+
+    let result = match result {
+        Err(e @ (GhrError::RepositoryNotFound { .. } | GhrError::ReleaseNotFound { .. })) => {
+            if let Some(cache) = cache {
+                let _ = cache.set_missing(&cache_key).await;
+            }
+            return Err(e);
+        }
+        result => result?,
+    };
 
     // Cache the result
     if let Some(cache) = cache {
@@ -126,6 +175,92 @@ pub async fn get_release_info_with_cache(
     Ok(result)
 }
 
+/// Fetch all assets of a release from its `assets_url`, following the pages
+async fn get_all_assets(client: &ApiClient, assets_url: &str) -> Result<Vec<Asset>> {
+    let per_page = constants::ASSETS_PER_PAGE;
+    collect_pages(per_page, usize::MAX, |page| {
+        let url = format!("{}?per_page={}&page={}", assets_url, per_page, page);
+        async move {
+            jdebug!("Fetching assets page {}", page);
+            retry_with_backoff(client.retry_policy(), || async {
+                let response = client.fetch(&url).await?;
+                if !response.status().is_success() {
+                    return Err(GhrError::GitHubApi(format!(
+                        "Failed to fetch release assets: HTTP {}",
+                        response.status()
+                    )));
+                }
+                read_json::<Vec<Asset>>(response).await
+            })
+            .await
+        }
+    })
+    .await
+}
+
+/// Replace the embedded assets of releases whose asset list may be cut off
+/// with the complete list from their `assets_url`
+async fn complete_assets(client: &ApiClient, releases: &mut [Release]) -> Result<()> {
+    for release in releases
+        .iter_mut()
+        .filter(|release| release.assets.len() >= constants::ASSETS_PER_PAGE)
+    {
+        if let Some(assets_url) = release.assets_url.as_deref() {
+            let assets = get_all_assets(client, assets_url).await?;
+            jdebug!("Release {} has {} assets", release.tag_name, assets.len());
+            release.assets = assets;
+        }
+    }
+    Ok(())
+}
+
+/// Fetch up to `limit` releases, newest first, following the pages of the release list
+///
+/// Unlike [`get_release_info_with_cache`], which returns the first page only,
+/// this is meant for mirroring and bypasses the cache.
+pub async fn get_all_releases(
+    client: &ApiClient,
+    base_url: &str,
+    repo: &str,
+    per_page: usize,
+    limit: usize,
+) -> Result<Vec<Release>> {
+    let (owner, repo_name) = repo
+        .split_once('/')
+        .ok_or_else(|| GhrError::Generic(format!("Invalid repository format: {}", repo)))?;
+
+    let mut releases = collect_pages(per_page, limit, |page| {
+        let url = constants::endpoints::releases_page_with_base(
+            base_url, owner, repo_name, per_page, page,
+        );
+        async move {
+            jdebug!("Fetching releases page {}", page);
+            retry_with_backoff(client.retry_policy(), || async {
+                let response = client.fetch(&url).await?;
+                if response.status() == reqwest::StatusCode::NOT_FOUND {
+                    return Err(GhrError::RepositoryNotFound {
+                        owner: owner.to_string(),
+                        repo: repo_name.to_string(),
+                        suggestions: Vec::new(),
+                    });
+                }
+                if !response.status().is_success() {
+                    return Err(GhrError::GitHubApi(format!(
+                        "Failed to fetch releases: HTTP {}",
+                        response.status()
+                    )));
+                }
+                read_json::<Vec<Release>>(response).await
+            })
+            .await
+        }
+    })
+    .await?;
+
+    complete_assets(client, &mut releases).await?;
+    Ok(releases)
+}
+
 /// Search pattern types
 #[derive(Debug)]
 pub enum SearchPattern {
@@ -178,10 +313,35 @@ pub fn parse_search_pattern(pattern: &str) -> Result<SearchPattern> {
     }
 }
 
+/// Extract a human-readable reason from a GitHub API error body
+///
+/// Error responses look like
+/// `{"message": "Validation Failed", "errors": [{"message": "..."}]}`; the
+/// detailed messages in `errors` are preferred over the generic top-level one.
+/// Bodies that are not JSON are returned trimmed as-is.
+pub fn parse_api_error_message(body: &str) -> String {
+    let Ok(error) = serde_json::from_str::<ApiErrorResponse>(body) else {
+        return body.trim().to_string();
+    };
+
+    let details: Vec<String> = error
+        .errors
+        .into_iter()
+        .filter_map(|e| e.message.or(e.code))
+        .collect();
+
+    match (error.message, details.is_empty()) {
+        (Some(message), true) => message,
+        (Some(message), false) => format!("{} ({})", message, details.join("; ")),
+        (None, false) => details.join("; "),
+        (None, true) => body.trim().to_string(),
+    }
+}
+
 /// Search for repositories
 #[allow(dead_code)]
 pub async fn search_repositories(
-    client: &Client,
+    client: &ApiClient,
     pattern: &SearchPattern,
     num: usize,
 ) -> Result<Vec<Repository>> {
@@ -191,7 +351,7 @@ pub async fn search_repositories(
 /// Search for repositories with custom base URL
 #[allow(dead_code)]
 pub async fn search_repositories_with_base(
-    client: &Client,
+    client: &ApiClient,
     base_url: &str,
     pattern: &SearchPattern,
     num: usize,
@@ -201,7 +361,7 @@ pub async fn search_repositories_with_base(
 
 /// Search for repositories with optional caching
 pub async fn search_repositories_with_cache(
-    client: &Client,
+    client: &ApiClient,
     base_url: &str,
     pattern: &SearchPattern,
     num: usize,
@@ -231,8 +391,13 @@ pub async fn search_repositories_with_cache(
 
     let url = constants::endpoints::search_repositories_with_base(base_url, &query, num);
 
-    let result = retry_with_backoff(|| async {
-        let response = client.get(&url).send().await?;
+    let result = retry_with_backoff(client.retry_policy(), || async {
+        let response = client.fetch(&url).await?;
+
+        if response.status() == reqwest::StatusCode::UNPROCESSABLE_ENTITY {
+            let body = response.text().await?;
+            return Err(GhrError::SearchRejected(parse_api_error_message(&body)));
+        }
 
         if !response.status().is_success() {
             return Err(GhrError::GitHubApi(format!(
@@ -241,7 +406,7 @@ pub async fn search_repositories_with_cache(
             )));
         }
 
-        let search_response: SearchResponse = response.json().await?;
+        let search_response: SearchResponse = read_json(response).await?;
 
         Ok(search_response.items)
     })
@@ -255,10 +420,55 @@ pub async fn search_repositories_with_cache(
     Ok(result)
 }
 
+/// Add similarly named repositories of the owner to a `RepositoryNotFound` error
+///
+/// Costs one search request; other errors, and a failed search, leave the error as is.
+pub async fn with_suggestions(client: &ApiClient, base_url: &str, error: GhrError) -> GhrError {
+    let GhrError::RepositoryNotFound { owner, repo, .. } = error else {
+        return error;
+    };
+
+    let pattern = SearchPattern::UserAllRepos {
+        username: owner.clone(),
+    };
+    let suggestions = match search_repositories_with_cache(
+        client,
+        base_url,
+        &pattern,
+        constants::SUGGEST_CANDIDATES,
+        None,
+    )
+    .await
+    {
+        Ok(repositories) => {
+            let names = repositories.iter().map(|r| r.name.as_str());
+            crate::util::closest_matches(
+                &repo,
+                names,
+                constants::SUGGEST_MIN_SIMILARITY,
+                constants::MAX_SUGGESTIONS,
+            )
+            .into_iter()
+            .map(|name| format!("{}/{}", owner, name))
+            .collect()
+        }
+        Err(e) => {
+            jdebug!("No suggestions for {}/{}: {}", owner, repo, e);
+            Vec::new()
+        }
+    };
+
+    GhrError::RepositoryNotFound {
+        owner,
+        repo,
+        suggestions,
+    }
+}
+
 /// Validate that a repository exists and is accessible
 #[allow(dead_code)]
 pub async fn validate_repository(
-    client: &Client,
+    client: &ApiClient,
     owner: &str,
     repo: &str,
 ) -> Result<RepositoryInfo> {
@@ -267,7 +477,7 @@ pub async fn validate_repository(
 
 /// Validate that a repository exists and is accessible with custom base URL
 pub async fn validate_repository_with_base(
-    client: &Client,
+    client: &ApiClient,
     base_url: &str,
     owner: &str,
     repo: &str,
@@ -276,16 +486,17 @@ pub async fn validate_repository_with_base(
 
     jinfo!("Validating repository {}/{}...", owner, repo);
 
-    retry_with_backoff(|| async {
-        let response = client.get(&url).send().await?;
+    retry_with_backoff(client.retry_policy(), || async {
+        let response = client.fetch(&url).await?;
 
         if response.status().is_success() {
-            let repo_info: RepositoryInfo = response.json().await?;
+            let repo_info: RepositoryInfo = read_json(response).await?;
             Ok(repo_info)
         } else if response.status() == reqwest::StatusCode::NOT_FOUND {
             Err(GhrError::RepositoryNotFound {
                 owner: owner.to_string(),
                 repo: repo.to_string(),
+                suggestions: Vec::new(),
             })
         } else {
             Err(GhrError::GitHubApi(format!(
@@ -300,66 +511,66 @@ pub async fn validate_repository_with_base(
 /// Validate that a ref (branch/tag/commit) exists in a repository
 #[allow(dead_code)]
 pub async fn validate_ref(
-    client: &Client,
+    client: &ApiClient,
     owner: &str,
     repo: &str,
     ref_name: &str,
-) -> Result<String> {
+) -> Result<ResolvedRef> {
     validate_ref_with_base(client, constants::GITHUB_API_BASE, owner, repo, ref_name).await
 }
 
 /// Validate that a ref (branch/tag/commit) exists in a repository with custom base URL
+///
+/// Returns the ref together with its kind so that the checkout can use an
+/// unambiguous form even when a branch and a tag share the same name.
 pub async fn validate_ref_with_base(
-    client: &Client,
+    client: &ApiClient,
     base_url: &str,
     owner: &str,
     repo: &str,
     ref_name: &str,
-) -> Result<String> {
+) -> Result<ResolvedRef> {
+    let resolved = |kind| ResolvedRef {
+        kind,
+        name: ref_name.to_string(),
+    };
+
     jinfo!("Validating ref '{}'...", ref_name);
 
     // Try as branch first
     let branch_url = constants::endpoints::branch_with_base(base_url, owner, repo, ref_name);
 
-    let response = retry_with_backoff(|| async {
-        client
-            .get(&branch_url)
-            .send()
-            .await
-            .map_err(GhrError::Network)
+    let response = retry_with_backoff(client.retry_policy(), || async {
+        client.fetch(&branch_url).await
     })
     .await?;
 
     if response.status().is_success() {
-        return Ok("branch".to_string());
+        return Ok(resolved(RefKind::Branch));
     }
 
     // Try as tag
     let tag_url = constants::endpoints::tag_with_base(base_url, owner, repo, ref_name);
 
-    let response = retry_with_backoff(|| async {
-        client.get(&tag_url).send().await.map_err(GhrError::Network)
+    let response = retry_with_backoff(client.retry_policy(), || async {
+        client.fetch(&tag_url).await
     })
     .await?;
 
     if response.status().is_success() {
-        return Ok("tag".to_string());
+        return Ok(resolved(RefKind::Tag));
     }
 
     // Try as commit SHA
     let commit_url = constants::endpoints::commit_with_base(base_url, owner, repo, ref_name);
 
-    let response = retry_with_backoff(|| async {
-        client
-            .get(&commit_url)
-            .send()
-            .await
-            .map_err(GhrError::Network)
+    let response = retry_with_backoff(client.retry_policy(), || async {
+        client.fetch(&commit_url).await
     })
     .await?;
 
     if response.status().is_success() {
-        return Ok("commit".to_string());
+        return Ok(resolved(RefKind::Commit));
     }
 
     // Ref not found
@@ -372,7 +583,7 @@ pub async fn validate_ref_with_base(
 
 /// Fetch tags for a repository
 pub async fn get_repository_tags(
-    client: &Client,
+    client: &ApiClient,
     base_url: &str,
     owner: &str,
     repo: &str,
@@ -380,8 +591,8 @@ pub async fn get_repository_tags(
 ) -> Result<Vec<String>> {
     let url = constants::endpoints::tags_with_base(base_url, owner, repo, per_page);
 
-    retry_with_backoff(|| async {
-        let response = client.get(&url).send().await?;
+    retry_with_backoff(client.retry_policy(), || async {
+        let response = client.fetch(&url).await?;
 
         if !response.status().is_success() {
             // If tags endpoint fails, return empty list instead of error
@@ -395,12 +606,131 @@ pub async fn get_repository_tags(
             return Ok(Vec::new());
         }
 
-        let tags: Vec<Tag> = response.json().await?;
+        let tags: Vec<Tag> = read_json(response).await?;
         Ok(tags.into_iter().map(|t| t.name).collect())
     })
     .await
 }
 
+/// Tags of a repository without releases as stand-in releases (--tags-as-releases)
+///
+/// Fetches one page of up to `per_page` tags. The API lists tags by name, so
+/// they are sorted by version to put the latest first, like releases.
+pub async fn get_tags_as_releases(
+    client: &ApiClient,
+    base_url: &str,
+    repo: &str,
+    per_page: usize,
+) -> Result<Vec<Release>> {
+    let (owner, repo_name) = repo
+        .split_once('/')
+        .ok_or_else(|| GhrError::Generic(format!("Invalid repository format: {}", repo)))?;
+    let tags = get_repository_tags(client, base_url, owner, repo_name, per_page).await?;
+    let mut releases: Vec<Release> = tags.into_iter().map(Release::from_tag).collect();
+    crate::listing::sort_by_version(&mut releases);
+    Ok(releases)
+}
+
+/// Fetch the topics of a repository; like tags, failures give an empty list
+pub async fn get_repository_topics(
+    client: &ApiClient,
+    base_url: &str,
+    owner: &str,
+    repo: &str,
+) -> Result<Vec<String>> {
+    let url = constants::endpoints::topics_with_base(base_url, owner, repo);
+
+    retry_with_backoff(client.retry_policy(), || async {
+        let response = client.fetch(&url).await?;
+
+        if !response.status().is_success() {
+            jdebug!(
+                "Failed to fetch topics for {}/{}: HTTP {}",
+                owner,
+                repo,
+                response.status()
+            );
+            return Ok(Vec::new());
+        }
+
+        let topics: Topics = read_json(response).await?;
+        Ok(topics.names)
+    })
+    .await
+}
+
+/// Fill in the topics of search results, fetching up to `concurrency` at a time
+///
+/// Results keep their order; a repository whose topics cannot be fetched keeps
+/// the topics it already had.
+pub async fn add_topics(
+    client: &ApiClient,
+    base_url: &str,
+    repositories: Vec<Repository>,
+    concurrency: usize,
+) -> Vec<Repository> {
+    stream::iter(repositories)
+        .map(|mut repo| async move {
+            if let Some((owner, name)) = repo.full_name.split_once('/') {
+                match get_repository_topics(client, base_url, owner, name).await {
+                    Ok(topics) if !topics.is_empty() => repo.topics = topics,
+                    Ok(_) => {}
+                    Err(e) => jdebug!("Failed to fetch topics for {}: {}", repo.full_name, e),
+                }
+            }
+            repo
+        })
+        .buffered(concurrency.max(1))
+        .collect()
+        .await
+}
+
+/// List up to `limit` branches of a repository with their head commits, following
+/// the pages of the branch list
+pub async fn list_branches(
+    client: &ApiClient,
+    base_url: &str,
+    owner: &str,
+    repo: &str,
+    per_page: usize,
+    limit: usize,
+) -> Result<Vec<Branch>> {
+    collect_pages(per_page, limit, |page| {
+        let url =
+            constants::endpoints::branches_page_with_base(base_url, owner, repo, per_page, page);
+        async move {
+            jdebug!("Fetching branches page {}", page);
+            retry_with_backoff(client.retry_policy(), || async {
+                let response = client.fetch(&url).await?;
+
+                if !response.status().is_success() {
+                    return Err(GhrError::GitHubApi(format!(
+                        "Failed to fetch branches: HTTP {}",
+                        response.status()
+                    )));
+                }
+
+                read_json::<Vec<Branch>>(response).await
+            })
+            .await
+        }
+    })
+    .await
+}
+
+/// Check whether a repository is empty, i.e. has no branches yet
+///
+/// Cloning an empty repository succeeds but leaves an empty working tree.
+pub async fn is_empty_repository(
+    client: &ApiClient,
+    base_url: &str,
+    owner: &str,
+    repo: &str,
+) -> Result<bool> {
+    let branches = list_branches(client, base_url, owner, repo, 1, 1).await?;
+    Ok(branches.is_empty())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -477,6 +807,38 @@ mod tests {
         matches!(result.unwrap_err(), GhrError::InvalidSearchPattern(_));
     }
 
+    #[test]
+    fn test_parse_api_error_message_search_rejected() {
+        let body = r#"{
+            "message": "Validation Failed",
+            "errors": [
+                {
+                    "message": "The search is longer than 256 characters.",
+                    "resource": "Search",
+                    "field": "q",
+                    "code": "invalid"
+                }
+            ],
+            "documentation_url": "https://docs.github.com/v3/search/"
+        }"#;
+
+        assert_eq!(
+            parse_api_error_message(body),
+            "Validation Failed (The search is longer than 256 characters.)"
+        );
+    }
+
+    #[test]
+    fn test_parse_api_error_message_code_only() {
+        let body = r#"{"message": "Validation Failed", "errors": [{"code": "missing"}]}"#;
+        assert_eq!(parse_api_error_message(body), "Validation Failed (missing)");
+    }
+
+    #[test]
+    fn test_parse_api_error_message_not_json() {
+        assert_eq!(parse_api_error_message("  Bad gateway\n"), "Bad gateway");
+    }
+
     #[test]
     fn test_parse_search_pattern_with_leading_trailing_spaces() {
         let result = parse_search_pattern("  rust-lang/compiler  ");
@@ -489,4 +851,553 @@ mod tests {
             _ => panic!("Expected UserWithKeyword pattern"),
         }
     }
+
+    async fn renamed_repo_server() -> wiremock::MockServer {
+        use wiremock::matchers::path;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(path("/repos/old-owner/old-name/releases"))
+            .respond_with(ResponseTemplate::new(301).insert_header(
+                "Location",
+                format!("{}/repositories/42/releases", server.uri()).as_str(),
+            ))
+            .mount(&server)
+            .await;
+        Mock::given(path("/repositories/42"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "name": "new-name",
+                "full_name": "new-owner/new-name",
+                "default_branch": "main",
+                "private": false
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(path("/repositories/42/releases"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(serde_json::json!([{
+                    "tag_name": "v1.0.0",
+                    "name": "v1.0.0",
+                    "published_at": "2024-01-01T00:00:00Z",
+                    "assets": [],
+                    "body": null
+                }])),
+            )
+            .mount(&server)
+            .await;
+        server
+    }
+
+    fn api_client(follow_renames: bool) -> ApiClient {
+        use crate::http::ApiOptions;
+
+        let client = crate::http::build_client(
+            reqwest::header::HeaderMap::new(),
+            &crate::http::ClientOptions::default(),
+        )
+        .unwrap();
+        ApiClient::new(
+            client,
+            ApiOptions {
+                follow_renames,
+                ..Default::default()
+            },
+        )
+    }
+
+    #[tokio::test]
+    async fn test_get_all_releases_paginates_up_to_limit() {
+        use wiremock::matchers::{path, query_param};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let release = |n: usize| {
+            serde_json::json!({
+                "tag_name": format!("v{}", n),
+                "name": null,
+                "published_at": "2024-01-01T00:00:00Z",
+                "assets": [],
+                "body": null
+            })
+        };
+        let server = MockServer::start().await;
+        for page in 1..=3 {
+            let releases: Vec<_> = (1..=2).map(|i| release((page - 1) * 2 + i)).collect();
+            Mock::given(path("/repos/owner/repo/releases"))
+                .and(query_param("per_page", "2"))
+                .and(query_param("page", page.to_string()))
+                .respond_with(ResponseTemplate::new(200).set_body_json(releases))
+                .mount(&server)
+                .await;
+        }
+        // Short last page ends the listing
+        Mock::given(path("/repos/owner/repo/releases"))
+            .and(query_param("page", "4"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(vec![release(7)]))
+            .mount(&server)
+            .await;
+
+        let api = api_client(true);
+        let tags = |releases: Vec<Release>| -> Vec<String> {
+            releases.into_iter().map(|r| r.tag_name).collect()
+        };
+
+        // Stops at the cap without fetching further pages
+        let releases = get_all_releases(&api, &server.uri(), "owner/repo", 2, 5)
+            .await
+            .unwrap();
+        assert_eq!(tags(releases), vec!["v1", "v2", "v3", "v4", "v5"]);
+        assert_eq!(server.received_requests().await.unwrap().len(), 3);
+
+        let releases = get_all_releases(&api, &server.uri(), "owner/repo", 2, 100)
+            .await
+            .unwrap();
+        assert_eq!(
+            tags(releases),
+            vec!["v1", "v2", "v3", "v4", "v5", "v6", "v7"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_release_assets_beyond_first_page() {
+        use wiremock::matchers::{path, query_param};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let asset = |n: usize| {
+            serde_json::json!({
+                "id": n,
+                "name": format!("asset-{}.tar.gz", n),
+                "browser_download_url": format!("https://example.com/asset-{}.tar.gz", n),
+                "size": 1024,
+                "download_count": 0
+            })
+        };
+        let assets =
+            |range: std::ops::RangeInclusive<usize>| -> Vec<_> { range.map(asset).collect() };
+
+        let server = MockServer::start().await;
+        let assets_url = format!("{}/repos/owner/repo/releases/1/assets", server.uri());
+        Mock::given(path("/repos/owner/repo/releases/tags/v1.0.0"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "tag_name": "v1.0.0",
+                "name": "v1.0.0",
+                "published_at": "2024-01-01T00:00:00Z",
+                "assets": assets(1..=100),
+                "assets_url": assets_url,
+                "body": null
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(path("/repos/owner/repo/releases/1/assets"))
+            .and(query_param("page", "1"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(assets(1..=100)))
+            .mount(&server)
+            .await;
+        Mock::given(path("/repos/owner/repo/releases/1/assets"))
+            .and(query_param("page", "2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(assets(101..=130)))
+            .mount(&server)
+            .await;
+
+        let releases = get_release_info_with_cache(
+            &api_client(true),
+            &server.uri(),
+            "owner/repo",
+            Some("v1.0.0"),
+            None,
+        )
+        .await
+        .unwrap();
+        let assets = &releases[0].assets;
+        assert_eq!(assets.len(), 130);
+        assert_eq!(assets[129].name, "asset-130.tar.gz");
+    }
+
+    #[tokio::test]
+    async fn test_download_from_repository_without_releases() {
+        use wiremock::matchers::path;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(path("/repos/owner/repo/releases"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([])))
+            .mount(&server)
+            .await;
+
+        let releases =
+            get_release_info_with_cache(&api_client(true), &server.uri(), "owner/repo", None, None)
+                .await
+                .unwrap();
+        for spec in ["latest", "latest-stable", "pre-latest", "v1.0.0"] {
+            let err = crate::listing::select_release(&releases, spec).unwrap_err();
+            assert!(matches!(err, GhrError::NoReleases), "{}: {}", spec, err);
+            assert_eq!(err.exit_code(), crate::errors::EXIT_NOT_FOUND);
+            assert!(err.to_string().contains("--ref <TAG>"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tags_as_releases() {
+        use crate::cli::ReleaseColumn;
+        use crate::output::{releases_table, DateStyle};
+        use wiremock::matchers::{path, query_param};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(path("/repos/owner/repo/releases"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([])))
+            .mount(&server)
+            .await;
+        Mock::given(path("/repos/owner/repo/tags"))
+            .and(query_param("per_page", "100"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([
+                { "name": "v1.9.0", "commit": { "sha": "c5b97d5ae6c19d5c5df71a34c7fbeeda2479ccbc" } },
+                { "name": "v1.10.0", "commit": { "sha": "6dcb09b5b57875f334f61aebed695e2e4193db5e" } }
+            ])))
+            .mount(&server)
+            .await;
+
+        let api = api_client(true);
+        let releases = get_release_info_with_cache(&api, &server.uri(), "owner/repo", None, None)
+            .await
+            .unwrap();
+        assert!(releases.is_empty());
+
+        let tags = get_tags_as_releases(&api, &server.uri(), "owner/repo", 100)
+            .await
+            .unwrap();
+        let names: Vec<&str> = tags.iter().map(|r| r.tag_name.as_str()).collect();
+        assert_eq!(names, vec!["v1.10.0", "v1.9.0"]);
+        assert!(tags.iter().all(|r| r.assets.is_empty()));
+        assert_eq!(
+            crate::listing::select_release(&tags, "latest")
+                .unwrap()
+                .tag_name,
+            "v1.10.0"
+        );
+
+        let listed: Vec<&Release> = tags.iter().collect();
+        let table =
+            releases_table(&listed, ReleaseColumn::DEFAULT, DateStyle::Absolute).to_string();
+        assert!(table.contains("v1.10.0"));
+        assert!(table.contains("v1.9.0"));
+    }
+
+    #[tokio::test]
+    async fn test_renamed_repository_followed() {
+        let server = renamed_repo_server().await;
+
+        let releases = get_release_info_with_cache(
+            &api_client(true),
+            &server.uri(),
+            "old-owner/old-name",
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(releases.len(), 1);
+        assert_eq!(releases[0].tag_name, "v1.0.0");
+    }
+
+    #[tokio::test]
+    async fn test_renamed_repository_not_followed() {
+        let server = renamed_repo_server().await;
+
+        let result = get_release_info_with_cache(
+            &api_client(false),
+            &server.uri(),
+            "old-owner/old-name",
+            None,
+            None,
+        )
+        .await;
+        match result {
+            Err(GhrError::RepositoryMoved { from, to }) => {
+                assert_eq!(from, "old-owner/old-name");
+                assert_eq!(to, "new-owner/new-name");
+            }
+            other => panic!("Expected RepositoryMoved, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_branches_follows_pages() {
+        use wiremock::matchers::{path, query_param};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let branch = |name: &str| serde_json::json!({ "name": name, "commit": { "sha": "0123" } });
+        let server = MockServer::start().await;
+        Mock::given(path("/repos/owner/repo/branches"))
+            .and(query_param("page", "1"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!([branch("main"), branch("dev")])),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(path("/repos/owner/repo/branches"))
+            .and(query_param("page", "2"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(serde_json::json!([branch("release")])),
+            )
+            .mount(&server)
+            .await;
+
+        let api = api_client(true);
+        let names = |branches: Vec<Branch>| -> Vec<String> {
+            branches.into_iter().map(|branch| branch.name).collect()
+        };
+        let all = list_branches(&api, &server.uri(), "owner", "repo", 2, usize::MAX)
+            .await
+            .unwrap();
+        assert_eq!(names(all), vec!["main", "dev", "release"]);
+        let first = list_branches(&api, &server.uri(), "owner", "repo", 2, 1)
+            .await
+            .unwrap();
+        assert_eq!(names(first), vec!["main"]);
+    }
+
+    #[tokio::test]
+    async fn test_offline_mode_uses_cache_only() {
+        let client = crate::http::build_client(
+            reqwest::header::HeaderMap::new(),
+            &crate::http::ClientOptions::default(),
+        )
+        .unwrap();
+        let api = ApiClient::new(
+            client,
+            crate::http::ApiOptions {
+                offline: true,
+                ..Default::default()
+            },
+        );
+        let dir = tempfile::tempdir().unwrap();
+        let cache = Cache::in_dir(dir.path()).without_expiry();
+        // Unreachable base URL: any request that gets sent would fail differently
+        let base_url = "http://offline.invalid";
+
+        let cached = vec![Release {
+            tag_name: "v1.0.0".to_string(),
+            ..Default::default()
+        }];
+        cache
+            .set(&format!("releases:offline/hit:{}", base_url), &cached)
+            .await
+            .unwrap();
+
+        let hit = get_release_info_with_cache(&api, base_url, "offline/hit", None, Some(&cache))
+            .await
+            .unwrap();
+        assert_eq!(hit[0].tag_name, "v1.0.0");
+
+        let miss =
+            get_release_info_with_cache(&api, base_url, "offline/miss", None, Some(&cache)).await;
+        assert!(matches!(miss, Err(GhrError::Offline(_))));
+    }
+
+    #[tokio::test]
+    async fn test_is_empty_repository() {
+        use wiremock::matchers::path;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(path("/repos/owner/empty/branches"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!([])))
+            .mount(&server)
+            .await;
+        Mock::given(path("/repos/owner/full/branches"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(serde_json::json!([{
+                    "name": "main",
+                    "commit": { "sha": "0123456789abcdef" }
+                }])),
+            )
+            .mount(&server)
+            .await;
+
+        let api = api_client(true);
+        assert!(is_empty_repository(&api, &server.uri(), "owner", "empty")
+            .await
+            .unwrap());
+        assert!(!is_empty_repository(&api, &server.uri(), "owner", "full")
+            .await
+            .unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_add_topics() {
+        use wiremock::matchers::path;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(path("/repos/owner/tool/topics"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({ "names": ["cli", "rust"] })),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(path("/repos/owner/gone/topics"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+
+        let search: SearchResponse = serde_json::from_value(serde_json::json!({
+            "total_count": 2,
+            "items": [
+                {"name": "tool", "full_name": "owner/tool", "description": null,
+                 "stargazers_count": 5, "html_url": "https://github.com/owner/tool",
+                 "owner": {"login": "owner"}, "private": false},
+                {"name": "gone", "full_name": "owner/gone", "description": null,
+                 "stargazers_count": 1, "html_url": "https://github.com/owner/gone",
+                 "owner": {"login": "owner"}, "private": false}
+            ]
+        }))
+        .unwrap();
+
+        let api = api_client(true);
+        let repos = add_topics(&api, &server.uri(), search.items, 4).await;
+        assert_eq!(repos[0].full_name, "owner/tool");
+        assert_eq!(repos[0].topics, vec!["cli", "rust"]);
+        assert_eq!(repos[1].full_name, "owner/gone");
+        assert!(repos[1].topics.is_empty());
+
+        let json = serde_json::to_value(&repos).unwrap();
+        assert_eq!(json[0]["topics"], serde_json::json!(["cli", "rust"]));
+        assert!(json[1].get("topics").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_retry_after_connection_closed_mid_body() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(AtomicUsize::new(0));
+
+        let served = requests.clone();
+        tokio::spawn(async move {
+            let body = r#"[{"tag_name":"v1.0.0","name":"v1.0.0","published_at":"2024-01-01T00:00:00Z","assets":[],"body":null}]"#;
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut request = [0u8; 4096];
+                let _ = socket.read(&mut request).await;
+                // The first response announces the full length but stops halfway
+                let sent = if served.fetch_add(1, Ordering::SeqCst) == 0 {
+                    body.len() / 2
+                } else {
+                    body.len()
+                };
+                let head = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    body.len()
+                );
+                socket.write_all(head.as_bytes()).await.unwrap();
+                socket.write_all(&body.as_bytes()[..sent]).await.unwrap();
+            }
+        });
+
+        let client = crate::http::build_client(
+            reqwest::header::HeaderMap::new(),
+            &crate::http::ClientOptions::default(),
+        )
+        .unwrap();
+        let api = ApiClient::new(
+            client,
+            crate::http::ApiOptions {
+                retry: RetryPolicy {
+                    base_delay: std::time::Duration::from_millis(10),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        );
+
+        let releases = get_release_info_with_cache(&api, &base_url, "owner/repo", None, None)
+            .await
+            .unwrap();
+        assert_eq!(releases[0].tag_name, "v1.0.0");
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_negative_cache_avoids_repeated_404() {
+        use wiremock::matchers::path;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(path("/repos/owner/missing/releases"))
+            .respond_with(ResponseTemplate::new(404))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let api = api_client(true);
+        let dir = tempfile::tempdir().unwrap();
+        let cache = Cache::in_dir(dir.path()).with_negative_ttl(std::time::Duration::from_secs(60));
+        for _ in 0..2 {
+            let result = get_release_info_with_cache(
+                &api,
+                &server.uri(),
+                "owner/missing",
+                None,
+                Some(&cache),
+            )
+            .await;
+            assert!(matches!(result, Err(GhrError::RepositoryNotFound { .. })));
+        }
+        server.verify().await;
+    }
+
+    #[tokio::test]
+    async fn test_not_found_with_suggestions() {
+        use wiremock::matchers::{path, query_param};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(path("/repos/saimizi/gh_relase"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+        let repo = |name: &str| {
+            serde_json::json!({
+                "name": name,
+                "full_name": format!("saimizi/{}", name),
+                "description": null,
+                "stargazers_count": 0,
+                "html_url": format!("https://github.com/saimizi/{}", name),
+                "owner": { "login": "saimizi" },
+                "private": false
+            })
+        };
+        Mock::given(path("/search/repositories"))
+            .and(query_param("q", "user:saimizi"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "items": [repo("dotfiles"), repo("gh_release"), repo("gh_releases_test")]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let api = api_client(false);
+        let error = validate_repository_with_base(&api, &server.uri(), "saimizi", "gh_relase")
+            .await
+            .unwrap_err();
+        let error = with_suggestions(&api, &server.uri(), error).await;
+        match &error {
+            GhrError::RepositoryNotFound { suggestions, .. } => assert_eq!(
+                suggestions,
+                &["saimizi/gh_release", "saimizi/gh_releases_test"]
+            ),
+            other => panic!("unexpected error: {:?}", other),
+        }
+        assert_eq!(
+            error.to_string(),
+            "Repository 'saimizi/gh_relase' not found or access denied. \
+             Did you mean: saimizi/gh_release, saimizi/gh_releases_test?"
+        );
+        server.verify().await;
+    }
 }
@@ -0,0 +1,159 @@
+use crate::cache::Cache;
+use crate::constants::gitlab_endpoints;
+use crate::errors::{GhrError, Result};
+use crate::github::retry_with_backoff;
+use crate::http::{collect_pages, read_json, ApiClient};
+use crate::models::{GitLabRelease, Release};
+
+/// Fetch release information from GitLab with optional caching
+///
+/// `repo` is the project path, e.g. "group/project" or "group/subgroup/project".
+pub async fn get_release_info_with_cache(
+    client: &ApiClient,
+    base_url: &str,
+    repo: &str,
+    tag: Option<&str>,
+    cache: Option<&Cache>,
+) -> Result<Vec<Release>> {
+    let cache_key = if let Some(tag) = tag {
+        format!("gitlab-releases:{}:{}:{}", repo, tag, base_url)
+    } else {
+        format!("gitlab-releases:{}:{}", repo, base_url)
+    };
+
+    if let Some(cache) = cache {
+        if let Some(cached) = cache.get::<Vec<Release>>(&cache_key).await {
+            return Ok(cached);
+        }
+    }
+
+    let url = if let Some(tag) = tag {
+        gitlab_endpoints::release_by_tag_with_base(base_url, repo, tag)
+    } else {
+        gitlab_endpoints::releases_with_base(base_url, repo)
+    };
+
+    let result = retry_with_backoff(client.retry_policy(), || async {
+        let response = client.fetch(&url).await?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(match tag {
+                Some(tag) => GhrError::ReleaseNotFound {
+                    tag: tag.to_string(),
+                },
+                None => GhrError::GitLabApi(format!("Project '{}' not found", repo)),
+            });
+        }
+        if !response.status().is_success() {
+            return Err(GhrError::GitLabApi(format!(
+                "Failed to fetch releases: HTTP {}",
+                response.status()
+            )));
+        }
+
+        if tag.is_some() {
+            let release: GitLabRelease = read_json(response).await?;
+            Ok(vec![release.into()])
+        } else {
+            let releases: Vec<GitLabRelease> = read_json(response).await?;
+            Ok(releases.into_iter().map(Release::from).collect())
+        }
+    })
+    .await?;
+
+    if let Some(cache) = cache {
+        let _ = cache.set(&cache_key, &result).await;
+    }
+
+    Ok(result)
+}
+
+/// Fetch up to `limit` releases of a project, following the pages of the release list
+pub async fn get_all_releases(
+    client: &ApiClient,
+    base_url: &str,
+    repo: &str,
+    per_page: usize,
+    limit: usize,
+) -> Result<Vec<Release>> {
+    collect_pages(per_page, limit, |page| {
+        let url = gitlab_endpoints::releases_page_with_base(base_url, repo, per_page, page);
+        async move {
+            retry_with_backoff(client.retry_policy(), || async {
+                let response = client.fetch(&url).await?;
+                if response.status() == reqwest::StatusCode::NOT_FOUND {
+                    return Err(GhrError::GitLabApi(format!("Project '{}' not found", repo)));
+                }
+                if !response.status().is_success() {
+                    return Err(GhrError::GitLabApi(format!(
+                        "Failed to fetch releases: HTTP {}",
+                        response.status()
+                    )));
+                }
+                let releases: Vec<GitLabRelease> = read_json(response).await?;
+                Ok(releases.into_iter().map(Release::from).collect())
+            })
+            .await
+        }
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::{build_client, ApiOptions, ClientOptions};
+    use wiremock::matchers::path;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_gitlab_endpoints() {
+        assert_eq!(
+            gitlab_endpoints::releases_with_base("https://gitlab.com/api/v4", "group/project"),
+            "https://gitlab.com/api/v4/projects/group%2Fproject/releases"
+        );
+        assert_eq!(
+            gitlab_endpoints::release_by_tag_with_base(
+                "https://gitlab.example.com/api/v4",
+                "group/sub/project",
+                "release/1.0"
+            ),
+            "https://gitlab.example.com/api/v4/projects/group%2Fsub%2Fproject/releases/release%2F1.0"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_gitlab_releases() {
+        let server = MockServer::start().await;
+        Mock::given(path("/projects/group%2Fproject/releases"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(serde_json::json!([{
+                    "tag_name": "v1.0.0",
+                    "name": "v1.0.0",
+                    "released_at": "2024-03-01T10:00:00Z",
+                    "assets": { "links": [{
+                        "id": 1,
+                        "name": "app.tar.gz",
+                        "url": "https://gitlab.com/group/project/-/package_files/1/download"
+                    }] }
+                }])),
+            )
+            .mount(&server)
+            .await;
+
+        let client =
+            build_client(reqwest::header::HeaderMap::new(), &ClientOptions::default()).unwrap();
+        let api = ApiClient::new(client, ApiOptions::default());
+        let releases =
+            get_release_info_with_cache(&api, &server.uri(), "group/project", None, None)
+                .await
+                .unwrap();
+
+        assert_eq!(releases.len(), 1);
+        assert_eq!(releases[0].tag_name, "v1.0.0");
+        assert_eq!(
+            releases[0].assets[0].browser_download_url,
+            "https://gitlab.com/group/project/-/package_files/1/download"
+        );
+    }
+}
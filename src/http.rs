@@ -0,0 +1,791 @@
+use crate::auth::{self, TokenPool};
+use crate::cli::TlsVersion;
+use crate::constants;
+use crate::errors::{GhrError, Result};
+use crate::models::RepositoryInfo;
+use jlogger_tracing::{jdebug, jtrace, jwarn};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, LOCATION};
+use reqwest::redirect::Policy;
+use reqwest::{Client, IntoUrl, RequestBuilder, Response, Url};
+use serde::de::DeserializeOwned;
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Options for building the HTTP client shared by all requests
+#[derive(Debug, Clone, Default)]
+pub struct ClientOptions {
+    /// Connect over IPv4 only
+    pub ipv4_only: bool,
+    /// Maximum number of redirects to follow automatically; `None` returns
+    /// redirect responses to the caller instead
+    pub max_redirects: Option<usize>,
+    /// Minimum TLS version to accept; `None` keeps the library default
+    pub min_tls: Option<TlsVersion>,
+    /// Ask for gzip or brotli compressed responses and decompress them
+    pub compression: bool,
+}
+
+/// Build the HTTP client with the given default headers and options
+pub fn build_client(headers: HeaderMap, options: &ClientOptions) -> Result<Client> {
+    let mut builder = Client::builder()
+        .default_headers(headers)
+        .gzip(options.compression)
+        .brotli(options.compression);
+
+    // reqwest drops the Authorization header when a redirect leaves the
+    // original host, so the token is never sent on to the storage backend
+    builder = builder.redirect(match options.max_redirects {
+        // `Policy::limited` counts the original URL too; allow exactly `max` hops
+        Some(max) => Policy::custom(move |attempt| {
+            if attempt.previous().len() > max {
+                attempt.error(format!("too many redirects (limit {})", max))
+            } else {
+                attempt.follow()
+            }
+        }),
+        None => Policy::none(),
+    });
+
+    if options.ipv4_only {
+        // With only an IPv4 local address bound, the connector skips IPv6
+        // addresses returned by the resolver instead of trying them first
+        builder = builder.local_address(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+    }
+
+    if let Some(min_tls) = options.min_tls {
+        // The native TLS backend cannot enforce TLS 1.3 as a minimum
+        builder = builder.use_rustls_tls().min_tls_version(min_tls.into());
+    }
+
+    Ok(builder.build()?)
+}
+
+/// Whether credentials for the API at `api_url` may be sent with a request to `url`
+///
+/// That is the API host itself and, for an `api.` host such as api.github.com,
+/// the site it belongs to (github.com), which serves release downloads.
+pub fn is_api_host(url: &str, api_url: &str) -> bool {
+    let parse = |url: &str| origin(&Url::parse(url).ok()?);
+    let (Some((host, port)), Some((api_host, api_port))) = (parse(url), parse(api_url)) else {
+        return false;
+    };
+    port == api_port && (host == api_host || api_host.strip_prefix("api.") == Some(host.as_str()))
+}
+
+/// A token header that is sent per request, and only to the API host
+///
+/// Used for the GitLab `PRIVATE-TOKEN`: reqwest only drops `Authorization`
+/// when a redirect leaves the host, so a custom header can't be a default
+/// header of a client that also downloads release links, which may point to
+/// any host.
+#[derive(Clone)]
+pub struct HostToken {
+    api_url: String,
+    name: HeaderName,
+    value: HeaderValue,
+}
+
+impl HostToken {
+    pub fn new(api_url: &str, name: HeaderName, mut value: HeaderValue) -> Self {
+        value.set_sensitive(true);
+        Self {
+            api_url: api_url.to_string(),
+            name,
+            value,
+        }
+    }
+
+    /// Add the token to a request for `url` if it goes to the API host
+    pub fn apply(&self, request: RequestBuilder, url: &str) -> RequestBuilder {
+        if is_api_host(url, &self.api_url) {
+            request.header(self.name.clone(), self.value.clone())
+        } else {
+            request
+        }
+    }
+}
+
+impl std::fmt::Debug for HostToken {
+    // Never print the token itself
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HostToken")
+            .field("api_url", &self.api_url)
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+/// Lowercase host and port of a URL, which decide where credentials may go
+fn origin(url: &Url) -> Option<(String, Option<u16>)> {
+    Some((
+        url.host_str()?.to_ascii_lowercase(),
+        url.port_or_known_default(),
+    ))
+}
+
+/// The client for a request to `url` outside the API: `authenticated` for the
+/// API host, see [`is_api_host`], and `public` for any other host
+pub fn client_for<'a>(
+    url: &str,
+    api_url: &str,
+    authenticated: &'a Client,
+    public: &'a Client,
+) -> &'a Client {
+    if is_api_host(url, api_url) {
+        authenticated
+    } else {
+        public
+    }
+}
+
+/// Read a JSON response body
+///
+/// A body that ends in the middle of the JSON, such as when the connection
+/// drops, fails with the retriable [`GhrError::TruncatedResponse`], while a
+/// complete body that doesn't match the expected type is a
+/// [`GhrError::JsonError`].
+pub async fn read_json<T: DeserializeOwned>(response: Response) -> Result<T> {
+    let url = response.url().to_string();
+    let bytes = response.bytes().await?;
+    parse_json(&bytes, &url)
+}
+
+/// Collect up to `limit` items from a paginated list
+///
+/// `fetch_page` is called with page numbers starting at 1 until a page holds
+/// fewer than `per_page` items or `limit` items have been collected.
+pub async fn collect_pages<T, F, Fut>(
+    per_page: usize,
+    limit: usize,
+    fetch_page: F,
+) -> Result<Vec<T>>
+where
+    F: Fn(usize) -> Fut,
+    Fut: std::future::Future<Output = Result<Vec<T>>>,
+{
+    let mut items = Vec::new();
+    let mut page = 1;
+    while items.len() < limit {
+        let batch = fetch_page(page).await?;
+        let last = batch.len() < per_page;
+        items.extend(batch);
+        if last {
+            break;
+        }
+        page += 1;
+    }
+    items.truncate(limit);
+    Ok(items)
+}
+
+fn parse_json<T: DeserializeOwned>(bytes: &[u8], url: &str) -> Result<T> {
+    serde_json::from_slice(bytes).map_err(|e| {
+        if e.is_eof() {
+            GhrError::TruncatedResponse {
+                url: url.to_string(),
+            }
+        } else {
+            GhrError::JsonError(e)
+        }
+    })
+}
+
+/// Retry settings for GitHub API requests
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Maximum number of retries after the first attempt
+    pub max_retries: u32,
+    /// Delay before the first retry, doubled for each further retry
+    pub base_delay: Duration,
+    /// Upper bound for a single delay
+    pub max_delay: Duration,
+    /// Total time after which no further retry is attempted
+    pub budget: Option<Duration>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: constants::retry::MAX_RETRIES,
+            base_delay: Duration::from_secs(constants::retry::BASE_DELAY_SECS),
+            max_delay: Duration::from_secs(constants::retry::MAX_DELAY_SECS),
+            budget: None,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before retry number `attempt` (starting at 0), or `None` to give up
+    ///
+    /// `elapsed` is the time spent since the first attempt; a retry whose delay
+    /// would end past the budget is not attempted.
+    pub fn delay_for(&self, attempt: u32, elapsed: Duration) -> Option<Duration> {
+        if attempt >= self.max_retries {
+            return None;
+        }
+
+        let delay = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt))
+            .min(self.max_delay);
+
+        match self.budget {
+            Some(budget) if elapsed.saturating_add(delay) > budget => None,
+            _ => Some(delay),
+        }
+    }
+}
+
+/// Client for GitHub API metadata requests
+///
+/// Applies the API timeout to each request, separately from the timeout used
+/// for asset downloads. The wrapped client must not follow redirects, so that
+/// a renamed repository can be detected and reported by [`ApiClient::fetch`].
+#[derive(Debug, Clone)]
+pub struct ApiClient {
+    client: Client,
+    options: ApiOptions,
+}
+
+/// Settings for GitHub API metadata requests
+#[derive(Debug, Clone, Default)]
+pub struct ApiOptions {
+    /// Timeout for each request
+    pub timeout: Option<Duration>,
+    /// Retry settings for failed requests
+    pub retry: RetryPolicy,
+    /// Continue at the new location when a repository was renamed
+    pub follow_renames: bool,
+    /// Never send requests; only cached responses are available
+    pub offline: bool,
+    /// Tokens to rotate between per request, instead of the client's default token
+    pub tokens: Option<Arc<TokenPool>>,
+    /// Token to add to each request instead of a default header (GitLab)
+    pub host_token: Option<HostToken>,
+}
+
+impl ApiClient {
+    pub fn new(client: Client, options: ApiOptions) -> Self {
+        Self { client, options }
+    }
+
+    /// Retry settings for requests made through this client
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.options.retry
+    }
+
+    /// Start a GET request with the API timeout applied
+    pub fn get<U: IntoUrl>(&self, url: U) -> RequestBuilder {
+        let request = self.client.get(url);
+        match self.options.timeout {
+            Some(timeout) => request.timeout(timeout),
+            None => request,
+        }
+    }
+
+    /// Send a GET request, following a single redirect on the same host
+    ///
+    /// The client sends the token with every request, so a redirect to another
+    /// host fails instead of handing the token to that host.
+    ///
+    /// Fails with [`GhrError::Offline`] without sending anything in offline mode.
+    ///
+    /// GitHub answers requests for a renamed or transferred repository with a
+    /// redirect to `/repositories/{id}/...`. The new name is logged and the
+    /// request continues at the new location, or fails with
+    /// [`GhrError::RepositoryMoved`] when renames are not to be followed.
+    pub async fn fetch(&self, url: &str) -> Result<Response> {
+        if self.options.offline {
+            return Err(GhrError::Offline(format!(
+                "no cached response for {}. Run the command online with --cache first",
+                url
+            )));
+        }
+
+        let response = self.send(url).await?;
+        if !response.status().is_redirection() {
+            return Ok(response);
+        }
+
+        let location = response
+            .headers()
+            .get(LOCATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| response.url().join(value).ok())
+            .ok_or_else(|| {
+                GhrError::GitHubApi(format!(
+                    "HTTP {} without a valid Location for '{}'",
+                    response.status(),
+                    url
+                ))
+            })?;
+        if origin(&location).is_none() || origin(&location) != origin(response.url()) {
+            return Err(GhrError::GitHubApi(format!(
+                "HTTP {} for '{}' redirects to another host: {}",
+                response.status(),
+                url,
+                location
+            )));
+        }
+
+        if let Some(repository_url) = moved_repository_url(&location) {
+            let from = repo_from_api_url(url).unwrap_or_else(|| url.to_string());
+            let to = self
+                .repository_name(repository_url)
+                .await
+                .unwrap_or_else(|| location.to_string());
+
+            if !self.options.follow_renames {
+                return Err(GhrError::RepositoryMoved { from, to });
+            }
+            jwarn!("Repository '{}' has moved to '{}'", from, to);
+        } else {
+            jdebug!("Following redirect to {}", location);
+        }
+
+        self.send(location.as_str()).await
+    }
+
+    /// Send a GET request with the next pooled token, if any, logging its status and elapsed time at TRACE level
+    async fn send(&self, url: &str) -> Result<Response> {
+        let mut request = self.get(url);
+        let token = self.options.tokens.as_ref().map(|pool| pool.next_token());
+        if let Some(token) = token {
+            request = request.header(AUTHORIZATION, auth::bearer(token)?);
+        }
+        if let Some(host_token) = &self.options.host_token {
+            request = host_token.apply(request, url);
+        }
+
+        let start = Instant::now();
+        let result = request.send().await;
+        match &result {
+            Ok(response) => jtrace!(
+                "GET {} -> {} in {:?}",
+                url,
+                response.status(),
+                start.elapsed()
+            ),
+            Err(e) => jtrace!("GET {} failed after {:?}: {}", url, start.elapsed(), e),
+        }
+        if let (Some(pool), Some(token), Ok(response)) = (&self.options.tokens, token, &result) {
+            pool.record(token, response.headers());
+        }
+        Ok(result?)
+    }
+
+    /// Look up the current "owner/repo" name of a repository by its API URL
+    async fn repository_name(&self, repository_url: Url) -> Option<String> {
+        let response = self.send(repository_url.as_str()).await.ok()?;
+        if !response.status().is_success() {
+            return None;
+        }
+        let info: RepositoryInfo = read_json(response).await.ok()?;
+        Some(info.full_name)
+    }
+}
+
+/// Extract "owner/repo" from an API URL such as `.../repos/owner/repo/releases`
+fn repo_from_api_url(url: &str) -> Option<String> {
+    let (_, path) = url.split_once("/repos/")?;
+    let mut segments = path.split(['/', '?']);
+    let owner = segments.next().filter(|s| !s.is_empty())?;
+    let repo = segments.next().filter(|s| !s.is_empty())?;
+    Some(format!("{}/{}", owner, repo))
+}
+
+/// The `/repositories/{id}` URL of a moved repository's redirect target
+fn moved_repository_url(location: &Url) -> Option<Url> {
+    let segments: Vec<&str> = location.path_segments()?.collect();
+    let pos = segments.iter().position(|s| *s == "repositories")?;
+    let id = segments.get(pos + 1)?;
+    if id.is_empty() || !id.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let mut url = location.clone();
+    url.set_query(None);
+    url.set_path(&segments[..=pos + 1].join("/"));
+    Some(url)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_test::traced_test;
+    use wiremock::matchers::path;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_build_client_compression() {
+        use flate2::{write::GzEncoder, Compression};
+        use std::io::Write;
+        use wiremock::matchers::header_exists;
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(br#"{"name": "repo"}"#).unwrap();
+        let gzipped = encoder.finish().unwrap();
+
+        let server = MockServer::start().await;
+        Mock::given(path("/repo"))
+            .and(header_exists("accept-encoding"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("Content-Encoding", "gzip")
+                    .set_body_bytes(gzipped),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(path("/repo"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"name": "plain"}"#))
+            .mount(&server)
+            .await;
+        let url = format!("{}/repo", server.uri());
+
+        let options = ClientOptions {
+            compression: true,
+            ..Default::default()
+        };
+        let response = build_client(HeaderMap::new(), &options)
+            .unwrap()
+            .get(&url)
+            .send()
+            .await
+            .unwrap();
+        let body: serde_json::Value = read_json(response).await.unwrap();
+        assert_eq!(body["name"], "repo");
+
+        let requests = server.received_requests().await.unwrap();
+        let accepted = requests[0].headers.get("accept-encoding").unwrap();
+        let accepted = accepted.to_str().unwrap();
+        assert!(accepted.contains("gzip") && accepted.contains("br"));
+
+        // Without compression, e.g. for asset downloads or --no-compression
+        let response = build_client(HeaderMap::new(), &ClientOptions::default())
+            .unwrap()
+            .get(&url)
+            .send()
+            .await
+            .unwrap();
+        let body: serde_json::Value = read_json(response).await.unwrap();
+        assert_eq!(body["name"], "plain");
+    }
+
+    #[test]
+    fn test_is_api_host() {
+        let api = "https://api.github.com";
+        assert!(is_api_host("https://api.github.com/repos/o/r", api));
+        assert!(is_api_host(
+            "https://github.com/o/r/releases/download/v1/SHA256SUMS",
+            api
+        ));
+        assert!(!is_api_host("https://example.com/SHA256SUMS", api));
+        assert!(!is_api_host("http://github.com:8080/SHA256SUMS", api));
+        assert!(!is_api_host("not a url", api));
+
+        let ghe = "https://ghe.example.com/api/v3";
+        assert!(is_api_host("https://GHE.example.com/o/r/SHA256SUMS", ghe));
+        assert!(!is_api_host("https://example.com/SHA256SUMS", ghe));
+    }
+
+    #[test]
+    fn test_build_client_ipv4_only() {
+        let options = ClientOptions {
+            ipv4_only: true,
+            ..Default::default()
+        };
+        assert!(build_client(HeaderMap::new(), &options).is_ok());
+        assert!(build_client(HeaderMap::new(), &ClientOptions::default()).is_ok());
+    }
+
+    #[test]
+    fn test_parse_json_truncated_vs_invalid() {
+        let url = "https://api.github.com/repos/owner/repo/releases";
+        let truncated = parse_json::<Vec<RepositoryInfo>>(br#"[{"name": "re"#, url);
+        assert!(matches!(truncated, Err(GhrError::TruncatedResponse { .. })));
+
+        let invalid = parse_json::<Vec<RepositoryInfo>>(br#"{"message": "oops"}"#, url);
+        assert!(matches!(invalid, Err(GhrError::JsonError(_))));
+    }
+
+    #[test]
+    fn test_build_client_min_tls() {
+        for min_tls in [TlsVersion::V1_2, TlsVersion::V1_3] {
+            let options = ClientOptions {
+                min_tls: Some(min_tls),
+                ..Default::default()
+            };
+            assert!(build_client(HeaderMap::new(), &options).is_ok());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_requests_rotate_tokens() {
+        use wiremock::matchers::header;
+
+        let server = MockServer::start().await;
+        for token in ["ghp_one", "ghp_two", "ghp_three"] {
+            Mock::given(path("/repos/owner/repo"))
+                .and(header(
+                    "Authorization",
+                    format!("Bearer {}", token).as_str(),
+                ))
+                .respond_with(ResponseTemplate::new(200))
+                .expect(2)
+                .mount(&server)
+                .await;
+        }
+
+        let client = build_client(HeaderMap::new(), &ClientOptions::default()).unwrap();
+        let pool = TokenPool::new(vec!["ghp_one".into(), "ghp_two".into(), "ghp_three".into()]);
+        let api = ApiClient::new(
+            client,
+            ApiOptions {
+                tokens: Some(Arc::new(pool)),
+                ..Default::default()
+            },
+        );
+        let url = format!("{}/repos/owner/repo", server.uri());
+        for _ in 0..6 {
+            assert!(api.fetch(&url).await.unwrap().status().is_success());
+        }
+        server.verify().await;
+    }
+
+    #[tokio::test]
+    #[traced_test]
+    async fn test_fetch_logs_timing() {
+        let server = MockServer::start().await;
+        Mock::given(path("/repos/owner/repo/releases"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(Vec::<u8>::new()))
+            .mount(&server)
+            .await;
+
+        let client = build_client(HeaderMap::new(), &ClientOptions::default()).unwrap();
+        let api = ApiClient::new(client, ApiOptions::default());
+        let url = format!("{}/repos/owner/repo/releases", server.uri());
+        api.fetch(&url).await.unwrap();
+
+        assert!(logs_contain(&format!("GET {} -> 200 OK in", url)));
+    }
+
+    #[tokio::test]
+    async fn test_api_timeout_per_request() {
+        let server = MockServer::start().await;
+        Mock::given(path("/fast"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+        Mock::given(path("/slow"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(1)))
+            .mount(&server)
+            .await;
+
+        let client = build_client(HeaderMap::new(), &ClientOptions::default()).unwrap();
+        let api = ApiClient::new(
+            client.clone(),
+            ApiOptions {
+                timeout: Some(Duration::from_millis(200)),
+                ..Default::default()
+            },
+        );
+
+        let fast = api.get(format!("{}/fast", server.uri())).send().await;
+        assert!(fast.unwrap().status().is_success());
+
+        let slow = api.get(format!("{}/slow", server.uri())).send().await;
+        assert!(slow.unwrap_err().is_timeout());
+
+        // The underlying client has no timeout of its own
+        let direct = client
+            .get(format!("{}/slow", server.uri()))
+            .timeout(Duration::from_secs(10))
+            .send()
+            .await;
+        assert!(direct.unwrap().status().is_success());
+    }
+
+    fn retry_policy(max_retries: u32, budget: Option<u64>) -> RetryPolicy {
+        RetryPolicy {
+            max_retries,
+            base_delay: Duration::from_secs(2),
+            max_delay: Duration::from_secs(10),
+            budget: budget.map(Duration::from_secs),
+        }
+    }
+
+    #[test]
+    fn test_retry_delay_capped() {
+        let policy = retry_policy(6, None);
+        let delays: Vec<Option<u64>> = (0..7)
+            .map(|attempt| {
+                policy
+                    .delay_for(attempt, Duration::ZERO)
+                    .map(|d| d.as_secs())
+            })
+            .collect();
+        assert_eq!(
+            delays,
+            vec![
+                Some(2),
+                Some(4),
+                Some(8),
+                Some(10),
+                Some(10),
+                Some(10),
+                None
+            ]
+        );
+
+        // Large attempt numbers don't overflow
+        let policy = retry_policy(u32::MAX, None);
+        assert_eq!(
+            policy.delay_for(100, Duration::ZERO),
+            Some(Duration::from_secs(10))
+        );
+    }
+
+    #[test]
+    fn test_retry_budget() {
+        let policy = retry_policy(10, Some(20));
+
+        // Simulated clock: elapsed time advances by each delay taken
+        let mut elapsed = Duration::ZERO;
+        let mut attempt = 0;
+        while let Some(delay) = policy.delay_for(attempt, elapsed) {
+            elapsed += delay;
+            attempt += 1;
+        }
+
+        // 2 + 4 + 8 = 14s; the next 10s delay would exceed the 20s budget
+        assert_eq!(attempt, 3);
+        assert_eq!(elapsed, Duration::from_secs(14));
+
+        // Time spent on the requests themselves also counts
+        assert_eq!(policy.delay_for(0, Duration::from_secs(19)), None);
+    }
+
+    #[test]
+    fn test_repo_from_api_url() {
+        assert_eq!(
+            repo_from_api_url("https://api.github.com/repos/owner/repo/releases"),
+            Some("owner/repo".to_string())
+        );
+        assert_eq!(
+            repo_from_api_url("https://ghe.example.com/api/v3/repos/owner/repo"),
+            Some("owner/repo".to_string())
+        );
+        assert_eq!(
+            repo_from_api_url("https://api.github.com/search/repositories?q=x"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_moved_repository_url() {
+        let location =
+            Url::parse("https://api.github.com/repositories/42/releases?page=2").unwrap();
+        assert_eq!(
+            moved_repository_url(&location).unwrap().as_str(),
+            "https://api.github.com/repositories/42"
+        );
+
+        let other = Url::parse("https://objects.githubusercontent.com/asset/42").unwrap();
+        assert!(moved_repository_url(&other).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_fetch_rejects_cross_host_redirect() {
+        use wiremock::matchers::header;
+
+        let api = MockServer::start().await;
+        let other = MockServer::start().await;
+        Mock::given(path("/repos/owner/repo/releases"))
+            .respond_with(
+                ResponseTemplate::new(302)
+                    .insert_header("Location", format!("{}/releases", other.uri()).as_str()),
+            )
+            .mount(&api)
+            .await;
+        Mock::given(path("/repos/owner/repo/tags"))
+            .respond_with(
+                ResponseTemplate::new(301).insert_header("Location", "/repos/owner/repo/tags2"),
+            )
+            .mount(&api)
+            .await;
+        Mock::given(path("/repos/owner/repo/tags2"))
+            .and(header("Authorization", "Bearer ghp_secret"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&api)
+            .await;
+        Mock::given(path("/releases"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&other)
+            .await;
+
+        let mut headers = HeaderMap::new();
+        headers.insert(AUTHORIZATION, auth::bearer("ghp_secret").unwrap());
+        let client = build_client(headers, &ClientOptions::default()).unwrap();
+        let api_client = ApiClient::new(client, ApiOptions::default());
+
+        let err = api_client
+            .fetch(&format!("{}/repos/owner/repo/releases", api.uri()))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("another host"), "{}", err);
+        assert!(other.received_requests().await.unwrap().is_empty());
+
+        // A redirect on the same host is still followed with the token
+        let response = api_client
+            .fetch(&format!("{}/repos/owner/repo/tags", api.uri()))
+            .await
+            .unwrap();
+        assert!(response.status().is_success());
+    }
+
+    #[tokio::test]
+    async fn test_max_redirects() {
+        let server = MockServer::start().await;
+        for (from, to) in [("/r1", "/r2"), ("/r2", "/r3"), ("/r3", "/final")] {
+            Mock::given(path(from))
+                .respond_with(
+                    ResponseTemplate::new(302)
+                        .insert_header("Location", format!("{}{}", server.uri(), to).as_str()),
+                )
+                .mount(&server)
+                .await;
+        }
+        Mock::given(path("/final"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&server)
+            .await;
+
+        let client_with_limit = |max| {
+            build_client(
+                HeaderMap::new(),
+                &ClientOptions {
+                    max_redirects: Some(max),
+                    ..Default::default()
+                },
+            )
+            .unwrap()
+        };
+        let url = format!("{}/r1", server.uri());
+
+        let within = client_with_limit(3).get(&url).send().await.unwrap();
+        assert!(within.status().is_success());
+
+        let exceeded = client_with_limit(2).get(&url).send().await;
+        assert!(exceeded.unwrap_err().is_redirect());
+
+        // Without a limit the redirect is returned as is
+        let manual = build_client(HeaderMap::new(), &ClientOptions::default())
+            .unwrap()
+            .get(&url)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(manual.status(), reqwest::StatusCode::FOUND);
+    }
+}
@@ -0,0 +1,186 @@
+//! Library behind the `ghr` command line tool
+//!
+//! [`download_release`] downloads the assets of a release, described by
+//! [`models::Release`], with a progress display of your own.
+
+pub mod errors;
+pub mod models;
+
+// Internals of the `ghr` binary, not part of the library API
+#[doc(hidden)]
+pub mod auth;
+#[doc(hidden)]
+pub mod batch;
+#[doc(hidden)]
+pub mod cache;
+#[doc(hidden)]
+pub mod checksum;
+#[doc(hidden)]
+pub mod cli;
+#[doc(hidden)]
+pub mod config;
+#[doc(hidden)]
+pub mod constants;
+#[doc(hidden)]
+pub mod download;
+#[doc(hidden)]
+pub mod extract;
+#[doc(hidden)]
+pub mod filters;
+#[doc(hidden)]
+pub mod git;
+#[doc(hidden)]
+pub mod github;
+#[doc(hidden)]
+pub mod gitlab;
+#[doc(hidden)]
+pub mod http;
+#[doc(hidden)]
+pub mod listing;
+#[doc(hidden)]
+pub mod output;
+#[doc(hidden)]
+pub mod picker;
+#[doc(hidden)]
+pub mod progress;
+#[doc(hidden)]
+pub mod provider;
+#[doc(hidden)]
+pub mod sign;
+#[doc(hidden)]
+pub mod util;
+
+pub use download::{ProgressCallback, ProgressEvent};
+pub use errors::{GhrError, Result};
+
+use models::Release;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Download all assets of `release` into `dir`, returning the paths of the files
+///
+/// Assets are fetched from their browser download URLs, a few at a time with
+/// retries of transient server errors. `progress` is called as each asset
+/// downloads, and once more when it has finished or failed. Fails with the
+/// first failed download once all downloads have ended.
+pub async fn download_release(
+    client: &reqwest::Client,
+    release: &Release,
+    dir: &Path,
+    progress: Option<ProgressCallback>,
+) -> Result<Vec<PathBuf>> {
+    let tasks = release
+        .assets
+        .iter()
+        .map(|asset| {
+            Ok(download::DownloadTask {
+                name: asset.name.clone(),
+                url: asset.browser_download_url.clone(),
+                output_path: dir.join(download::sanitize_component(&asset.name)?),
+                size: asset.size,
+                digest: asset.digest.clone(),
+                modified: None,
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    tokio::fs::create_dir_all(dir).await?;
+
+    let options = download::DownloadOptions {
+        concurrency: constants::DEFAULT_CONCURRENCY,
+        timeout: None,
+        retry: http::RetryPolicy::default(),
+        checksum_retries: 0,
+        retry_budget: None,
+        min_rate: None,
+        checksums: None,
+        progress,
+        host_token: None,
+    };
+    download::download_assets(Arc::new(client.clone()), tasks, &options)
+        .await
+        .into_iter()
+        .map(|result| result.map(|task| task.output_path))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use models::Asset;
+    use std::sync::Mutex;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[tokio::test]
+    async fn test_download_release_progress_callback() {
+        // Serve the body in several chunks so that progress is reported in steps
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let mut request = [0; 1024];
+                    let _ = socket.read(&mut request).await;
+                    let _ = socket
+                        .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 4096\r\n\r\n")
+                        .await;
+                    for _ in 0..4 {
+                        let _ = socket.write_all(&[b'x'; 1024]).await;
+                        let _ = socket.flush().await;
+                        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+                    }
+                });
+            }
+        });
+
+        let release = Release {
+            tag_name: "v1.0.0".to_string(),
+            assets: vec![Asset {
+                name: "app.tar.gz".to_string(),
+                browser_download_url: format!("http://{}/app.tar.gz", addr),
+                size: 4096,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&reports);
+        let finished = Arc::new(Mutex::new(Vec::new()));
+        let recorded_finished = Arc::clone(&finished);
+        let callback: ProgressCallback = Arc::new(move |name, event| match event {
+            ProgressEvent::Bytes { downloaded, size } => {
+                recorded
+                    .lock()
+                    .unwrap()
+                    .push((name.to_string(), downloaded, size));
+            }
+            ProgressEvent::Finished => recorded_finished.lock().unwrap().push(name.to_string()),
+            ProgressEvent::Failed(e) => panic!("{}: {}", name, e),
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let paths = download_release(
+            &reqwest::Client::new(),
+            &release,
+            dir.path(),
+            Some(callback),
+        )
+        .await
+        .unwrap();
+        assert_eq!(paths, vec![dir.path().join("app.tar.gz")]);
+        assert_eq!(std::fs::read(&paths[0]).unwrap().len(), 4096);
+
+        let reports = reports.lock().unwrap();
+        assert!(reports.len() > 2, "{:?}", reports);
+        assert!(reports
+            .iter()
+            .all(|(name, _, total)| name == "app.tar.gz" && *total == 4096));
+        let counts: Vec<u64> = reports
+            .iter()
+            .map(|(_, downloaded, _)| *downloaded)
+            .collect();
+        assert!(counts.windows(2).all(|w| w[0] <= w[1]), "{:?}", counts);
+        assert_eq!(counts.first(), Some(&0));
+        assert_eq!(counts.last(), Some(&4096));
+        assert_eq!(*finished.lock().unwrap(), vec!["app.tar.gz".to_string()]);
+    }
+}
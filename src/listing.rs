@@ -0,0 +1,904 @@
+use crate::cli::{Cli, SortKey};
+use crate::errors::{GhrError, Result};
+use crate::models::{Asset, Release};
+use chrono::{DateTime, FixedOffset};
+use globset::Glob;
+use jlogger_tracing::jwarn;
+use regex::{Regex, RegexBuilder};
+use std::cmp::{Ordering, Reverse};
+use std::collections::HashSet;
+
+/// Keep only releases whose total download count reaches `min_downloads`
+pub fn filter_min_downloads(releases: Vec<&Release>, min_downloads: u64) -> Vec<&Release> {
+    releases
+        .into_iter()
+        .filter(|r| r.total_downloads() >= min_downloads)
+        .collect()
+}
+
+/// Releases shown by `--list`: filtered, sorted and limited to `--num` (0 for all)
+pub fn listed_releases<'a>(releases: &'a [Release], cli: &Cli) -> Result<Vec<&'a Release>> {
+    let mut candidates: Vec<&Release> = releases.iter().collect();
+    if cli.dedupe_by_commit {
+        candidates = dedupe_by_commit(candidates);
+    }
+    if let Some(min_downloads) = cli.min_downloads {
+        candidates = filter_min_downloads(candidates, min_downloads);
+    }
+    if let Some(pattern) = cli.release_grep.as_deref() {
+        let pattern = release_grep(pattern)?;
+        candidates = filter_grep(candidates, &pattern);
+    }
+    if let Some(login) = cli.author.as_deref() {
+        candidates = filter_author(candidates, login);
+    }
+    sort_releases(&mut candidates, cli.sort_key());
+    if cli.reverse {
+        candidates.reverse();
+    }
+    if cli.num > 0 {
+        candidates.truncate(cli.num);
+    }
+    Ok(candidates)
+}
+
+/// Build the case-insensitive regex used by `--release-grep`
+pub fn release_grep(pattern: &str) -> Result<Regex> {
+    Ok(RegexBuilder::new(pattern).case_insensitive(true).build()?)
+}
+
+/// Keep only releases whose tag, name or release notes match `pattern`
+pub fn filter_grep<'a>(releases: Vec<&'a Release>, pattern: &Regex) -> Vec<&'a Release> {
+    releases
+        .into_iter()
+        .filter(|r| {
+            pattern.is_match(&r.tag_name)
+                || r.name.as_deref().is_some_and(|name| pattern.is_match(name))
+                || r.body.as_deref().is_some_and(|body| pattern.is_match(body))
+        })
+        .collect()
+}
+
+/// Keep only releases created by `login`, ignoring case like GitHub does
+pub fn filter_author<'a>(releases: Vec<&'a Release>, login: &str) -> Vec<&'a Release> {
+    releases
+        .into_iter()
+        .filter(|r| {
+            r.author
+                .as_ref()
+                .is_some_and(|author| author.login.eq_ignore_ascii_case(login))
+        })
+        .collect()
+}
+
+/// Collapse releases created from the same commit, keeping the newest of each
+///
+/// Only releases whose `target_commitish` is a commit SHA are compared; one
+/// naming a branch says nothing about the commit that was tagged.
+pub fn dedupe_by_commit(releases: Vec<&Release>) -> Vec<&Release> {
+    let mut seen = HashSet::new();
+    // The API returns releases newest first
+    releases
+        .into_iter()
+        .filter(|r| match r.target_commitish.as_deref() {
+            Some(target) if is_commit_sha(target) => seen.insert(target.to_ascii_lowercase()),
+            _ => true,
+        })
+        .collect()
+}
+
+/// Whether `target` is a full commit SHA rather than a branch name
+fn is_commit_sha(target: &str) -> bool {
+    target.len() == 40 && target.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Select the release to download for a `--download` value
+///
+/// Besides a tag name, accepts the keywords:
+/// - "latest": the most recent release
+/// - "latest-stable": the most recent release that is neither a prerelease nor a draft
+/// - "pre-latest": the most recent prerelease that is not a draft
+pub fn select_release<'a>(releases: &'a [Release], spec: &str) -> Result<&'a Release> {
+    if releases.is_empty() {
+        return Err(GhrError::NoReleases);
+    }
+
+    // The API returns releases newest first
+    match spec {
+        "latest" => releases.first().ok_or(GhrError::NoReleases),
+        "latest-stable" => releases
+            .iter()
+            .find(|r| !r.prerelease && !r.draft)
+            .ok_or_else(|| GhrError::Generic("No stable release found".to_string())),
+        "pre-latest" => releases
+            .iter()
+            .find(|r| r.prerelease && !r.draft)
+            .ok_or_else(|| GhrError::Generic("No prerelease found".to_string())),
+        tag => {
+            releases
+                .iter()
+                .find(|r| r.tag_name == tag)
+                .ok_or_else(|| GhrError::ReleaseNotFound {
+                    tag: tag.to_string(),
+                })
+        }
+    }
+}
+
+/// Select the release to download like `select_release`, with `--prerelease-fallback`
+///
+/// When "latest-stable" finds no stable release, the latest prerelease is
+/// used instead, with a warning.
+pub fn select_release_with_fallback<'a>(
+    releases: &'a [Release],
+    spec: &str,
+    prerelease_fallback: bool,
+) -> Result<&'a Release> {
+    let result = select_release(releases, spec);
+    if result.is_err() && prerelease_fallback && spec == "latest-stable" {
+        if let Ok(release) = select_release(releases, "pre-latest") {
+            jwarn!(
+                "No stable release found, falling back to prerelease {}",
+                release.tag_name
+            );
+            return Ok(release);
+        }
+    }
+    result
+}
+
+/// Whether a `--download` value is a glob over tags rather than a single tag
+pub fn is_tag_glob(spec: &str) -> bool {
+    spec.contains(['*', '?', '['])
+}
+
+/// Select the releases whose tag matches a glob such as "v1.*"
+///
+/// Returns the matching releases in their original order. Errors if the glob
+/// is invalid or matches no release.
+pub fn releases_matching<'a>(releases: &'a [Release], pattern: &str) -> Result<Vec<&'a Release>> {
+    let matcher = Glob::new(pattern)?.compile_matcher();
+    let matching: Vec<&Release> = releases
+        .iter()
+        .filter(|r| matcher.is_match(&r.tag_name))
+        .collect();
+    if matching.is_empty() {
+        return Err(GhrError::Generic(format!(
+            "No release tag matches '{}'",
+            pattern
+        )));
+    }
+    Ok(matching)
+}
+
+/// Fall back to an older release when `selected` has no matching asset
+///
+/// Scans up to `scan` releases published before `selected` and returns the
+/// first one for which `has_match` holds, or `selected` itself if none does.
+pub fn fallback_release<'a>(
+    releases: &'a [Release],
+    selected: &'a Release,
+    scan: usize,
+    has_match: impl Fn(&Release) -> bool,
+) -> &'a Release {
+    if has_match(selected) {
+        return selected;
+    }
+    // The API returns releases newest first, so older ones follow `selected`
+    releases
+        .iter()
+        .skip_while(|r| !std::ptr::eq(*r, selected))
+        .skip(1)
+        .take(scan)
+        .find(|r| has_match(r))
+        .unwrap_or(selected)
+}
+
+/// Select the releases published after the release tagged `tag`
+///
+/// Returns the newer releases in their original order. Errors if no release
+/// has the given tag.
+pub fn releases_since<'a>(releases: &'a [Release], tag: &str) -> Result<Vec<&'a Release>> {
+    let since =
+        releases
+            .iter()
+            .find(|r| r.tag_name == tag)
+            .ok_or_else(|| GhrError::ReleaseNotFound {
+                tag: tag.to_string(),
+            })?;
+    let since_date = parse_date(&since.published_at);
+
+    Ok(releases
+        .iter()
+        .filter(|r| match (parse_date(&r.published_at), since_date) {
+            (Some(date), Some(since_date)) => date > since_date,
+            // Fall back to comparing the raw timestamps
+            _ => r.published_at > since.published_at,
+        })
+        .collect())
+}
+
+/// Parse an RFC 3339 timestamp as returned by the GitHub API
+fn parse_date(date: &str) -> Option<DateTime<FixedOffset>> {
+    DateTime::parse_from_rfc3339(date).ok()
+}
+
+/// Sort releases for display according to the requested key
+///
+/// The sort is stable: releases with equal keys keep the order returned by
+/// the API, so the same input always produces the same listing.
+pub fn sort_releases(releases: &mut [&Release], key: SortKey) {
+    match key {
+        // The API already returns releases newest first
+        SortKey::Date => {}
+        // `sort_by_key` is stable, unlike `sort_unstable_by_key`
+        SortKey::Downloads => releases.sort_by_key(|r| Reverse(r.total_downloads())),
+        SortKey::Natural => releases.sort_by(|a, b| natural_cmp(&b.tag_name, &a.tag_name)),
+        SortKey::Size => releases.sort_by_key(|r| Reverse(r.total_size())),
+        SortKey::Name => releases.sort_by(|a, b| a.tag_name.cmp(&b.tag_name)),
+    }
+}
+
+/// Sort numbered assets for `--list-assets`; the numbers stay attached to their asset
+pub fn sort_assets(assets: &mut [(usize, &Asset)], key: SortKey) {
+    match key {
+        // Keep the order of the release
+        SortKey::Date => {}
+        SortKey::Downloads => assets.sort_by_key(|(_, a)| Reverse(a.download_count)),
+        SortKey::Natural => assets.sort_by(|(_, a), (_, b)| natural_cmp(&a.name, &b.name)),
+        SortKey::Size => assets.sort_by_key(|(_, a)| Reverse(a.size)),
+        SortKey::Name => assets.sort_by(|(_, a), (_, b)| a.name.cmp(&b.name)),
+    }
+}
+
+/// Compare two tags in natural ("version sort") order
+///
+/// Tags are split into runs of digits and non-digits; digit runs compare
+/// numerically and everything else compares as text, so `build-2` sorts
+/// before `build-10`.
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a_runs = runs(a);
+    let mut b_runs = runs(b);
+
+    loop {
+        let ordering = match (a_runs.next(), b_runs.next()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if is_digits(x) && is_digits(y) => {
+                let x_num = x.trim_start_matches('0');
+                let y_num = y.trim_start_matches('0');
+                // Compare by length first so arbitrarily long numbers never overflow
+                x_num.len().cmp(&y_num.len()).then_with(|| x_num.cmp(y_num))
+            }
+            (Some(x), Some(y)) => x.cmp(y),
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+}
+
+/// Sort releases newest version first, for tags that carry no publication date
+///
+/// Tags that parse as a version ("v1.10.0", "2.0", "1.0.0-rc.1") come first,
+/// highest first, with a prerelease below its release. The others keep their
+/// order after them.
+pub fn sort_by_version(releases: &mut [Release]) {
+    releases.sort_by(
+        |a, b| match (parse_version(&a.tag_name), parse_version(&b.tag_name)) {
+            (Some(x), Some(y)) => version_cmp(&y, &x),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        },
+    );
+}
+
+/// Version of a tag: up to three numeric components and a prerelease, if any
+type Version<'a> = ([u64; 3], Option<&'a str>);
+
+/// Parse a tag such as "v1.2.3-rc.1+build" as a version; build metadata is ignored
+fn parse_version(tag: &str) -> Option<Version<'_>> {
+    let tag = tag.strip_prefix(['v', 'V']).unwrap_or(tag);
+    let tag = tag.split_once('+').map_or(tag, |(version, _)| version);
+    let (core, pre) = match tag.split_once('-') {
+        Some((core, pre)) => (core, Some(pre)),
+        None => (tag, None),
+    };
+
+    let mut numbers = [0; 3];
+    let mut parts = core.split('.');
+    for (i, part) in parts.by_ref().take(3).enumerate() {
+        if part.is_empty() || !part.bytes().all(|b| b.is_ascii_digit()) {
+            return None;
+        }
+        numbers[i] = part.parse().ok()?;
+    }
+    if parts.next().is_some() {
+        return None;
+    }
+    Some((numbers, pre))
+}
+
+fn version_cmp(a: &Version<'_>, b: &Version<'_>) -> Ordering {
+    a.0.cmp(&b.0).then_with(|| match (a.1, b.1) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (Some(x), Some(y)) => natural_cmp(x, y),
+    })
+}
+
+/// Split a string into maximal runs of ASCII digits and non-digits
+fn runs(s: &str) -> impl Iterator<Item = &str> {
+    let mut rest = s;
+    std::iter::from_fn(move || {
+        let first = rest.chars().next()?;
+        let digit = first.is_ascii_digit();
+        let end = rest
+            .find(|c: char| c.is_ascii_digit() != digit)
+            .unwrap_or(rest.len());
+        let (run, tail) = rest.split_at(end);
+        rest = tail;
+        Some(run)
+    })
+}
+
+fn is_digits(run: &str) -> bool {
+    run.starts_with(|c: char| c.is_ascii_digit())
+}
+
+/// Parse a `--download-index` spec of the form `<tag>:<n>[,<n>...]`
+///
+/// Returns the tag and the 1-based asset indices in the given order.
+pub fn parse_download_index(spec: &str) -> Result<(String, Vec<usize>)> {
+    let invalid = || {
+        GhrError::Generic(format!(
+            "Invalid download index '{}'. Expected '<tag>:<n>[,<n>...]'",
+            spec
+        ))
+    };
+
+    let (tag, indices) = spec.rsplit_once(':').ok_or_else(invalid)?;
+    let tag = tag.trim();
+    if tag.is_empty() {
+        return Err(invalid());
+    }
+
+    let indices = indices
+        .split(',')
+        .map(|n| n.trim().parse::<usize>().map_err(|_| invalid()))
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok((tag.to_string(), indices))
+}
+
+/// Select assets by the 1-based index shown by `--list-assets`
+///
+/// Indices refer to the asset order of the release as returned by the API,
+/// which is also the order `--list-assets` prints them in.
+pub fn assets_by_index<'a>(release: &'a Release, indices: &[usize]) -> Result<Vec<&'a Asset>> {
+    indices
+        .iter()
+        .map(|&n| {
+            n.checked_sub(1)
+                .and_then(|i| release.assets.get(i))
+                .ok_or_else(|| {
+                    GhrError::Generic(format!(
+                        "Asset index {} out of range: release '{}' has {} asset(s)",
+                        n,
+                        release.tag_name,
+                        release.assets.len()
+                    ))
+                })
+        })
+        .collect()
+}
+
+/// URLs printed by `--print-urls`: the release page, then each asset's download URL
+pub fn release_urls<'a>(release: &'a Release, assets: &[&'a Asset]) -> Vec<&'a str> {
+    std::iter::once(release.html_url.as_str())
+        .chain(assets.iter().map(|a| a.browser_download_url.as_str()))
+        .filter(|url| !url.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    fn release(tag: &str, downloads: &[u32]) -> Release {
+        Release {
+            tag_name: tag.to_string(),
+            assets: downloads
+                .iter()
+                .map(|&download_count| Asset {
+                    download_count,
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_filter_min_downloads() {
+        let releases = [
+            release("v3", &[5, 5]),
+            release("v2", &[100, 20]),
+            release("v1", &[]),
+            release("v0", &[40, 60]),
+        ];
+
+        let filtered = filter_min_downloads(releases.iter().collect(), 100);
+        let tags: Vec<&str> = filtered.iter().map(|r| r.tag_name.as_str()).collect();
+        assert_eq!(tags, vec!["v2", "v0"]);
+    }
+
+    #[test]
+    fn test_filter_author() {
+        let by = |tag: &str, login: Option<&str>| Release {
+            tag_name: tag.to_string(),
+            author: login.map(|login| crate::models::Owner {
+                login: login.to_string(),
+            }),
+            ..Default::default()
+        };
+        let releases = [
+            by("v4", Some("alice")),
+            by("v3", Some("release-bot")),
+            by("v2", Some("Alice")),
+            by("v1", None),
+        ];
+
+        let filtered = filter_author(releases.iter().collect(), "ALICE");
+        let tags: Vec<&str> = filtered.iter().map(|r| r.tag_name.as_str()).collect();
+        assert_eq!(tags, vec!["v4", "v2"]);
+        assert!(filter_author(releases.iter().collect(), "bob").is_empty());
+    }
+
+    #[test]
+    fn test_listed_releases_count() {
+        // More releases than the default --num, half of them popular
+        let releases: Vec<Release> = (0..40)
+            .map(|i| release(&format!("v{}", i), &[if i % 2 == 0 { 100 } else { 1 }]))
+            .collect();
+        let cli = |args: &[&str]| {
+            Cli::try_parse_from(
+                ["ghr", "-r", "owner/repo", "--list", "--count-only"]
+                    .iter()
+                    .chain(args),
+            )
+            .unwrap()
+        };
+
+        let count = |args: &[&str]| listed_releases(&releases, &cli(args)).unwrap().len();
+        assert_eq!(count(&["-n", "0"]), 40);
+        assert_eq!(count(&["-n", "0", "--min-downloads", "100"]), 20);
+        assert_eq!(count(&["-n", "5", "--min-downloads", "100"]), 5);
+        assert_eq!(count(&["-n", "0", "--release-grep", "^v1"]), 11);
+
+        let listed =
+            listed_releases(&releases, &cli(&["-n", "0", "--min-downloads", "100"])).unwrap();
+        assert!(listed.iter().all(|r| r.total_downloads() >= 100));
+    }
+
+    fn dated(tag: &str, published_at: &str) -> Release {
+        Release {
+            tag_name: tag.to_string(),
+            published_at: published_at.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_releases_since() {
+        let releases = [
+            dated("v1.3.0", "2024-04-01T00:00:00Z"),
+            dated("v1.2.1", "2024-03-15T00:00:00Z"),
+            dated("v1.2.0", "2024-03-01T00:00:00Z"),
+            dated("v1.1.0", "2024-02-01T00:00:00Z"),
+        ];
+
+        let newer = releases_since(&releases, "v1.2.0").unwrap();
+        let tags: Vec<&str> = newer.iter().map(|r| r.tag_name.as_str()).collect();
+        assert_eq!(tags, vec!["v1.3.0", "v1.2.1"]);
+
+        assert!(releases_since(&releases, "v1.3.0").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_releases_since_unknown_tag() {
+        let releases = [dated("v1.0.0", "2024-01-01T00:00:00Z")];
+        assert!(matches!(
+            releases_since(&releases, "v0.9.0"),
+            Err(GhrError::ReleaseNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_sort_releases_by_downloads() {
+        let releases = [
+            release("v3", &[5]),
+            release("v2", &[100, 20]),
+            release("v1", &[50]),
+        ];
+
+        let mut sorted: Vec<&Release> = releases.iter().collect();
+        sort_releases(&mut sorted, SortKey::Downloads);
+        let tags: Vec<&str> = sorted.iter().map(|r| r.tag_name.as_str()).collect();
+        assert_eq!(tags, vec!["v2", "v1", "v3"]);
+    }
+
+    #[test]
+    fn test_natural_cmp() {
+        assert_eq!(natural_cmp("build-2", "build-10"), Ordering::Less);
+        assert_eq!(natural_cmp("r42", "r9"), Ordering::Greater);
+        assert_eq!(natural_cmp("2024.10", "2024.9"), Ordering::Greater);
+        assert_eq!(natural_cmp("v1.2", "v1.2.1"), Ordering::Less);
+        assert_eq!(natural_cmp("build-007", "build-7"), Ordering::Less);
+        assert_eq!(natural_cmp("abc", "abc"), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_sort_by_version() {
+        let mut releases: Vec<Release> = [
+            "v1.9.0",
+            "nightly",
+            "v1.10.0",
+            "v1.10.0-rc.2",
+            "v1.10.0-rc.10",
+            "2.0",
+            "latest-build",
+            "v1.2.3.4",
+        ]
+        .into_iter()
+        .map(|tag| Release::from_tag(tag.to_string()))
+        .collect();
+
+        sort_by_version(&mut releases);
+        let tags: Vec<&str> = releases.iter().map(|r| r.tag_name.as_str()).collect();
+        assert_eq!(
+            tags,
+            vec![
+                "2.0",
+                "v1.10.0",
+                "v1.10.0-rc.10",
+                "v1.10.0-rc.2",
+                "v1.9.0",
+                "nightly",
+                "latest-build",
+                "v1.2.3.4"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sort_releases_natural() {
+        let releases = [
+            release("build-2", &[]),
+            release("build-117", &[]),
+            release("build-10", &[]),
+        ];
+
+        let mut sorted: Vec<&Release> = releases.iter().collect();
+        sort_releases(&mut sorted, SortKey::Natural);
+        let tags: Vec<&str> = sorted.iter().map(|r| r.tag_name.as_str()).collect();
+        assert_eq!(tags, vec!["build-117", "build-10", "build-2"]);
+    }
+
+    #[test]
+    fn test_parse_download_index() {
+        let (tag, indices) = parse_download_index("v1.2.3:2,4").unwrap();
+        assert_eq!(tag, "v1.2.3");
+        assert_eq!(indices, vec![2, 4]);
+
+        // Only the last colon separates the indices
+        let (tag, indices) = parse_download_index("release:2024:1").unwrap();
+        assert_eq!(tag, "release:2024");
+        assert_eq!(indices, vec![1]);
+
+        assert!(parse_download_index("v1.2.3").is_err());
+        assert!(parse_download_index(":1").is_err());
+        assert!(parse_download_index("v1.2.3:two").is_err());
+    }
+
+    #[test]
+    fn test_assets_by_index() {
+        let names = [
+            "app-linux.tar.gz",
+            "app-macos.zip",
+            "app-windows.zip",
+            "SHA256SUMS",
+        ];
+        let release = Release {
+            tag_name: "v1.0.0".to_string(),
+            assets: names
+                .iter()
+                .map(|name| Asset {
+                    name: name.to_string(),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        };
+
+        let picked = assets_by_index(&release, &[2, 4]).unwrap();
+        let picked: Vec<&str> = picked.iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(picked, vec!["app-macos.zip", "SHA256SUMS"]);
+
+        assert!(assets_by_index(&release, &[0]).is_err());
+        assert!(assets_by_index(&release, &[5]).is_err());
+    }
+
+    #[test]
+    fn test_sort_releases_ties_keep_api_order() {
+        let releases = [
+            release("v5", &[10]),
+            release("v4", &[30]),
+            release("v3", &[10]),
+            release("v2", &[30]),
+            release("v1", &[10]),
+        ];
+
+        let mut sorted: Vec<&Release> = releases.iter().collect();
+        sort_releases(&mut sorted, SortKey::Downloads);
+        let tags: Vec<&str> = sorted.iter().map(|r| r.tag_name.as_str()).collect();
+        assert_eq!(tags, vec!["v4", "v2", "v5", "v3", "v1"]);
+    }
+
+    #[test]
+    fn test_release_urls() {
+        let release = Release {
+            tag_name: "v1.0.0".to_string(),
+            html_url: "https://github.com/owner/repo/releases/tag/v1.0.0".to_string(),
+            assets: ["app-linux.tar.gz", "app-macos.zip"]
+                .iter()
+                .map(|name| Asset {
+                    name: name.to_string(),
+                    browser_download_url: format!(
+                        "https://github.com/owner/repo/releases/download/v1.0.0/{}",
+                        name
+                    ),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        };
+
+        let linux: Vec<&Asset> = release.assets.iter().take(1).collect();
+        assert_eq!(
+            release_urls(&release, &linux),
+            vec![
+                "https://github.com/owner/repo/releases/tag/v1.0.0",
+                "https://github.com/owner/repo/releases/download/v1.0.0/app-linux.tar.gz",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_filter_grep() {
+        let noted = |tag: &str, name: Option<&str>, body: Option<&str>| Release {
+            tag_name: tag.to_string(),
+            name: name.map(String::from),
+            body: body.map(String::from),
+            ..Default::default()
+        };
+        let releases = [
+            noted("v1.3.0", Some("Spring release"), Some("New features")),
+            noted("v1.2.1", None, Some("Fixes CVE-2024-1234 in the parser")),
+            noted("v1.2.0", Some("Security fixes"), None),
+            noted("cve-tracker", None, None),
+        ];
+
+        let pattern = release_grep("cve-2024-\\d+").unwrap();
+        let matched = filter_grep(releases.iter().collect(), &pattern);
+        let tags: Vec<&str> = matched.iter().map(|r| r.tag_name.as_str()).collect();
+        assert_eq!(tags, vec!["v1.2.1"]);
+
+        // Matches tag and name too, case-insensitively
+        let pattern = release_grep("SECURITY|^CVE").unwrap();
+        let matched = filter_grep(releases.iter().collect(), &pattern);
+        let tags: Vec<&str> = matched.iter().map(|r| r.tag_name.as_str()).collect();
+        assert_eq!(tags, vec!["v1.2.0", "cve-tracker"]);
+
+        assert!(release_grep("(unclosed").is_err());
+    }
+
+    fn flagged(tag: &str, prerelease: bool, draft: bool) -> Release {
+        Release {
+            tag_name: tag.to_string(),
+            prerelease,
+            draft,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_dedupe_by_commit() {
+        let sha = "3f786850e387550fdab836ed7e6dc881de23001b";
+        let targeted = |tag: &str, target: &str| Release {
+            tag_name: tag.to_string(),
+            target_commitish: Some(target.to_string()),
+            ..Default::default()
+        };
+        let releases = [
+            targeted("v1.0.1", sha),
+            targeted("v1.0.0", &sha.to_uppercase()),
+            targeted("v0.9.1", "main"),
+            targeted("v0.9.0", "main"),
+            flagged("v0.1.0", false, false),
+        ];
+
+        let tags: Vec<&str> = dedupe_by_commit(releases.iter().collect())
+            .iter()
+            .map(|r| r.tag_name.as_str())
+            .collect();
+        // Releases from a branch may point at different commits and are kept
+        assert_eq!(tags, vec!["v1.0.1", "v0.9.1", "v0.9.0", "v0.1.0"]);
+    }
+
+    #[test]
+    fn test_select_release_latest_variants() {
+        let releases = [
+            flagged("v2.1.0-rc1", true, true),
+            flagged("v2.0.0-beta", true, false),
+            flagged("v1.9.0-draft", false, true),
+            flagged("v1.8.0", false, false),
+        ];
+
+        let tag = |spec| select_release(&releases, spec).unwrap().tag_name.as_str();
+        assert_eq!(tag("latest"), "v2.1.0-rc1");
+        assert_eq!(tag("latest-stable"), "v1.8.0");
+        assert_eq!(tag("pre-latest"), "v2.0.0-beta");
+        assert_eq!(tag("v1.9.0-draft"), "v1.9.0-draft");
+        assert!(matches!(
+            select_release(&releases, "v0.1.0"),
+            Err(GhrError::ReleaseNotFound { .. })
+        ));
+    }
+
+    #[test]
+    fn test_select_release_no_prerelease() {
+        let releases = [
+            flagged("v1.0.0", false, false),
+            flagged("v1.1.0-rc1", true, true),
+        ];
+        assert!(select_release(&releases, "pre-latest").is_err());
+        assert!(matches!(
+            select_release(&[], "latest"),
+            Err(GhrError::NoReleases)
+        ));
+    }
+
+    #[test]
+    #[tracing_test::traced_test]
+    fn test_select_release_prerelease_fallback() {
+        let releases = [
+            flagged("v2.0.0-rc2", true, true),
+            flagged("v2.0.0-rc1", true, false),
+            flagged("v2.0.0-beta", true, false),
+        ];
+
+        assert!(select_release_with_fallback(&releases, "latest-stable", false).is_err());
+        assert!(!logs_contain("falling back"));
+
+        let release = select_release_with_fallback(&releases, "latest-stable", true).unwrap();
+        assert_eq!(release.tag_name, "v2.0.0-rc1");
+        assert!(logs_contain(
+            "No stable release found, falling back to prerelease v2.0.0-rc1"
+        ));
+
+        // Only "latest-stable" falls back
+        assert!(select_release_with_fallback(&releases, "v1.0.0", true).is_err());
+        // Nothing to fall back to
+        assert!(select_release_with_fallback(
+            &[flagged("v2.0.0-rc2", true, true)],
+            "latest-stable",
+            true
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_fallback_release() {
+        let named = |tag: &str, names: &[&str]| Release {
+            tag_name: tag.to_string(),
+            assets: names
+                .iter()
+                .map(|name| Asset {
+                    name: name.to_string(),
+                    ..Default::default()
+                })
+                .collect(),
+            ..Default::default()
+        };
+        let releases = [
+            named("v3", &["tool-windows.zip"]),
+            named("v2", &["tool-windows.zip", "tool-linux.tar.gz"]),
+            named("v1", &["tool-linux.tar.gz"]),
+        ];
+        let linux = |r: &Release| r.assets.iter().any(|a| a.name.contains("linux"));
+
+        let chosen = fallback_release(&releases, &releases[0], 2, linux);
+        assert_eq!(chosen.tag_name, "v2");
+
+        // Releases that match are kept
+        let chosen = fallback_release(&releases, &releases[1], 2, linux);
+        assert_eq!(chosen.tag_name, "v2");
+
+        // No match within the scan window keeps the selected release
+        let chosen = fallback_release(&releases, &releases[0], 2, |r| r.tag_name == "v0");
+        assert_eq!(chosen.tag_name, "v3");
+        let chosen = fallback_release(&releases, &releases[0], 0, linux);
+        assert_eq!(chosen.tag_name, "v3");
+    }
+
+    #[test]
+    fn test_sort_assets() {
+        let asset = |name: &str, size: u64, download_count: u32| Asset {
+            name: name.to_string(),
+            size,
+            download_count,
+            ..Default::default()
+        };
+        let release = Release {
+            assets: vec![
+                asset("app-linux.tar.gz", 3_000, 50),
+                asset("app-windows.zip", 5_000, 20),
+                asset("SHA256SUMS", 200, 90),
+                asset("app-macos.tar.gz", 4_000, 20),
+            ],
+            ..Default::default()
+        };
+        let sorted = |key| {
+            let mut numbered: Vec<(usize, &Asset)> = release
+                .assets
+                .iter()
+                .enumerate()
+                .map(|(i, a)| (i + 1, a))
+                .collect();
+            sort_assets(&mut numbered, key);
+            numbered.iter().map(|(i, _)| *i).collect::<Vec<_>>()
+        };
+
+        assert_eq!(sorted(SortKey::Date), vec![1, 2, 3, 4]);
+        assert_eq!(sorted(SortKey::Size), vec![2, 4, 1, 3]);
+        assert_eq!(sorted(SortKey::Name), vec![3, 1, 4, 2]);
+        // Ties keep the release order
+        assert_eq!(sorted(SortKey::Downloads), vec![3, 1, 2, 4]);
+    }
+
+    #[test]
+    fn test_releases_matching() {
+        let releases = [
+            release("v2.0.0", &[]),
+            release("v1.10.0", &[]),
+            release("v1.2.1", &[]),
+            release("v10.0.0", &[]),
+            release("nightly-v1.3", &[]),
+            release("v1.0.0-rc1", &[]),
+        ];
+        let tags = |pattern| -> Vec<String> {
+            releases_matching(&releases, pattern)
+                .unwrap()
+                .iter()
+                .map(|r| r.tag_name.clone())
+                .collect()
+        };
+
+        assert_eq!(tags("v1.*"), vec!["v1.10.0", "v1.2.1", "v1.0.0-rc1"]);
+        assert_eq!(tags("v1.?.*"), vec!["v1.2.1", "v1.0.0-rc1"]);
+        assert!(releases_matching(&releases, "v3.*").is_err());
+        assert!(matches!(
+            releases_matching(&releases, "v1.[*"),
+            Err(GhrError::GlobError(_))
+        ));
+
+        assert!(is_tag_glob("v1.*"));
+        assert!(!is_tag_glob("v1.2.3"));
+        assert!(!is_tag_glob("latest"));
+    }
+}
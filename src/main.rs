@@ -1,42 +1,56 @@
-mod auth;
-mod cache;
-mod cli;
-mod constants;
-mod errors;
-mod filters;
-mod git;
-mod github;
-mod models;
-
-use chrono::prelude::*;
+use gh_release::{
+    auth, batch, cache, checksum, cli, config, constants, download, errors, extract, filters, git,
+    github, http, listing, models, output, picker, progress, provider, sign, util,
+};
+
 use cli::Cli;
 use errors::{GhrError, Result};
-use futures::stream::{self, StreamExt};
+use futures::stream::StreamExt;
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
-use jlogger_tracing::{jdebug, jerror, jinfo, JloggerBuilder, LevelFilter, LogTimeFormat};
+use jlogger_tracing::{jdebug, jerror, jinfo, jwarn, JloggerBuilder, LevelFilter, LogTimeFormat};
 use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, USER_AGENT};
-use reqwest::Client;
-use std::io::{self, Write};
-use std::path::PathBuf;
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::fs;
 
-use clap::Parser;
+/// `print!` that returns an error instead of panicking when stdout is closed
+macro_rules! out {
+    ($($arg:tt)*) => {
+        output::write_flushed(io::stdout().lock(), &format!($($arg)*))
+    };
+}
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    let cli = Cli::parse();
+/// `println!` that returns an error instead of panicking when stdout is closed
+macro_rules! outln {
+    ($($arg:tt)*) => {
+        out!("{}\n", format_args!($($arg)*))
+    };
+}
 
-    // Validate that either --repo, --search, or --clone is provided
-    if cli.repo.is_none() && cli.search.is_none() && cli.clone.is_none() && cli.get_file.is_none() {
-        return Err(GhrError::MissingArgument(
-            "Either --repo, --search, --get-file or --clone must be provided. Use --help for more information."
-                .to_string(),
-        ));
+#[tokio::main]
+async fn main() -> ExitCode {
+    match run().await {
+        Ok(()) => ExitCode::SUCCESS,
+        // The reader of our output is gone, which is how `| head` ends a listing
+        Err(e) if e.is_broken_pipe() => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::from(e.exit_code())
+        }
     }
+}
+
+async fn run() -> Result<()> {
+    let mut cli = Cli::parse_args();
 
     let verbose = cli.verbose;
     let log_level = match verbose {
+        // Per-asset messages would drown the final summary
+        _ if cli.summary_only => LevelFilter::ERROR,
         1 => LevelFilter::DEBUG,
         2 => LevelFilter::TRACE,
         _ => LevelFilter::INFO,
@@ -48,6 +62,92 @@ async fn main() -> Result<()> {
         .log_time(LogTimeFormat::TimeLocal)
         .build();
 
+    let config = config::Config::load_default_or(cli.config.as_deref())?;
+    config.apply(&mut cli);
+    // Whether the API was chosen by a flag, the config file or a "host/owner/repo"
+    let mut api_url_chosen = cli.explicit.api_url || config.api_url.is_some();
+    cli.repo = cli::combine_owner_repo(cli.owner.as_deref(), cli.repo.as_deref())?;
+
+    // "host/owner/repo" selects the GitHub (or GitLab) instance as well
+    if let Some((host, repo)) = cli.repo.as_deref().and_then(cli::split_repo_host) {
+        let api_url = cli::api_url_for_host(cli.provider, host);
+        if cli.api_url != constants::GITHUB_API_BASE && cli.api_url != api_url {
+            return Err(GhrError::Generic(format!(
+                "--repo host '{}' conflicts with --api-url {}",
+                host, cli.api_url
+            )));
+        }
+        jinfo!("Using API {} for {}", api_url, repo);
+        cli.repo = Some(repo.to_string());
+        cli.api_url = api_url;
+        api_url_chosen = true;
+    }
+
+    // GITHUB_API_URL or GH_HOST select the GitHub instance when nothing else did
+    if cli.provider == cli::Provider::Github && !api_url_chosen {
+        if let Some(api_url) = cli::api_url_from_env(|name| std::env::var(name).ok()) {
+            jdebug!("Using API {} from the environment", api_url);
+            cli.api_url = api_url;
+        }
+    }
+
+    // GitLab projects may be nested in groups and follow other naming rules
+    if cli.provider == cli::Provider::Github {
+        if let Some(repo) = cli.repo.as_deref() {
+            cli.repo = Some(git::parse_repo(repo)?);
+        }
+    }
+
+    if cli.provider == cli::Provider::Gitlab {
+        if cli.search.is_some()
+            || cli.clone.is_some()
+            || cli.get_file.is_some()
+            || cli.list_branches
+            || cli.repo_info
+            || cli.suggest
+            || cli.tags_as_releases
+        {
+            return Err(GhrError::Generic(
+                "--search, --clone, --get-file, --list-branches, --repo-info, --suggest and \
+                 --tags-as-releases are only supported with --provider github"
+                    .to_string(),
+            ));
+        }
+        if cli.api_url == constants::GITHUB_API_BASE {
+            cli.api_url = constants::GITLAB_API_BASE.to_string();
+        }
+    }
+
+    if cli.auth_check {
+        for line in auth::auth_check(&cli) {
+            outln!("{}", line)?;
+        }
+        return Ok(());
+    }
+
+    let no_mode = cli.repo.is_none()
+        && cli.repos_file.is_none()
+        && cli.search.is_none()
+        && cli.clone.is_none()
+        && cli.get_file.is_none();
+
+    // Fall back to the repository of the git checkout in the current directory
+    if no_mode && cli.provider == cli::Provider::Github {
+        if let Some(repo) = git::detect_origin_repo().await {
+            jinfo!("Using repository '{}' from git remote 'origin'", repo);
+            cli.repo = Some(repo);
+        }
+    }
+
+    // Validate that either --repo, --search, or --clone is provided
+    if no_mode && cli.repo.is_none() {
+        return Err(GhrError::MissingArgument(
+            "Either --repo, --repos-file, --search, --get-file or --clone must be provided (or run inside a GitHub checkout). Use --help for more information."
+                .to_string(),
+        ));
+    }
+    cli.check_mode()?;
+
     let mut header = HeaderMap::new();
 
     header.insert(
@@ -60,14 +160,71 @@ async fn main() -> Result<()> {
         HeaderValue::from_static(constants::GITHUB_API_VERSION),
     );
 
-    if auth::add_auth_header(&cli, &mut header).is_err() {
+    // For requests to hosts other than the API, which must not see the token
+    let public_header = header.clone();
+    let host_token = auth::add_auth_header(&cli, &mut header).unwrap_or_else(|_| {
         jinfo!("No authentication method provided, proceeding unauthenticated");
-    }
-
-    let client = Client::builder().default_headers(header).build()?;
+        None
+    });
+
+    // Asset downloads follow redirects to the storage backend, while API
+    // requests handle redirects themselves to detect renamed repositories
+    let client = http::build_client(
+        header.clone(),
+        &http::ClientOptions {
+            ipv4_only: cli.ipv4_only,
+            max_redirects: Some(cli.max_redirects),
+            min_tls: cli.min_tls,
+            // Assets are saved byte for byte, and are mostly compressed already
+            compression: false,
+        },
+    )?;
+    let public_client = http::build_client(
+        public_header,
+        &http::ClientOptions {
+            ipv4_only: cli.ipv4_only,
+            max_redirects: Some(cli.max_redirects),
+            min_tls: cli.min_tls,
+            compression: false,
+        },
+    )?;
+    let api_client = http::build_client(
+        header,
+        &http::ClientOptions {
+            ipv4_only: cli.ipv4_only,
+            max_redirects: None,
+            min_tls: cli.min_tls,
+            compression: !cli.no_compression,
+        },
+    )?;
+    let retry_policy = http::RetryPolicy {
+        max_retries: cli.max_retries,
+        max_delay: Duration::from_secs(cli.retry_max_delay),
+        budget: cli.retry_budget.map(Duration::from_secs),
+        ..Default::default()
+    };
+    let api = http::ApiClient::new(
+        api_client,
+        http::ApiOptions {
+            timeout: cli.api_timeout.map(Duration::from_secs),
+            retry: retry_policy,
+            follow_renames: !cli.no_follow_rename,
+            offline: cli.offline,
+            tokens: auth::token_pool(&cli).map(Arc::new),
+            host_token: host_token.clone(),
+        },
+    );
+    let releases_api = provider::new(cli.provider, &api, &cli.api_url);
+    let download_timeout = cli.timeout.map(Duration::from_secs);
 
     // Create cache instance
-    let cache = cache::Cache::new(cli.cache);
+    let cache = if cli.offline {
+        cache::Cache::for_offline()
+    } else if cli.cache_negative {
+        cache::Cache::new(cli.cache).with_negative_ttl(Duration::from_secs(cli.cache_negative_ttl))
+    } else {
+        cache::Cache::new(cli.cache)
+    };
 
     // CLONE MODE - handle repository cloning
     if let Some(clone_arg) = cli.clone.as_deref() {
@@ -82,8 +239,9 @@ async fn main() -> Result<()> {
 
         // Validate repository exists
         let repo_info =
-            github::validate_repository_with_base(&client, &cli.api_url, &spec.owner, &spec.repo)
-                .await?;
+            github::validate_repository_with_base(&api, &cli.api_url, &spec.owner, &spec.repo)
+                .await;
+        let repo_info = suggest_if_missing(&cli, &api, repo_info).await?;
         jinfo!(
             "Repository found: {} ({})",
             repo_info.full_name,
@@ -94,18 +252,31 @@ async fn main() -> Result<()> {
             }
         );
 
+        // An empty repository clones fine, but into an empty working tree
+        match github::is_empty_repository(&api, &cli.api_url, &spec.owner, &spec.repo).await {
+            Ok(true) => jwarn!(
+                "Repository {} is empty; the clone will have no files",
+                repo_info.full_name
+            ),
+            Ok(false) => {}
+            Err(e) => jdebug!("Could not check whether the repository is empty: {}", e),
+        }
+
         // Validate ref if specified
-        if let Some(ref_name) = spec.ref_name.as_ref() {
-            let ref_type = github::validate_ref_with_base(
-                &client,
+        let resolved_ref = if let Some(ref_name) = spec.ref_name.as_ref() {
+            let resolved = github::validate_ref_with_base(
+                &api,
                 &cli.api_url,
                 &spec.owner,
                 &spec.repo,
                 ref_name,
             )
             .await?;
-            jinfo!("Reference '{}' found (type: {})", ref_name, ref_type);
-        }
+            jinfo!("Reference '{}' found (type: {})", ref_name, resolved.kind);
+            Some(resolved)
+        } else {
+            None
+        };
 
         // Determine target directory
         let default_dir = git::get_repo_name(&spec.original_url);
@@ -121,17 +292,25 @@ async fn main() -> Result<()> {
         if cli.dry_run {
             eprintln!("\nDry-run mode: Would clone repository");
             eprintln!("  Repository: {}/{}", spec.owner, spec.repo);
-            if let Some(ref_name) = &spec.ref_name {
-                eprintln!("  Ref: {}", ref_name);
+            if let Some(resolved) = &resolved_ref {
+                eprintln!("  Ref: {} ({})", resolved.canonical(), resolved.kind);
             }
             eprintln!("  Target directory: {}", target_dir);
+            if cli.recurse_submodules {
+                eprintln!("  Submodules: yes");
+            }
             eprintln!("\nNo action taken (dry-run mode)");
             return Ok(());
         }
 
         // Execute clone
         jinfo!("Cloning to '{}'...", target_dir);
-        git::execute_git_clone(&clone_url, target_dir, spec.ref_name.as_deref()).await?;
+        let options = git::CloneOptions {
+            recurse_submodules: cli.recurse_submodules,
+            detach: cli.detach,
+            token,
+        };
+        git::execute_git_clone(&clone_url, target_dir, resolved_ref.as_ref(), &options).await?;
 
         jinfo!("Successfully cloned repository to '{}'", target_dir);
         return Ok(());
@@ -143,7 +322,7 @@ async fn main() -> Result<()> {
 
         let pattern = github::parse_search_pattern(search_pattern)?;
         let repositories = github::search_repositories_with_cache(
-            &client,
+            &api,
             &cli.api_url,
             &pattern,
             cli.num,
@@ -156,6 +335,13 @@ async fn main() -> Result<()> {
             return Ok(());
         }
 
+        let repositories = if cli.with_topics {
+            jinfo!("Fetching topics for {} repositories...", repositories.len());
+            github::add_topics(&api, &cli.api_url, repositories, cli.concurrency).await
+        } else {
+            repositories
+        };
+
         // Display results based on format
         match cli.format {
             cli::OutputFormat::Json => {
@@ -167,7 +353,7 @@ async fn main() -> Result<()> {
                     let parts: Vec<&str> = repo.full_name.split('/').collect();
                     if parts.len() == 2 {
                         let tags = github::get_repository_tags(
-                            &client,
+                            &api,
                             &cli.api_url,
                             parts[0],
                             parts[1],
@@ -183,17 +369,15 @@ async fn main() -> Result<()> {
                     }
                 }
 
-                let json = serde_json::to_string_pretty(&repos_with_tags)?;
-                println!("{}", json);
+                let json = output::to_json(&repos_with_tags, cli.json_compact)?;
+                outln!("{}", json)?;
+            }
+            cli::OutputFormat::Csv => {
+                out!("{}", output::repositories_csv(&repositories)?)?;
             }
             cli::OutputFormat::Table => {
                 // Display results in table format
-                eprintln!("{:4} {:<7} {:2}{:40}", "No", "Stars", " ", "Repository",);
-                eprintln!("{:-<108}", "");
-
-                for (i, repo) in repositories.iter().enumerate() {
-                    eprintln!("{:<4} {}", i + 1, repo.summary());
-                }
+                eprintln!("{}", output::repositories_table(&repositories));
 
                 eprintln!("\nFound {} repositories", repositories.len());
             }
@@ -221,11 +405,10 @@ async fn main() -> Result<()> {
 
         // Check if file exists and prompt user for confirmation
         if output_path.exists() {
-            print!(
+            out!(
                 "File '{}' already exists. Overwrite? [y/N]: ",
                 output_path.display()
-            );
-            io::stdout().flush().unwrap();
+            )?;
 
             let mut response = String::new();
             io::stdin()
@@ -242,12 +425,13 @@ async fn main() -> Result<()> {
         let client = Arc::new(client);
         let multi_progress = Arc::new(MultiProgress::new());
 
-        let response = client
+        let mut request = client
             .get(&download_url)
-            .header(ACCEPT, constants::headers::ACCEPT_OCTET_STREAM)
-            .send()
-            .await
-            .map_err(GhrError::Network)?;
+            .header(ACCEPT, constants::headers::ACCEPT_OCTET_STREAM);
+        if let Some(timeout) = download_timeout {
+            request = request.timeout(timeout);
+        }
+        let response = request.send().await.map_err(GhrError::Network)?;
 
         let status = response.status();
         if !status.is_success() {
@@ -293,33 +477,313 @@ async fn main() -> Result<()> {
         return Ok(());
     }
 
-    if let Some(download) = cli.download.as_deref() {
+    let started = Instant::now();
+    let report = Mutex::new(output::RunReport::new(cli.mode_name()));
+    let retry_budget = cli
+        .global_retry_budget
+        .map(|retries| Arc::new(download::RetryBudget::new(retries)));
+    let ctx = RepoContext {
+        client: &client,
+        public_client: &public_client,
+        host_token: host_token.as_ref(),
+        api: &api,
+        releases_api: releases_api.as_ref(),
+        cache: &cache,
+        download_timeout,
+        progress: None,
+        report: cli.summary_json.is_some().then_some(&report),
+        retry_budget: retry_budget.as_ref(),
+    };
+
+    let result = match cli.repos_file.as_deref() {
+        Some(repos_file) => run_batch(&cli, repos_file, &ctx).await,
+        None => {
+            let result = run_repo(&cli, &ctx).await;
+            suggest_if_missing(&cli, &api, result).await
+        }
+    };
+
+    // Written for failed runs as well, which is when CI needs it most
+    if let Some(path) = cli.summary_json.as_deref() {
+        let mut report = report.into_inner().unwrap_or_else(|e| e.into_inner());
+        report.elapsed_secs = started.elapsed().as_secs_f64();
+        report.finish(&result);
+        // Failing to write the report must not hide why the run failed
+        match output::write_json_file(path, &report) {
+            Ok(()) => jinfo!("Run report written to {}", path.display()),
+            Err(e) => jwarn!("Failed to write run report to {}: {}", path.display(), e),
+        }
+    }
+
+    result
+}
+
+/// Clients and settings shared by the release operations on each repository
+struct RepoContext<'a> {
+    client: &'a reqwest::Client,
+    /// Client without the token, for URLs outside the API host
+    public_client: &'a reqwest::Client,
+    /// GitLab token, sent with requests to the API host only
+    host_token: Option<&'a http::HostToken>,
+    api: &'a http::ApiClient,
+    releases_api: &'a dyn provider::ReleaseProvider,
+    cache: &'a cache::Cache,
+    download_timeout: Option<Duration>,
+    /// Progress display shared by repositories processed concurrently
+    progress: Option<MultiProgress>,
+    /// Collects the outcome of each repository's downloads for --summary-json
+    report: Option<&'a Mutex<output::RunReport>>,
+    /// Retries shared by all downloads of the run (--global-retry-budget)
+    retry_budget: Option<&'a Arc<download::RetryBudget>>,
+}
+
+/// The client to fetch a --checksums-url manifest with, which only carries the
+/// token when the manifest is hosted next to the API
+fn checksums_client<'a>(cli: &Cli, ctx: &RepoContext<'a>, url: &str) -> &'a reqwest::Client {
+    http::client_for(url, &cli.api_url, ctx.client, ctx.public_client)
+}
+
+/// Run the operation selected on the command line for every repository in `repos_file`
+///
+/// Up to `--max-concurrent-repos` repositories are processed at a time. A
+/// failure is reported and doesn't stop the other repositories.
+async fn run_batch(cli: &Cli, repos_file: &str, ctx: &RepoContext<'_>) -> Result<()> {
+    let repos = batch::read_repos_file(repos_file)?;
+    let (max_repos, concurrency) =
+        batch::limit_parallelism(cli.max_concurrent_repos, cli.concurrency);
+    jinfo!(
+        "Processing {} repositories, {} at a time",
+        repos.len(),
+        max_repos
+    );
+
+    let ctx = RepoContext {
+        progress: Some(MultiProgress::new()),
+        ..*ctx
+    };
+    let ctx = &ctx;
+    let results = batch::run_concurrently(repos, max_repos, |repo| async move {
+        let mut repo_cli = cli.clone();
+        // Keep same-named assets of different repositories apart
+        if !cli.flat && cli.output_dir.is_none() {
+            repo_cli.directory = Some(batch::repo_dir(cli.directory.as_deref(), &repo));
+        }
+        repo_cli.repo = Some(repo);
+        repo_cli.concurrency = concurrency;
+        let result = run_repo(&repo_cli, ctx).await;
+        suggest_if_missing(cli, ctx.api, result).await
+    })
+    .await;
+
+    let total = results.len();
+    let mut failed = 0;
+    for (repo, result) in results {
+        if let Err(e) = result {
+            if e.is_broken_pipe() {
+                return Err(e);
+            }
+            jerror!("{}: {}", repo, e);
+            failed += 1;
+        }
+    }
+
+    if failed > 0 {
+        return Err(GhrError::Generic(format!(
+            "{} of {} repositories failed",
+            failed, total
+        )));
+    }
+    Ok(())
+}
+
+/// With --suggest, add similarly named repositories to a "repository not found" error
+async fn suggest_if_missing<T>(cli: &Cli, api: &http::ApiClient, result: Result<T>) -> Result<T> {
+    match result {
+        Err(e) if cli.suggest => Err(github::with_suggestions(api, &cli.api_url, e).await),
+        result => result,
+    }
+}
+
+/// Print checksum manifest lines of `files` to stdout, one block per algorithm (--print-checksum)
+///
+/// With several algorithms, each line is labeled with its algorithm.
+fn print_checksums(files: &[(&str, &Path)], algorithms: &[cli::HashAlgorithm]) -> Result<()> {
+    for &algorithm in algorithms {
+        let lines = if algorithms.len() > 1 {
+            checksum::tagged_checksum_lines(files, algorithm)?
+        } else {
+            checksum::checksum_lines(files, algorithm)?
+        };
+        for line in lines {
+            outln!("{}", line)?;
+        }
+    }
+    Ok(())
+}
+
+/// Tags of `repo` standing in for its releases, which it has none of (--tags-as-releases)
+async fn releases_from_tags(
+    cli: &Cli,
+    ctx: &RepoContext<'_>,
+    repo: &str,
+) -> Result<Vec<models::Release>> {
+    jinfo!("No releases in {}, using its tags instead", repo);
+    github::get_tags_as_releases(ctx.api, &cli.api_url, repo, constants::RELEASES_PER_PAGE).await
+}
+
+/// Download the source tarball of `repo` at `git_ref`, for refs without a release
+async fn download_source_archive(
+    cli: &Cli,
+    ctx: &RepoContext<'_>,
+    repo: &str,
+    git_ref: &str,
+) -> Result<()> {
+    let (owner, repo_name) = repo.rsplit_once('/').ok_or_else(|| {
+        GhrError::Generic(format!(
+            "Invalid repository format '{}'. Expected 'owner/repo'",
+            repo
+        ))
+    })?;
+    let dir = match cli.output_dir.as_deref() {
+        Some(template) => download::render_output_dir(template, owner, repo_name, git_ref)?,
+        None => cli
+            .directory
+            .as_deref()
+            .map(PathBuf::from)
+            .unwrap_or_default(),
+    };
+    let name = download::source_archive_name(repo_name, git_ref)?;
+    let url = ctx.releases_api.source_archive_url(repo, git_ref);
+    jinfo!(
+        "No release for '{}', downloading the source archive",
+        git_ref
+    );
+
+    if cli.print_urls {
+        outln!("{}", url)?;
+        return Ok(());
+    }
+    if cli.dry_run {
+        eprintln!("\nDry-run mode: Would download source archive");
+        eprintln!("  - {} from {}", name, url);
+        eprintln!("Destination: {}", dir.join(&name).display());
+        eprintln!("\nNo action taken (dry-run mode)");
+        return Ok(());
+    }
+    if cli.offline {
+        return Err(GhrError::Offline(
+            "source archive downloads are not available".to_string(),
+        ));
+    }
+
+    if !dir.as_os_str().is_empty() {
+        fs::create_dir_all(&dir).await?;
+    }
+    let task = download::DownloadTask {
+        name: name.clone(),
+        url,
+        output_path: dir.join(&name),
+        // Archives are generated on demand, their size is unknown
+        size: 0,
+        digest: None,
+        modified: None,
+    };
+    let bars = progress::DownloadBars::new(
+        ctx.progress.clone().unwrap_or_default(),
+        std::slice::from_ref(&task),
+        cli.total_progress,
+    );
+    let options = download::DownloadOptions {
+        concurrency: 1,
+        timeout: ctx.download_timeout,
+        retry: *ctx.api.retry_policy(),
+        retry_budget: ctx.retry_budget.cloned(),
+        checksum_retries: 0,
+        min_rate: cli.min_rate(),
+        checksums: None,
+        progress: Some(bars.callback()),
+        host_token: ctx.host_token.cloned(),
+    };
+    let results =
+        download::download_assets(Arc::new(ctx.client.clone()), vec![task], &options).await;
+    bars.finish(&results);
+    for result in results {
+        let task = result?;
+        jinfo!("Source archive saved to: {}", task.output_path.display());
+        if cli.extract {
+            if let Some(format) = extract::resolve_format(&task.name, cli.archive_format) {
+                let dest = extract::extract_archive(&task.output_path, format, cli.allow_symlinks)?;
+                jinfo!("Extracted '{}' to '{}'", task.name, dest.display());
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Download, list or show the releases of the repository given by `--repo`
+async fn run_repo(cli: &Cli, ctx: &RepoContext<'_>) -> Result<()> {
+    // --ref downloads the release of that tag if there is one, or else the source
+    let ref_cli;
+    let cli = match cli.git_ref.as_deref() {
+        Some(git_ref) => {
+            let repo = cli.repo.as_deref().ok_or_else(|| {
+                GhrError::MissingArgument("--repo is required for --ref".to_string())
+            })?;
+            match ctx
+                .releases_api
+                .releases(repo, Some(git_ref), Some(ctx.cache))
+                .await
+            {
+                Ok(_) => {
+                    jinfo!("Release '{}' found, downloading its assets", git_ref);
+                    ref_cli = Cli {
+                        download: Some(git_ref.to_string()),
+                        git_ref: None,
+                        ..cli.clone()
+                    };
+                    &ref_cli
+                }
+                Err(GhrError::ReleaseNotFound { .. }) => {
+                    return download_source_archive(cli, ctx, repo, git_ref).await;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        None => cli,
+    };
+
+    if cli.download.is_some()
+        || cli.since_release.is_some()
+        || cli.download_index.is_some()
+        || cli.all_releases
+    {
         let repo = cli.repo.as_deref().ok_or_else(|| {
             GhrError::MissingArgument("--repo is required for download mode".to_string())
         })?;
-        let releases =
-            github::get_release_info_with_cache(&client, &cli.api_url, repo, None, Some(&cache))
-                .await?;
-
-        // Support "latest" as a special keyword to download the most recent release
-        let release = if download == "latest" {
-            jinfo!("Downloading latest release");
-            releases.first().ok_or_else(|| GhrError::NoReleases)?
+        let releases = if cli.all_releases {
+            ctx.releases_api
+                .all_releases(repo, cli.max_releases)
+                .await?
         } else {
-            jinfo!("Downloading release: {}", download);
-            releases
-                .iter()
-                .find(|r| r.tag_name == download)
-                .ok_or_else(|| GhrError::ReleaseNotFound {
-                    tag: download.to_string(),
-                })?
+            ctx.releases_api
+                .releases(repo, None, Some(ctx.cache))
+                .await?
         };
-
-        // Create output directory if specified
-        if let Some(directory) = &cli.directory {
-            fs::create_dir_all(directory).await?;
-            jinfo!("Saving assets to: {}", directory);
+        if releases.is_empty() {
+            // A plain --download selects a tag and gets its source archive instead
+            let spec = cli.download.as_deref().filter(|d| !listing::is_tag_glob(d));
+            if let (true, Some(spec)) = (cli.tags_as_releases, spec) {
+                let tags = releases_from_tags(cli, ctx, repo).await?;
+                let tag = listing::select_release(&tags, spec)?;
+                return download_source_archive(cli, ctx, repo, &tag.tag_name).await;
+            }
+            return Err(GhrError::NoReleases);
         }
+        let index_spec = cli
+            .download_index
+            .as_deref()
+            .map(listing::parse_download_index)
+            .transpose()?;
 
         // Parse filter patterns
         let filter_patterns: Vec<filters::FilterType> = if let Some(filter) = cli.filter.as_deref()
@@ -332,53 +796,255 @@ async fn main() -> Result<()> {
             Vec::new()
         };
 
-        // Parse owner/repo for API URL construction
-        let parts: Vec<&str> = repo.split('/').collect();
-        if parts.len() != 2 {
-            return Err(GhrError::Generic(format!(
+        let size_range = filters::SizeRange {
+            min: cli.min_size,
+            max: cli.max_size,
+        };
+
+        // Select the release(s) to download; several releases go into per-tag directories
+        let (selected, per_tag_dirs): (Vec<&models::Release>, bool) = if cli.all_releases {
+            if releases.len() == cli.max_releases {
+                jwarn!(
+                    "Mirroring the newest {} releases only (--max-releases)",
+                    cli.max_releases
+                );
+            }
+            jinfo!("Mirroring {} release(s)", releases.len());
+            (releases.iter().collect(), true)
+        } else if let Some(since) = cli.since_release.as_deref() {
+            let newer = listing::releases_since(&releases, since)?;
+            jinfo!("Found {} release(s) newer than {}", newer.len(), since);
+            (newer, true)
+        } else if let Some((tag, _)) = &index_spec {
+            jinfo!("Downloading release: {}", tag);
+            let release = releases
+                .iter()
+                .find(|r| &r.tag_name == tag)
+                .ok_or_else(|| GhrError::ReleaseNotFound { tag: tag.clone() })?;
+            (vec![release], false)
+        } else if let Some(pattern) = cli.download.as_deref().filter(|d| listing::is_tag_glob(d)) {
+            let matching = listing::releases_matching(&releases, pattern)?;
+            jinfo!("Found {} release(s) matching {}", matching.len(), pattern);
+            (matching, true)
+        } else {
+            let download = cli.download.as_deref().unwrap_or_default();
+            let mut release = listing::select_release_with_fallback(
+                &releases,
+                download,
+                cli.prerelease_fallback,
+            )?;
+            if let Some(scan) = cli.fallback_scan {
+                let has_match = |release: &models::Release| {
+                    release.assets.iter().any(|asset| {
+                        filters::apply_filters(&asset.name, &filter_patterns)
+                            && size_range.contains(asset.size)
+                    })
+                };
+                let fallback = listing::fallback_release(&releases, release, scan, has_match);
+                if !std::ptr::eq(fallback, release) {
+                    jinfo!(
+                        "No matching assets in {}, using release {} instead",
+                        release.tag_name,
+                        fallback.tag_name
+                    );
+                    release = fallback;
+                }
+            }
+            jinfo!("Downloading release: {}", release.tag_name);
+            (vec![release], false)
+        };
+
+        // Create output directory if specified
+        if let Some(directory) = &cli.directory {
+            fs::create_dir_all(directory).await?;
+            jinfo!("Saving assets to: {}", directory);
+        }
+        let base_dir = cli
+            .directory
+            .as_deref()
+            .map(PathBuf::from)
+            .unwrap_or_default();
+
+        // Parse owner/repo for the output directory; GitLab projects may be
+        // nested in several groups, which all count as the owner
+        let (owner, repo_name) = repo.rsplit_once('/').ok_or_else(|| {
+            GhrError::Generic(format!(
                 "Invalid repository format '{}'. Expected 'owner/repo'",
                 repo
-            )));
-        }
-        let owner = parts[0];
-        let repo_name = parts[1];
+            ))
+        })?;
 
-        // Collect assets to download with filtering
         let mut assets_to_download = Vec::new();
-        for asset in &release.assets {
-            let name = &asset.name;
+        // Assets of the selected releases left out by the filters
+        let mut skipped = 0;
+        // Releases with at least one asset to download
+        let mut release_count = 0;
+        // Asset of each download, by output path, for --assets-json
+        let mut downloaded_assets = HashMap::new();
+        for release in &selected {
+            // Collect assets by index, or those matching the filters
+            let mut matching_assets = Vec::new();
+            if let Some((_, indices)) = &index_spec {
+                matching_assets = listing::assets_by_index(release, indices)?;
+            } else {
+                for asset in &release.assets {
+                    // Apply advanced filtering
+                    if !filters::apply_filters(&asset.name, &filter_patterns) {
+                        jinfo!("Skipping asset '{}' due to filter", asset.name);
+                        continue;
+                    }
+                    if !size_range.contains(asset.size) {
+                        jinfo!("Skipping asset '{}' due to size", asset.name);
+                        continue;
+                    }
+                    matching_assets.push(asset);
+                }
+            }
 
-            // Apply advanced filtering
-            if !filters::apply_filters(name, &filter_patterns) {
-                jinfo!("Skipping asset '{}' due to filter", name);
-                continue;
+            // Assets still being uploaded fail to download
+            if !cli.include_incomplete {
+                matching_assets = filters::skip_incomplete(matching_assets);
             }
 
-            // Use API URL for downloading (works with private repos)
-            // Format: https://api.github.com/repos/{owner}/{repo}/releases/assets/{asset_id}
-            let download_url = format!(
-                "{}/repos/{}/{}/releases/assets/{}",
-                cli.api_url, owner, repo_name, asset.id
-            );
+            // Let the user narrow the matching assets down interactively
+            if cli.pick && !matching_assets.is_empty() {
+                let picked = picker::pick_assets(&matching_assets)?;
+                matching_assets = picked.into_iter().map(|i| matching_assets[i]).collect();
+            }
 
-            // Get asset size for progress bar
-            let size = asset.size;
+            if cli.with_sidecars {
+                matching_assets = filters::with_sidecars(release, matching_assets);
+            }
 
-            // Construct output path
-            let output_path = if let Some(directory) = &cli.directory {
-                PathBuf::from(directory).join(name)
+            if cli.print_urls {
+                for url in listing::release_urls(release, &matching_assets) {
+                    outln!("{}", url)?;
+                }
+                continue;
+            }
+
+            let release_dir = if let Some(template) = cli.output_dir.as_deref() {
+                download::render_output_dir(template, owner, repo_name, &release.tag_name)?
+            } else if per_tag_dirs {
+                base_dir.join(download::sanitize_component(&release.tag_name)?)
             } else {
-                PathBuf::from(name)
+                base_dir.clone()
             };
 
-            assets_to_download.push((name.clone(), download_url, output_path, size));
+            skipped += release.assets.len().saturating_sub(matching_assets.len());
+            if !matching_assets.is_empty() {
+                release_count += 1;
+            }
+            for asset in matching_assets {
+                let name = &asset.name;
+                if cli.assets_json {
+                    downloaded_assets.insert(release_dir.join(name), asset);
+                }
+
+                assets_to_download.push(download::DownloadTask {
+                    name: name.clone(),
+                    url: ctx.releases_api.asset_url(repo, asset),
+                    output_path: release_dir.join(name),
+                    size: asset.size,
+                    digest: asset.digest.clone(),
+                    modified: if cli.preserve_timestamps {
+                        asset.modified_at()
+                    } else {
+                        None
+                    },
+                });
+            }
+        }
+
+        if cli.print_urls {
+            return Ok(());
         }
 
         if assets_to_download.is_empty() {
+            if cli.require_match {
+                return Err(filters::no_match_error(&selected));
+            }
             jinfo!("No assets to download");
             return Ok(());
         }
 
+        if cli.verify_only && !cli.print_checksum.is_empty() {
+            let files: Vec<(&str, &Path)> = assets_to_download
+                .iter()
+                .map(|task| (task.name.as_str(), task.output_path.as_path()))
+                .collect();
+            return print_checksums(&files, &cli.print_checksum);
+        }
+
+        if cli.verify_only {
+            let checksums = match cli.checksums_url.as_deref() {
+                Some(url) => {
+                    checksum::fetch_checksums(checksums_client(cli, ctx, url), url, ctx.host_token)
+                        .await?
+                }
+                None => Default::default(),
+            };
+
+            // A published checksums file takes precedence over the API digest
+            let expected_digest =
+                |task: &download::DownloadTask| task.expected_sha256(Some(&checksums));
+            let digest_source = |task: &download::DownloadTask| {
+                task.expected_sha256_with_source(Some(&checksums))
+                    .map(|(_, source)| source)
+            };
+
+            if cli.parallel_hash_verify {
+                let files = assets_to_download
+                    .iter()
+                    .map(|task| (task.output_path.clone(), task.size, expected_digest(task)))
+                    .collect();
+                let statuses =
+                    checksum::check_existing_all(files, checksum::hash_parallelism()).await;
+                let mut report = checksum::HashReport::default();
+                for (task, status) in assets_to_download.iter().zip(statuses) {
+                    let outcome = checksum::HashOutcome::from_status(status?, digest_source(task));
+                    report.add(task.output_path.display().to_string(), outcome);
+                }
+                eprintln!("{}", output::hash_report_table(&report));
+                return match report.failed() {
+                    0 => Ok(()),
+                    failed => Err(GhrError::Generic(format!(
+                        "{} of {} file(s) failed verification",
+                        failed,
+                        assets_to_download.len()
+                    ))),
+                };
+            }
+
+            let mut failed = 0;
+            for task in &assets_to_download {
+                let expected = expected_digest(task);
+                let status =
+                    checksum::check_existing(&task.output_path, task.size, expected.as_deref())?;
+                let path = task.output_path.display();
+                match &status {
+                    checksum::FileStatus::Ok => eprintln!("OK       {}", path),
+                    checksum::FileStatus::Missing => eprintln!("MISSING  {}", path),
+                    checksum::FileStatus::Corrupt(reason) => {
+                        eprintln!("CORRUPT  {} ({})", path, reason)
+                    }
+                }
+                if status != checksum::FileStatus::Ok {
+                    failed += 1;
+                }
+            }
+
+            if failed > 0 {
+                return Err(GhrError::Generic(format!(
+                    "{} of {} file(s) missing or corrupt",
+                    failed,
+                    assets_to_download.len()
+                )));
+            }
+            jinfo!("All {} file(s) verified", assets_to_download.len());
+            return Ok(());
+        }
+
         // Handle dry-run mode
         if cli.dry_run {
             eprintln!(
@@ -388,17 +1054,17 @@ async fn main() -> Result<()> {
             eprintln!("{:-<80}", "");
 
             let mut total_size: u64 = 0;
-            for (name, _, _, size) in &assets_to_download {
-                let size_mb = *size as f64 / 1_048_576.0;
-                eprintln!("  - {} ({:.2} MB)", name, size_mb);
-                total_size += size;
+            for task in &assets_to_download {
+                eprintln!("  - {} ({})", task.name, util::humanize_bytes(task.size));
+                total_size += task.size;
             }
 
-            let total_mb = total_size as f64 / 1_048_576.0;
             eprintln!("{:-<80}", "");
-            eprintln!("Total size: {:.2} MB", total_mb);
+            eprintln!("Total size: {}", util::humanize_bytes(total_size));
 
-            if let Some(directory) = &cli.directory {
+            if let Some(template) = &cli.output_dir {
+                eprintln!("Destination: {}", template);
+            } else if let Some(directory) = &cli.directory {
                 eprintln!("Destination: {}", directory);
             } else {
                 eprintln!("Destination: current directory");
@@ -408,75 +1074,97 @@ async fn main() -> Result<()> {
             return Ok(());
         }
 
+        if let Some(limit) = cli.max_assets {
+            download::check_asset_limit(&assets_to_download, limit)?;
+        }
+
+        if cli.offline {
+            return Err(GhrError::Offline(
+                "asset downloads are not available; use --dry-run or --print-urls".to_string(),
+            ));
+        }
+
+        if cli.all_releases && !cli.assume_yes {
+            let total_size: u64 = assets_to_download.iter().map(|task| task.size).sum();
+            let prompt = format!(
+                "Download {} asset(s) ({}) from {} release(s)?",
+                assets_to_download.len(),
+                util::humanize_bytes(total_size),
+                release_count
+            );
+            if !picker::confirm(&prompt, "Pass --assume-yes to mirror without asking.")? {
+                return Err(GhrError::Generic("Mirroring cancelled".to_string()));
+            }
+        }
+
+        // Load the key up front so that a bad key fails before downloading
+        let signing_key = match cli.signing_key.as_deref() {
+            Some(path) if cli.sign_output => Some(sign::load_secret_key(path)?),
+            _ => None,
+        };
+
         jinfo!(
             "Downloading {} asset(s) with concurrency limit of {}",
             assets_to_download.len(),
             cli.concurrency
         );
 
-        // Setup multi-progress bar
-        let multi_progress = Arc::new(MultiProgress::new());
-        let client = Arc::new(client);
-
-        // Parallel download with concurrency limit
-        let download_results: Vec<Result<String>> = stream::iter(assets_to_download)
-            .map(|(name, url, output_path, size)| {
-                let client = Arc::clone(&client);
-                let multi_progress = Arc::clone(&multi_progress);
-
-                async move {
-                    // Create progress bar for this asset
-                    let pb = multi_progress.add(ProgressBar::new(size));
-                    pb.set_style(
-                        ProgressStyle::default_bar()
-                            .template("{msg}\n{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({eta})")
-                            .unwrap()
-                            .progress_chars("#>-"),
-                    );
-                    pb.set_message(format!("Downloading: {}", name));
-
-                    jdebug!("Download URL: {}", url);
-
-                    // Download with progress tracking
-                    let response = client
-                        .get(&url)
-                        .header(ACCEPT, constants::headers::ACCEPT_OCTET_STREAM)
-                        .send()
-                        .await
-                        .map_err(GhrError::Network)?;
-
-                    let status = response.status();
-                    if !status.is_success() {
-                        pb.finish_with_message(format!("Failed: {} (HTTP {})", name, status));
-                        return Err(GhrError::GitHubApi(format!("HTTP {} for '{}'", status, name)));
-                    }
-
-                    // Read bytes with progress
-                    let mut downloaded: u64 = 0;
-                    let mut bytes_vec = Vec::new();
-                    let mut stream = response.bytes_stream();
-
-                    while let Some(chunk_result) = stream.next().await {
-                        let chunk =
-                            chunk_result.map_err(GhrError::Network)?;
-                        downloaded += chunk.len() as u64;
-                        bytes_vec.extend_from_slice(&chunk);
-                        pb.set_position(downloaded);
-                    }
+        // Fetch published checksums up front so that a bad URL fails before downloading
+        let checksums = if let Some(url) = cli.checksums_url.as_deref() {
+            jinfo!("Fetching checksums from {}", url);
+            Some(Arc::new(
+                checksum::fetch_checksums(checksums_client(cli, ctx, url), url, ctx.host_token)
+                    .await?,
+            ))
+        } else {
+            None
+        };
+        // Per-release directories only need to exist once something is downloaded into them
+        if per_tag_dirs || cli.output_dir.is_some() {
+            for task in &assets_to_download {
+                if let Some(parent) = task.output_path.parent() {
+                    fs::create_dir_all(parent).await?;
+                }
+            }
+        }
 
-                    pb.finish_with_message(format!("Complete: {}", name));
+        let started = Instant::now();
 
-                    // Write to file
-                    fs::write(&output_path, &bytes_vec)
-                        .await
-                        .map_err(GhrError::Io)?;
+        // Identical assets are downloaded once and linked to the other locations
+        let (assets_to_download, duplicates) = if cli.dedupe {
+            download::split_duplicates(assets_to_download)
+        } else {
+            (assets_to_download, Vec::new())
+        };
 
-                    Ok(name)
-                }
-            })
-            .buffer_unordered(cli.concurrency)
-            .collect()
-            .await;
+        // No progress bars with --summary-only
+        let bars = (!cli.summary_only).then(|| {
+            progress::DownloadBars::new(
+                ctx.progress.clone().unwrap_or_default(),
+                &assets_to_download,
+                cli.total_progress,
+            )
+        });
+        let options = download::DownloadOptions {
+            concurrency: cli.concurrency,
+            timeout: ctx.download_timeout,
+            retry: *ctx.api.retry_policy(),
+            retry_budget: ctx.retry_budget.cloned(),
+            checksum_retries: cli.retry_on_checksum_mismatch,
+            min_rate: cli.min_rate(),
+            progress: bars.as_ref().map(|bars| bars.callback()),
+            checksums,
+            host_token: ctx.host_token.cloned(),
+        };
+        let client = Arc::new(ctx.client.clone());
+        let mut download_results =
+            download::download_assets(Arc::clone(&client), assets_to_download, &options).await;
+        let linked =
+            download::link_duplicates(client, duplicates, &download_results, &options).await;
+        download_results.extend(linked);
+        if let Some(bars) = &bars {
+            bars.finish(&download_results);
+        }
 
         // Check for errors
         let mut errors = Vec::new();
@@ -484,25 +1172,155 @@ async fn main() -> Result<()> {
 
         for result in download_results {
             match result {
-                Ok(name) => successes.push(name),
+                Ok(task) => successes.push(task),
+                Err(e) => errors.push(e),
+            }
+        }
+
+        if cli.assets_json {
+            for task in &successes {
+                let Some(asset) = downloaded_assets.get(&task.output_path) else {
+                    continue;
+                };
+                let record = download::AssetRecord {
+                    asset,
+                    source_url: &task.url,
+                    sha256: options
+                        .checksums
+                        .as_ref()
+                        .and_then(|sums| sums.get(&task.name))
+                        .map(String::as_str),
+                };
+                match download::write_asset_json(&task.output_path, &record) {
+                    Ok(path) => jdebug!("Wrote metadata to '{}'", path.display()),
+                    Err(e) => errors.push(e.for_asset(&task.name)),
+                }
+            }
+        }
+
+        if !cli.print_checksum.is_empty() {
+            let files: Vec<(&str, &Path)> = successes
+                .iter()
+                .map(|task| (task.name.as_str(), task.output_path.as_path()))
+                .collect();
+            if let Err(e) = print_checksums(&files, &cli.print_checksum) {
+                errors.push(e);
+            }
+        }
+
+        if let Some(key) = &signing_key {
+            let dir = Path::new(cli.directory.as_deref().unwrap_or("."));
+            // Name files relative to the manifest, like sha256sum run in its directory
+            let files: Vec<(String, &Path)> = successes
+                .iter()
+                .map(|task| {
+                    let path = task.output_path.as_path();
+                    let name = path.strip_prefix(dir).unwrap_or(path);
+                    (name.display().to_string(), path)
+                })
+                .collect();
+            let files: Vec<(&str, &Path)> = files
+                .iter()
+                .map(|(name, path)| (name.as_str(), *path))
+                .collect();
+            match sign::write_signed_manifest(dir, &files, key) {
+                Ok(path) => jinfo!("Wrote signed manifest '{}'", path.display()),
                 Err(e) => errors.push(e),
             }
         }
 
+        // Extract downloaded archives
+        if cli.extract {
+            for task in &successes {
+                let name = &task.name;
+                let Some(format) = extract::resolve_format(name, cli.archive_format) else {
+                    jinfo!("Not extracting '{}': not a recognized archive", name);
+                    continue;
+                };
+                match extract::extract_archive(&task.output_path, format, cli.allow_symlinks) {
+                    Ok(dest) => jinfo!("Extracted '{}' to '{}'", name, dest.display()),
+                    Err(e) => errors.push(e),
+                }
+            }
+        }
+
+        if cli.parallel_hash_verify {
+            let mut report = checksum::HashReport::default();
+            for task in &successes {
+                // Downloads were verified against the same digest
+                let outcome = match task.expected_sha256_with_source(options.checksums.as_deref()) {
+                    Some((_, source)) => checksum::HashOutcome::Pass(source),
+                    None => checksum::HashOutcome::MissingSum,
+                };
+                report.add(task.output_path.display().to_string(), outcome);
+            }
+            for error in &errors {
+                if let GhrError::DownloadFailed { asset, source } = error {
+                    report.add(
+                        asset.clone(),
+                        checksum::HashOutcome::Fail(source.to_string()),
+                    );
+                }
+            }
+            eprintln!("{}", output::hash_report_table(&report));
+        }
+
+        if let Some(report) = ctx.report {
+            let tags: Vec<&str> = selected.iter().map(|r| r.tag_name.as_str()).collect();
+            report
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .record(repo, &tags, &successes, &errors, skipped);
+        }
+
         // Report results
-        if !successes.is_empty() {
-            jinfo!("Successfully downloaded {} asset(s)", successes.len());
+        if cli.summary_only {
+            let summary = output::DownloadSummary {
+                downloaded: successes.len(),
+                total_bytes: successes.iter().map(|task| task.size).sum(),
+                elapsed: started.elapsed(),
+                failed: errors
+                    .iter()
+                    .filter_map(|error| match error {
+                        GhrError::DownloadFailed { asset, .. } => Some(asset.clone()),
+                        _ => None,
+                    })
+                    .collect(),
+                errors: errors.iter().map(ToString::to_string).collect(),
+            };
+            for line in output::download_summary(&summary) {
+                eprintln!("{}", line);
+            }
+        } else if !successes.is_empty() {
+            let total_size: u64 = successes.iter().map(|task| task.size).sum();
+            if cli.all_releases {
+                jinfo!(
+                    "Successfully mirrored {} asset(s) of {} release(s), {}",
+                    successes.len(),
+                    release_count,
+                    util::humanize_bytes(total_size)
+                );
+            } else {
+                jinfo!(
+                    "Successfully downloaded {} asset(s), {}",
+                    successes.len(),
+                    util::humanize_bytes(total_size)
+                );
+            }
         }
 
+        if errors.len() == 1 {
+            // Reported by main() like any other error, keeping its exit code
+            return Err(errors.remove(0));
+        }
         if !errors.is_empty() {
-            jerror!("Failed to download {} asset(s):", errors.len());
-            for error in &errors {
-                jerror!("  - {}", error);
+            if !cli.summary_only {
+                let messages: Vec<String> = errors.iter().map(ToString::to_string).collect();
+                for line in output::download_error_lines(&messages, cli.quiet_errors) {
+                    jerror!("{}", line);
+                }
             }
-            return Err(GhrError::Generic(format!(
-                "Download failed with {} error(s)",
-                errors.len()
-            )));
+            return Err(GhrError::DownloadsFailed(errors));
         }
 
         return Ok(());
@@ -513,73 +1331,227 @@ async fn main() -> Result<()> {
         GhrError::MissingArgument("--repo is required for info/list mode".to_string())
     })?;
 
-    if let Some(info_tags) = cli.info.as_deref() {
+    if let Some(tag) = cli.list_assets.as_deref() {
+        // ASSETS MODE - show the numbered assets of a release
+        let releases = ctx
+            .releases_api
+            .releases(repo, Some(tag), Some(ctx.cache))
+            .await?;
+        let release = releases.first().ok_or_else(|| GhrError::ReleaseNotFound {
+            tag: tag.to_string(),
+        })?;
+
+        // Numbering matches the indices accepted by --download-index, also when
+        // some assets are hidden by the size range
+        let size_range = filters::SizeRange {
+            min: cli.min_size,
+            max: cli.max_size,
+        };
+        let mut numbered: Vec<(usize, &models::Asset)> = release
+            .assets
+            .iter()
+            .enumerate()
+            .map(|(i, asset)| (i + 1, asset))
+            .filter(|(_, asset)| size_range.contains(asset.size))
+            .collect();
+        listing::sort_assets(&mut numbered, cli.sort_key());
+        if cli.reverse {
+            numbered.reverse();
+        }
+
+        match cli.format {
+            cli::OutputFormat::Json => {
+                let assets: Vec<&models::Asset> = numbered.iter().map(|(_, a)| *a).collect();
+                let json = output::to_json(&assets, cli.json_compact)?;
+                outln!("{}", json)?;
+            }
+            cli::OutputFormat::Csv => {
+                out!("{}", output::assets_csv(&numbered, cli.pretty_size)?)?;
+            }
+            cli::OutputFormat::Table => {
+                eprintln!("{}", output::assets_table(&numbered));
+
+                eprintln!(
+                    "\nShowing {} of {} asset(s) in {}",
+                    numbered.len(),
+                    release.assets.len(),
+                    tag
+                );
+            }
+        }
+    } else if cli.repo_info {
+        // REPOSITORY INFO MODE - show the repository's metadata
+        let (owner, repo_name) = repo.split_once('/').ok_or_else(|| {
+            GhrError::Generic(format!(
+                "Invalid repository format '{}'. Expected 'owner/repo'",
+                repo
+            ))
+        })?;
+        let mut info =
+            github::validate_repository_with_base(ctx.api, &cli.api_url, owner, repo_name).await?;
+        let releases = ctx
+            .releases_api
+            .releases(repo, None, Some(ctx.cache))
+            .await?;
+        // The newest release that is neither a prerelease nor a draft
+        info.latest_release = listing::select_release(&releases, "latest-stable")
+            .ok()
+            .map(|release| release.tag_name.clone());
+
+        match cli.format {
+            cli::OutputFormat::Json => {
+                outln!("{}", output::to_json(&info, cli.json_compact)?)?;
+            }
+            cli::OutputFormat::Csv => {
+                out!("{}", output::repository_info_csv(&info, cli.pretty_size)?)?;
+            }
+            cli::OutputFormat::Table => {
+                eprintln!("{}", output::repository_info_table(&info));
+            }
+        }
+    } else if cli.list_branches {
+        // BRANCHES MODE - show branches with their head commits
+        let (owner, repo_name) = repo.split_once('/').ok_or_else(|| {
+            GhrError::Generic(format!(
+                "Invalid repository format '{}'. Expected 'owner/repo'",
+                repo
+            ))
+        })?;
+        let limit = if cli.num == 0 { usize::MAX } else { cli.num };
+        let branches = github::list_branches(
+            ctx.api,
+            &cli.api_url,
+            owner,
+            repo_name,
+            constants::BRANCHES_PER_PAGE,
+            limit,
+        )
+        .await?;
+
+        match cli.format {
+            cli::OutputFormat::Json => {
+                let json = output::to_json(&branches, cli.json_compact)?;
+                outln!("{}", json)?;
+            }
+            cli::OutputFormat::Csv => {
+                out!("{}", output::branches_csv(&branches)?)?;
+            }
+            cli::OutputFormat::Table => {
+                eprintln!("{}", output::branches_table(&branches));
+
+                eprintln!("\nShowing {} branches", branches.len());
+            }
+        }
+    } else if let Some(info_tags) = cli.info.as_deref() {
         // INFO MODE - show detailed information about specific versions
         let tags: Vec<&str> = info_tags.split(',').map(|s| s.trim()).collect();
+        let as_json = matches!(cli.format, cli::OutputFormat::Json) && !cli.print_urls;
+        let mut found = Vec::new();
 
-        for tag in tags {
+        for &tag in &tags {
             jinfo!("Fetching information for release: {}", tag);
-            let releases = github::get_release_info_with_cache(
-                &client,
-                &cli.api_url,
-                repo,
-                Some(tag),
-                Some(&cache),
-            )
-            .await?;
+            let releases = ctx
+                .releases_api
+                .releases(repo, Some(tag), Some(ctx.cache))
+                .await?;
 
             if let Some(release) = releases.first() {
-                println!("\n{}", "=".repeat(80));
-                println!("{}", release);
-                if let Some(body) = &release.body {
-                    println!("\nRelease Notes:");
-                    println!("{}", "-".repeat(80));
-                    println!("{}", body);
+                if cli.open_discussion {
+                    match &release.discussion_url {
+                        Some(url) => {
+                            jinfo!("Opening {}", url);
+                            util::open_in_browser(url)?;
+                        }
+                        None => jwarn!("Release '{}' has no discussion", release.tag_name),
+                    }
+                }
+                if cli.print_urls {
+                    let assets: Vec<&models::Asset> = release.assets.iter().collect();
+                    for url in listing::release_urls(release, &assets) {
+                        outln!("{}", url)?;
+                    }
+                    continue;
                 }
-                println!("{}", "=".repeat(80));
+                if as_json {
+                    found.extend(releases);
+                    continue;
+                }
+
+                outln!(
+                    "{}",
+                    output::release_info(release, cli.info_section(), cli.truncate_notes)
+                )?;
             }
         }
+
+        if as_json {
+            let json = output::releases_info_json(&found, tags.len() > 1, cli.json_compact)?;
+            outln!("{}", json)?;
+        }
     } else {
         // LIST MODE - show list of recent releases
-        let releases =
-            github::get_release_info_with_cache(&client, &cli.api_url, repo, None, Some(&cache))
-                .await?;
-        let releases_to_show: Vec<_> = releases.iter().take(cli.num).collect();
+        let mut releases = if cli.num == 0 {
+            ctx.releases_api.all_releases(repo, usize::MAX).await?
+        } else {
+            ctx.releases_api
+                .releases(repo, None, Some(ctx.cache))
+                .await?
+        };
+        if releases.is_empty() && cli.tags_as_releases {
+            releases = releases_from_tags(cli, ctx, repo).await?;
+        }
+        let releases_to_show = listing::listed_releases(&releases, cli)?;
+        if cli.count_only {
+            outln!("{}", releases_to_show.len())?;
+            return Ok(());
+        }
+        let dates = if cli.relative_dates {
+            output::DateStyle::Relative(chrono::Utc::now())
+        } else {
+            output::DateStyle::Absolute
+        };
 
         match cli.format {
             cli::OutputFormat::Json => {
-                let json = serde_json::to_string_pretty(&releases_to_show)?;
-                println!("{}", json);
+                let json = if cli.compact {
+                    let compact: Vec<models::CompactRelease> = releases_to_show
+                        .iter()
+                        .map(|release| models::CompactRelease::new(release))
+                        .collect();
+                    output::to_json(&compact, cli.json_compact)?
+                } else {
+                    let summaries: Vec<models::ReleaseWithTotals> = releases_to_show
+                        .iter()
+                        .map(|release| models::ReleaseWithTotals::new(release))
+                        .collect();
+                    output::to_json(&summaries, cli.json_compact)?
+                };
+                outln!("{}", json)?;
+            }
+            cli::OutputFormat::Csv => {
+                out!(
+                    "{}",
+                    output::releases_csv(
+                        &releases_to_show,
+                        cli.release_columns(),
+                        cli.pretty_size
+                    )?
+                )?;
+            }
+            cli::OutputFormat::Table if cli.oneline => {
+                for line in output::releases_oneline(&releases_to_show, dates) {
+                    eprintln!("{}", line);
+                }
             }
             cli::OutputFormat::Table => {
                 eprintln!(
-                    "{:4} {:20} {:30} {:15} {:10}",
-                    "No", "Tag", "Name", "Published", "Assets"
+                    "{}",
+                    output::releases_table(&releases_to_show, cli.release_columns(), dates)
                 );
-                eprintln!("{:-<108}", "");
-
-                for (i, release) in releases_to_show.iter().enumerate() {
-                    let name = release.name.as_deref().unwrap_or("N/A");
-
-                    // Parse and format the published date
-                    let published = DateTime::parse_from_rfc3339(&release.published_at)
-                        .ok()
-                        .map(|dt| dt.format("%Y-%m-%d").to_string())
-                        .unwrap_or_else(|| "Unknown".to_string());
-
-                    eprintln!(
-                        "{:<4} {:20} {:30} {:15} {:10}",
-                        i + 1,
-                        release.tag_name,
-                        truncate(name, 30),
-                        published,
-                        release.assets.len()
-                    );
-                }
 
                 eprintln!(
                     "\nShowing {} of {} releases",
-                    cli.num.min(releases.len()),
+                    releases_to_show.len(),
                     releases.len()
                 );
             }
@@ -589,12 +1561,78 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
-/// Truncate string to specified length with ellipsis
-fn truncate(s: &str, max_len: usize) -> String {
-    if s.chars().count() > max_len {
-        let truncated: String = s.chars().take(max_len - 3).collect();
-        format!("{}...", truncated)
-    } else {
-        s.to_string()
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::path;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_run_batch_downloads_into_repo_dirs() {
+        let server = MockServer::start().await;
+        for repo in ["owner/app", "other/app"] {
+            Mock::given(path(format!("/repos/{}/releases", repo)))
+                .respond_with(
+                    ResponseTemplate::new(200).set_body_json(serde_json::json!([{
+                        "tag_name": "v1.0.0",
+                        "name": "v1.0.0",
+                        "published_at": "2024-01-01T00:00:00Z",
+                        "assets": [{
+                            "id": 1,
+                            "name": "app.tar.gz",
+                            "browser_download_url": "https://example.com/app.tar.gz",
+                            "size": repo.len(),
+                            "download_count": 0
+                        }],
+                        "body": null
+                    }])),
+                )
+                .mount(&server)
+                .await;
+            Mock::given(path(format!("/repos/{}/releases/assets/1", repo)))
+                .respond_with(ResponseTemplate::new(200).set_body_string(repo))
+                .mount(&server)
+                .await;
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let repos_file = dir.path().join("repos.txt");
+        std::fs::write(&repos_file, "owner/app\nother/app\n").unwrap();
+        let out = dir.path().join("out");
+        let cli = Cli::parse_args_from([
+            "ghr",
+            "--repos-file",
+            repos_file.to_str().unwrap(),
+            "-d",
+            "latest",
+            "--api-url",
+            &server.uri(),
+            out.to_str().unwrap(),
+        ]);
+
+        let client = reqwest::Client::new();
+        let api = http::ApiClient::new(client.clone(), Default::default());
+        let releases_api = provider::new(cli.provider, &api, &cli.api_url);
+        let cache = cache::Cache::new(false);
+        let ctx = RepoContext {
+            client: &client,
+            public_client: &client,
+            host_token: None,
+            api: &api,
+            releases_api: releases_api.as_ref(),
+            cache: &cache,
+            download_timeout: None,
+            progress: None,
+            report: None,
+            retry_budget: None,
+        };
+        run_batch(&cli, repos_file.to_str().unwrap(), &ctx)
+            .await
+            .unwrap();
+
+        // Same-named assets of the two repositories end up side by side
+        let read = |repo_dir: &str| std::fs::read_to_string(out.join(repo_dir).join("app.tar.gz"));
+        assert_eq!(read("owner_app").unwrap(), "owner/app");
+        assert_eq!(read("other_app").unwrap(), "other/app");
     }
 }
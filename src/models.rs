@@ -1,35 +1,163 @@
+use crate::util::humanize_bytes;
+use chrono::DateTime;
 use serde::{Deserialize, Serialize};
 use std::fmt::Display;
+use std::time::SystemTime;
 
 /// GitHub release asset
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct Asset {
     pub id: u64,
     pub name: String,
     pub browser_download_url: String,
     pub size: u64,
     pub download_count: u32,
+    /// MIME type given at upload, e.g. "application/gzip"
+    #[serde(default)]
+    pub content_type: Option<String>,
+    /// Upload state: "uploaded" once complete, "starting" while still uploading
+    #[serde(default)]
+    pub state: String,
+    /// Content digest computed by GitHub, e.g. "sha256:<hex>"
+    #[serde(default)]
+    pub digest: Option<String>,
+    #[serde(default)]
+    pub created_at: Option<String>,
+    #[serde(default)]
+    pub updated_at: Option<String>,
+}
+
+impl Asset {
+    /// Last modification time of the asset: `updated_at`, falling back to `created_at`
+    ///
+    /// Returns `None` when neither timestamp is present or parseable.
+    pub fn modified_at(&self) -> Option<SystemTime> {
+        [&self.updated_at, &self.created_at]
+            .into_iter()
+            .flatten()
+            .find_map(|timestamp| DateTime::parse_from_rfc3339(timestamp).ok())
+            .map(SystemTime::from)
+    }
+
+    /// Whether the asset has finished uploading and can be downloaded
+    ///
+    /// Assets without a known state (e.g. from older cache entries) are
+    /// assumed to be complete.
+    pub fn is_uploaded(&self) -> bool {
+        self.state.is_empty() || self.state == "uploaded"
+    }
 }
 
 impl Display for Asset {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let size_mb = self.size as f64 / 1_048_576.0;
         write!(
             f,
-            "  - {} ({:.2} MB, {} downloads)",
-            self.name, size_mb, self.download_count
+            "  - {} ({}, {} downloads)",
+            self.name,
+            humanize_bytes(self.size),
+            self.download_count
         )
     }
 }
 
 /// GitHub release
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct Release {
     pub tag_name: String,
     pub name: Option<String>,
     pub published_at: String,
+    #[serde(default)]
+    pub html_url: String,
+    #[serde(default)]
+    pub prerelease: bool,
+    #[serde(default)]
+    pub draft: bool,
     pub assets: Vec<Asset>,
+    /// Account that created the release
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<Owner>,
+    /// Branch or commit SHA the release's tag was created from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_commitish: Option<String>,
+    /// API URL listing all assets; the release object embeds only the first page
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub assets_url: Option<String>,
     pub body: Option<String>,
+    /// Discussion opened for the release, if the repository has discussions enabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub discussion_url: Option<String>,
+    /// Number of users @mentioned in the release notes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mentions_count: Option<u32>,
+}
+
+impl Release {
+    /// Stand-in release for a tag of a repository without releases (--tags-as-releases)
+    ///
+    /// Tags carry no assets or publication date; their source archive is what
+    /// gets downloaded.
+    pub fn from_tag(tag: String) -> Self {
+        Release {
+            tag_name: tag,
+            ..Default::default()
+        }
+    }
+
+    /// Sum of the download counts of all assets in this release
+    pub fn total_downloads(&self) -> u64 {
+        self.assets.iter().map(|a| a.download_count as u64).sum()
+    }
+
+    /// Sum of the sizes of all assets in this release, in bytes
+    pub fn total_size(&self) -> u64 {
+        self.assets.iter().map(|a| a.size).sum()
+    }
+}
+
+/// Release with derived totals for enhanced JSON output
+#[derive(Debug, Serialize)]
+pub struct ReleaseWithTotals<'a> {
+    #[serde(flatten)]
+    pub release: &'a Release,
+    pub total_size: u64,
+}
+
+impl<'a> ReleaseWithTotals<'a> {
+    pub fn new(release: &'a Release) -> Self {
+        Self {
+            release,
+            total_size: release.total_size(),
+        }
+    }
+}
+
+/// Minimal, stable JSON projection of a release for `--compact`
+///
+/// Fields here are part of the output contract and must not change when the
+/// API models do.
+#[derive(Debug, Serialize)]
+pub struct CompactRelease<'a> {
+    pub tag: &'a str,
+    pub name: Option<&'a str>,
+    pub published_at: &'a str,
+    pub prerelease: bool,
+    pub draft: bool,
+    pub asset_count: usize,
+    pub total_size: u64,
+}
+
+impl<'a> CompactRelease<'a> {
+    pub fn new(release: &'a Release) -> Self {
+        Self {
+            tag: &release.tag_name,
+            name: release.name.as_deref(),
+            published_at: &release.published_at,
+            prerelease: release.prerelease,
+            draft: release.draft,
+            asset_count: release.assets.len(),
+            total_size: release.total_size(),
+        }
+    }
 }
 
 impl Display for Release {
@@ -38,6 +166,12 @@ impl Display for Release {
         writeln!(f, "Tag: {}", self.tag_name)?;
         writeln!(f, "Name: {}", name)?;
         writeln!(f, "Published: {}", self.published_at)?;
+        if let Some(url) = &self.discussion_url {
+            writeln!(f, "Discussion: {}", url)?;
+        }
+        if let Some(count) = self.mentions_count {
+            writeln!(f, "Mentions: {}", count)?;
+        }
         writeln!(f, "Assets:")?;
         for asset in &self.assets {
             writeln!(f, "{}", asset)?;
@@ -52,6 +186,21 @@ pub struct SearchResponse {
     pub items: Vec<Repository>,
 }
 
+/// Error body returned by the GitHub API on failed requests
+#[derive(Debug, Deserialize)]
+pub struct ApiErrorResponse {
+    pub message: Option<String>,
+    #[serde(default)]
+    pub errors: Vec<ApiErrorDetail>,
+}
+
+/// Single entry of the `errors` array in an API error body
+#[derive(Debug, Deserialize)]
+pub struct ApiErrorDetail {
+    pub message: Option<String>,
+    pub code: Option<String>,
+}
+
 /// GitHub repository
 #[allow(dead_code)]
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -63,6 +212,9 @@ pub struct Repository {
     pub html_url: String,
     pub owner: Owner,
     pub private: bool,
+    /// Topics, filled in with `--with-topics`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub topics: Vec<String>,
 }
 
 /// Repository with additional tag information for enhanced JSON output
@@ -79,29 +231,12 @@ pub struct Owner {
     pub login: String,
 }
 
-impl Repository {
-    pub fn summary(&self) -> String {
-        // Add lock emoji for private repositories
-        let privacy_indicator = if self.private { "*" } else { " " };
-
-        format!(
-            "{:<7} {:2}{:40}",
-            self.stargazers_count, privacy_indicator, self.full_name
-        )
-    }
-}
-
 impl Display for Repository {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let desc = self.description.as_deref().unwrap_or("");
 
         // Truncate description respecting UTF-8 character boundaries
-        let desc_truncated = if desc.chars().count() > 50 {
-            let truncated: String = desc.chars().take(47).collect();
-            format!("{}...", truncated)
-        } else {
-            desc.to_string()
-        };
+        let desc_truncated = crate::util::truncate(desc, 50);
 
         // Add lock emoji for private repositories
         let privacy_indicator = if self.private { "*" } else { " " };
@@ -124,14 +259,174 @@ pub struct CloneSpec {
     pub original_url: String,
 }
 
+/// Kind of git ref resolved through the GitHub API
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RefKind {
+    Branch,
+    Tag,
+    Commit,
+}
+
+impl Display for RefKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = match self {
+            RefKind::Branch => "branch",
+            RefKind::Tag => "tag",
+            RefKind::Commit => "commit",
+        };
+        write!(f, "{}", kind)
+    }
+}
+
+/// A ref validated against the repository, with its kind known
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedRef {
+    pub kind: RefKind,
+    pub name: String,
+}
+
+impl ResolvedRef {
+    /// Fully qualified ref name (e.g. "refs/heads/main", "refs/tags/v1.0.0")
+    ///
+    /// Commits have no ref namespace, so the SHA is returned as-is.
+    pub fn canonical(&self) -> String {
+        match self.kind {
+            RefKind::Branch => format!("refs/heads/{}", self.name),
+            RefKind::Tag => format!("refs/tags/{}", self.name),
+            RefKind::Commit => self.name.clone(),
+        }
+    }
+}
+
+/// Release from the GitLab API
+#[derive(Debug, Deserialize)]
+pub struct GitLabRelease {
+    pub tag_name: String,
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub released_at: Option<String>,
+    pub created_at: Option<String>,
+    #[serde(default)]
+    pub upcoming_release: bool,
+    #[serde(default, rename = "_links")]
+    pub links: GitLabReleaseLinks,
+    #[serde(default)]
+    pub assets: GitLabAssets,
+    #[serde(default)]
+    pub author: Option<GitLabUser>,
+}
+
+/// GitLab user, as embedded in other objects
+#[derive(Debug, Deserialize)]
+pub struct GitLabUser {
+    pub username: String,
+}
+
+/// Links of a GitLab release
+#[derive(Debug, Default, Deserialize)]
+pub struct GitLabReleaseLinks {
+    /// Release page
+    #[serde(default, rename = "self")]
+    pub self_url: String,
+}
+
+/// Assets of a GitLab release; only links are downloadable files
+#[derive(Debug, Default, Deserialize)]
+pub struct GitLabAssets {
+    #[serde(default)]
+    pub links: Vec<GitLabAssetLink>,
+}
+
+/// File attached to a GitLab release
+#[derive(Debug, Deserialize)]
+pub struct GitLabAssetLink {
+    pub id: u64,
+    pub name: String,
+    pub url: String,
+    /// Permanent URL redirecting to `url`
+    pub direct_asset_url: Option<String>,
+}
+
+impl From<GitLabRelease> for Release {
+    /// GitLab does not report asset sizes or download counts, so those are zero
+    fn from(release: GitLabRelease) -> Self {
+        Release {
+            published_at: release
+                .released_at
+                .or(release.created_at)
+                .unwrap_or_default(),
+            html_url: release.links.self_url,
+            prerelease: release.upcoming_release,
+            draft: false,
+            assets: release
+                .assets
+                .links
+                .into_iter()
+                .map(|link| Asset {
+                    id: link.id,
+                    name: link.name,
+                    browser_download_url: link.direct_asset_url.unwrap_or(link.url),
+                    ..Default::default()
+                })
+                .collect(),
+            body: release.description,
+            author: release.author.map(|user| Owner {
+                login: user.username,
+            }),
+            name: release.name,
+            tag_name: release.tag_name,
+            target_commitish: None,
+            assets_url: None,
+            discussion_url: None,
+            mentions_count: None,
+        }
+    }
+}
+
 /// Repository info from GitHub API
 #[allow(dead_code)]
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub struct RepositoryInfo {
     pub name: String,
     pub full_name: String,
     pub default_branch: String,
     pub private: bool,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub stargazers_count: u32,
+    #[serde(default)]
+    pub open_issues_count: u32,
+    /// Size in KiB, as reported by GitHub
+    #[serde(default)]
+    pub size: u64,
+    /// "public", "private" or "internal"; missing on older GitHub Enterprise versions
+    #[serde(default)]
+    pub visibility: Option<String>,
+    #[serde(default)]
+    pub license: Option<License>,
+    /// Tag of the latest release, filled in by `--repo-info`
+    #[serde(default, skip_deserializing)]
+    pub latest_release: Option<String>,
+}
+
+impl RepositoryInfo {
+    /// Visibility, derived from `private` when GitHub doesn't report it
+    pub fn visibility(&self) -> &str {
+        match self.visibility.as_deref() {
+            Some(visibility) => visibility,
+            None if self.private => "private",
+            None => "public",
+        }
+    }
+}
+
+/// License of a repository
+#[derive(Debug, Deserialize, Serialize)]
+pub struct License {
+    /// SPDX identifier, e.g. "MIT"; "NOASSERTION" for licenses GitHub doesn't recognize
+    pub spdx_id: Option<String>,
+    pub name: String,
 }
 
 /// GitHub tag
@@ -140,88 +435,226 @@ pub struct Tag {
     pub name: String,
 }
 
+/// Topics of a repository
+#[derive(Debug, Deserialize)]
+pub struct Topics {
+    pub names: Vec<String>,
+}
+
+/// GitHub branch
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Branch {
+    pub name: String,
+    pub commit: BranchCommit,
+}
+
+/// Head commit of a branch
+#[derive(Debug, Deserialize, Serialize)]
+pub struct BranchCommit {
+    pub sha: String,
+}
+
 // Result type is now defined in errors.rs
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    // Tests for Repository methods
     #[test]
-    fn test_repository_summary_public_repo() {
-        let repo = Repository {
-            name: "test-repo".to_string(),
-            full_name: "user/test-repo".to_string(),
-            description: Some("A test repository".to_string()),
-            stargazers_count: 42,
-            html_url: "https://github.com/user/test-repo".to_string(),
-            owner: Owner {
-                login: "user".to_string(),
-            },
-            private: false,
+    fn test_gitlab_release_conversion() {
+        let json = r#"{
+            "tag_name": "v1.2.0",
+            "name": "Release 1.2.0",
+            "description": "Notes",
+            "created_at": "2024-03-01T09:00:00Z",
+            "released_at": "2024-03-01T10:00:00Z",
+            "upcoming_release": false,
+            "author": { "id": 1, "username": "maintainer", "name": "Maintainer" },
+            "_links": { "self": "https://gitlab.com/group/project/-/releases/v1.2.0" },
+            "assets": {
+                "count": 3,
+                "sources": [{ "format": "zip", "url": "https://gitlab.com/group/project/-/archive/v1.2.0/project-v1.2.0.zip" }],
+                "links": [{
+                    "id": 7,
+                    "name": "app-linux.tar.gz",
+                    "url": "https://gitlab.com/group/project/-/package_files/1/download",
+                    "direct_asset_url": "https://gitlab.com/group/project/-/releases/v1.2.0/downloads/app-linux.tar.gz",
+                    "link_type": "package"
+                }]
+            }
+        }"#;
+
+        let release: Release = serde_json::from_str::<GitLabRelease>(json).unwrap().into();
+        assert_eq!(release.tag_name, "v1.2.0");
+        assert_eq!(release.name.as_deref(), Some("Release 1.2.0"));
+        assert_eq!(release.published_at, "2024-03-01T10:00:00Z");
+        assert_eq!(
+            release.html_url,
+            "https://gitlab.com/group/project/-/releases/v1.2.0"
+        );
+        assert_eq!(release.author.unwrap().login, "maintainer");
+        assert_eq!(release.assets.len(), 1);
+        assert_eq!(release.assets[0].name, "app-linux.tar.gz");
+        assert_eq!(
+            release.assets[0].browser_download_url,
+            "https://gitlab.com/group/project/-/releases/v1.2.0/downloads/app-linux.tar.gz"
+        );
+    }
+
+    #[test]
+    fn test_release_discussion() {
+        let json = r#"{
+            "tag_name": "v1.0.0",
+            "name": "v1.0.0",
+            "published_at": "2024-01-01T00:00:00Z",
+            "assets": [],
+            "body": "Thanks @alice and @bob",
+            "discussion_url": "https://github.com/owner/repo/discussions/42",
+            "mentions_count": 2
+        }"#;
+        let release: Release = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            release.discussion_url.as_deref(),
+            Some("https://github.com/owner/repo/discussions/42")
+        );
+        assert_eq!(release.mentions_count, Some(2));
+        let shown = release.to_string();
+        assert!(shown.contains("Discussion: https://github.com/owner/repo/discussions/42\n"));
+        assert!(shown.contains("Mentions: 2\n"));
+
+        let json = r#"{
+            "tag_name": "v1.0.0",
+            "name": null,
+            "published_at": "2024-01-01T00:00:00Z",
+            "assets": [],
+            "body": null
+        }"#;
+        let release: Release = serde_json::from_str(json).unwrap();
+        assert_eq!(release.discussion_url, None);
+        assert_eq!(release.mentions_count, None);
+        let shown = release.to_string();
+        assert!(!shown.contains("Discussion:"));
+        assert!(!shown.contains("Mentions:"));
+        // Absent fields stay absent in JSON output
+        let value = serde_json::to_value(&release).unwrap();
+        assert!(value.get("discussion_url").is_none());
+    }
+
+    #[test]
+    fn test_asset_modified_at() {
+        let asset = Asset {
+            created_at: Some("2024-01-01T00:00:00Z".to_string()),
+            updated_at: Some("2024-01-02T00:00:00Z".to_string()),
+            ..Default::default()
         };
+        let expected = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_704_153_600);
+        assert_eq!(asset.modified_at(), Some(expected));
 
-        let summary = repo.summary();
-        assert!(summary.contains("user/test-repo"));
-        assert!(summary.contains("42"));
-        assert!(!summary.contains("*")); // Not private
+        let asset = Asset {
+            created_at: Some("2024-01-02T00:00:00Z".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(asset.modified_at(), Some(expected));
+        assert_eq!(Asset::default().modified_at(), None);
     }
 
     #[test]
-    fn test_repository_summary_private_repo() {
-        let repo = Repository {
-            name: "private-repo".to_string(),
-            full_name: "org/private-repo".to_string(),
-            description: Some("A private repository".to_string()),
-            stargazers_count: 100,
-            html_url: "https://github.com/org/private-repo".to_string(),
-            owner: Owner {
-                login: "org".to_string(),
-            },
-            private: true,
+    fn test_release_total_size() {
+        let release = Release {
+            assets: vec![
+                Asset {
+                    size: 1_000,
+                    ..Default::default()
+                },
+                Asset {
+                    size: 2_500,
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
         };
+        assert_eq!(release.total_size(), 3_500);
+        assert_eq!(Release::default().total_size(), 0);
 
-        let summary = repo.summary();
-        assert!(summary.contains("org/private-repo"));
-        assert!(summary.contains("100"));
-        assert!(summary.contains("*")); // Private indicator
+        let json = serde_json::to_value(ReleaseWithTotals::new(&release)).unwrap();
+        assert_eq!(json["total_size"], 3_500);
     }
 
     #[test]
-    fn test_repository_summary_zero_stars() {
-        let repo = Repository {
-            name: "new-repo".to_string(),
-            full_name: "user/new-repo".to_string(),
-            description: None,
-            stargazers_count: 0,
-            html_url: "https://github.com/user/new-repo".to_string(),
-            owner: Owner {
-                login: "user".to_string(),
-            },
-            private: false,
+    fn test_compact_release_keys() {
+        let release = Release {
+            tag_name: "v1.0.0".to_string(),
+            published_at: "2024-03-01T00:00:00Z".to_string(),
+            body: Some("notes".to_string()),
+            prerelease: true,
+            assets: vec![Asset {
+                size: 100,
+                ..Default::default()
+            }],
+            ..Default::default()
         };
 
-        let summary = repo.summary();
-        assert!(summary.contains("user/new-repo"));
-        assert!(summary.contains("0"));
+        let value = serde_json::to_value(CompactRelease::new(&release)).unwrap();
+        let mut keys: Vec<&str> = value
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(|k| k.as_str())
+            .collect();
+        keys.sort_unstable();
+        assert_eq!(
+            keys,
+            vec![
+                "asset_count",
+                "draft",
+                "name",
+                "prerelease",
+                "published_at",
+                "tag",
+                "total_size"
+            ]
+        );
+        assert_eq!(value["tag"], "v1.0.0");
+        assert_eq!(value["prerelease"], true);
+        assert_eq!(value["asset_count"], 1);
+        assert_eq!(value["total_size"], 100);
     }
 
     #[test]
-    fn test_repository_summary_high_star_count() {
-        let repo = Repository {
-            name: "popular-repo".to_string(),
-            full_name: "org/popular-repo".to_string(),
-            description: Some("Very popular".to_string()),
-            stargazers_count: 123456,
-            html_url: "https://github.com/org/popular-repo".to_string(),
-            owner: Owner {
-                login: "org".to_string(),
-            },
-            private: false,
+    fn test_resolved_ref_canonical() {
+        let branch = ResolvedRef {
+            kind: RefKind::Branch,
+            name: "feature/x".to_string(),
         };
+        assert_eq!(branch.canonical(), "refs/heads/feature/x");
 
-        let summary = repo.summary();
-        assert!(summary.contains("org/popular-repo"));
-        assert!(summary.contains("123456"));
+        let tag = ResolvedRef {
+            kind: RefKind::Tag,
+            name: "v1.0.0".to_string(),
+        };
+        assert_eq!(tag.canonical(), "refs/tags/v1.0.0");
+
+        let commit = ResolvedRef {
+            kind: RefKind::Commit,
+            name: "abc1234".to_string(),
+        };
+        assert_eq!(commit.canonical(), "abc1234");
+    }
+
+    // Tests for Branch
+    #[test]
+    fn test_branch_deserialize() {
+        let json = r#"[
+            {"name": "main", "commit": {"sha": "6dcb09b5b57875f334f61aebed695e2e4193db5e", "url": "https://api.github.com/repos/o/r/commits/6dcb09b"}, "protected": true},
+            {"name": "feature/x", "commit": {"sha": "c5b97d5ae6c19d5c5df71a34c7fbeeda2479ccbc", "url": "https://api.github.com/repos/o/r/commits/c5b97d5"}, "protected": false}
+        ]"#;
+
+        let branches: Vec<Branch> = serde_json::from_str(json).unwrap();
+        assert_eq!(branches.len(), 2);
+        assert_eq!(branches[1].name, "feature/x");
+        assert_eq!(
+            branches[0].commit.sha,
+            "6dcb09b5b57875f334f61aebed695e2e4193db5e"
+        );
     }
 }
@@ -0,0 +1,1193 @@
+use crate::checksum::{HashOutcome, HashReport};
+use crate::cli::ReleaseColumn;
+use crate::download::DownloadTask;
+use crate::errors::{GhrError, Result};
+use crate::models::{Asset, Branch, Release, Repository, RepositoryInfo};
+use crate::util::{humanize_bytes, truncate};
+use chrono::{DateTime, Utc};
+use comfy_table::presets::UTF8_HORIZONTAL_ONLY;
+use comfy_table::{
+    Attribute, Cell, CellAlignment, Color, ContentArrangement, Table, TableComponent,
+};
+use serde::Serialize;
+use std::io::{IsTerminal, Write};
+use std::path::Path;
+use std::time::Duration;
+use unicode_width::UnicodeWidthStr;
+
+/// Format a release's publication date as shown in listings, e.g. "2024-03-01"
+pub fn format_published(published_at: &str) -> String {
+    DateTime::parse_from_rfc3339(published_at)
+        .ok()
+        .map(|dt| dt.format("%Y-%m-%d").to_string())
+        .unwrap_or_else(|| "Unknown".to_string())
+}
+
+/// Format a release's publication date relative to `now`, e.g. "3 days ago"
+pub fn format_relative(published_at: &str, now: DateTime<Utc>) -> String {
+    let Ok(published) = DateTime::parse_from_rfc3339(published_at) else {
+        return "Unknown".to_string();
+    };
+    let seconds = now
+        .signed_duration_since(published.with_timezone(&Utc))
+        .num_seconds();
+    // Clock skew can put a fresh release slightly in the future
+    if seconds < 60 {
+        return "just now".to_string();
+    }
+
+    let (count, unit) = match seconds {
+        s if s < 3_600 => (s / 60, "minute"),
+        s if s < 86_400 => (s / 3_600, "hour"),
+        s if s < 30 * 86_400 => (s / 86_400, "day"),
+        s if s < 365 * 86_400 => (s / (30 * 86_400), "month"),
+        s => (s / (365 * 86_400), "year"),
+    };
+    let plural = if count == 1 { "" } else { "s" };
+    format!("{} {}{} ago", count, unit, plural)
+}
+
+/// How publication dates are shown in release listings
+#[derive(Debug, Clone, Copy)]
+pub enum DateStyle {
+    /// Calendar date, e.g. "2024-03-01"
+    Absolute,
+    /// Age at the given time, e.g. "3 days ago"
+    Relative(DateTime<Utc>),
+}
+
+impl DateStyle {
+    fn format(self, published_at: &str) -> String {
+        match self {
+            DateStyle::Absolute => format_published(published_at),
+            DateStyle::Relative(now) => format_relative(published_at, now),
+        }
+    }
+}
+
+/// Serialize a value as JSON, pretty-printed unless `compact` is set
+pub fn to_json<T: Serialize + ?Sized>(value: &T, compact: bool) -> Result<String> {
+    Ok(if compact {
+        serde_json::to_string(value)?
+    } else {
+        serde_json::to_string_pretty(value)?
+    })
+}
+
+/// `--info` output as JSON: the release itself, or an array when several tags were requested
+pub fn releases_info_json(releases: &[Release], multiple: bool, compact: bool) -> Result<String> {
+    match releases {
+        [release] if !multiple => to_json(release, compact),
+        _ => to_json(releases, compact),
+    }
+}
+
+/// Type of a release: "release", "prerelease" or "draft"
+fn release_kind(release: &Release) -> &'static str {
+    if release.draft {
+        "draft"
+    } else if release.prerelease {
+        "prerelease"
+    } else {
+        "release"
+    }
+}
+
+/// Release listing with one terse line per release, e.g.
+/// `v1.2.3  (release)  2024-03-01  assets:6`
+///
+/// Tags are padded to the longest tag shown instead of a fixed column width,
+/// measured in terminal columns so that wide (e.g. CJK) characters stay aligned.
+pub fn releases_oneline(releases: &[&Release], dates: DateStyle) -> Vec<String> {
+    let width = releases
+        .iter()
+        .map(|release| release.tag_name.width())
+        .max()
+        .unwrap_or_default();
+    releases
+        .iter()
+        .map(|release| {
+            format!(
+                "{}{}  ({})  {}  assets:{}",
+                release.tag_name,
+                " ".repeat(width - release.tag_name.width()),
+                release_kind(release),
+                dates.format(&release.published_at),
+                release.assets.len()
+            )
+        })
+        .collect()
+}
+
+/// Table with a bold header row; columns listed in `right` are right-aligned
+///
+/// Column widths follow the display width of the content, so wide (e.g. CJK)
+/// characters stay aligned. Colors are only used when stderr, where tables are
+/// printed, is a terminal.
+fn new_table(header: &[&str], right: &[usize]) -> Table {
+    let mut table = Table::new();
+    table
+        .load_preset(UTF8_HORIZONTAL_ONLY)
+        .remove_style(TableComponent::HorizontalLines)
+        .remove_style(TableComponent::MiddleIntersections)
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(header.iter().map(|title| {
+            Cell::new(title)
+                .add_attribute(Attribute::Bold)
+                .fg(Color::Cyan)
+        }));
+    if std::io::stderr().is_terminal() {
+        table.enforce_styling();
+    } else {
+        table.force_no_tty();
+    }
+    for &index in right {
+        if let Some(column) = table.column_mut(index) {
+            column.set_cell_alignment(CellAlignment::Right);
+        }
+    }
+    table
+}
+
+/// Release listing as a table with the given columns
+pub fn releases_table(releases: &[&Release], columns: &[ReleaseColumn], dates: DateStyle) -> Table {
+    let header: Vec<&str> = columns.iter().map(|column| column.title()).collect();
+    let right: Vec<usize> = columns
+        .iter()
+        .enumerate()
+        .filter(|(_, column)| matches!(column, ReleaseColumn::Assets | ReleaseColumn::Size))
+        .map(|(i, _)| i)
+        .collect();
+    let mut table = new_table(&header, &right);
+    for (i, release) in releases.iter().enumerate() {
+        table.add_row(columns.iter().map(|column| match column {
+            ReleaseColumn::No => (i + 1).to_string(),
+            ReleaseColumn::Tag => release.tag_name.clone(),
+            ReleaseColumn::Name => truncate(release.name.as_deref().unwrap_or("N/A"), 30),
+            ReleaseColumn::Published => dates.format(&release.published_at),
+            ReleaseColumn::Assets => release.assets.len().to_string(),
+            ReleaseColumn::Size => humanize_bytes(release.total_size()),
+            ReleaseColumn::Type => release_kind(release).to_string(),
+        }));
+    }
+    table
+}
+
+/// Search results as a table; private repositories are marked with `*`
+///
+/// A topics column is added when any repository has topics.
+pub fn repositories_table(repositories: &[Repository]) -> Table {
+    let with_topics = repositories.iter().any(|repo| !repo.topics.is_empty());
+    let mut header = vec!["No", "Stars", "", "Repository"];
+    if with_topics {
+        header.push("Topics");
+    }
+    let mut table = new_table(&header, &[1]);
+    for (i, repo) in repositories.iter().enumerate() {
+        let mut row = vec![
+            (i + 1).to_string(),
+            repo.stargazers_count.to_string(),
+            if repo.private { "*" } else { "" }.to_string(),
+            repo.full_name.clone(),
+        ];
+        if with_topics {
+            row.push(repo.topics.join(", "));
+        }
+        table.add_row(row);
+    }
+    table
+}
+
+/// Branch listing as a table
+pub fn branches_table(branches: &[Branch]) -> Table {
+    let mut table = new_table(&["No", "Branch", "Commit"], &[]);
+    for (i, branch) in branches.iter().enumerate() {
+        table.add_row(vec![
+            (i + 1).to_string(),
+            branch.name.clone(),
+            branch.commit.sha.clone(),
+        ]);
+    }
+    table
+}
+
+/// Fields shown by `--repo-info`, in order, with the size in bytes unless `pretty`
+fn repository_info_fields(info: &RepositoryInfo, pretty: bool) -> Vec<(&'static str, String)> {
+    let license = info
+        .license
+        .as_ref()
+        .map(|license| match license.spdx_id.as_deref() {
+            Some(id) if id != "NOASSERTION" => id.to_string(),
+            _ => license.name.clone(),
+        });
+    vec![
+        ("Repository", info.full_name.clone()),
+        ("Description", info.description.clone().unwrap_or_default()),
+        ("Stars", info.stargazers_count.to_string()),
+        ("Default Branch", info.default_branch.clone()),
+        ("Visibility", info.visibility().to_string()),
+        ("Size", csv_size(info.size * 1024, pretty)),
+        ("License", license.unwrap_or_else(|| "None".to_string())),
+        ("Open Issues", info.open_issues_count.to_string()),
+        (
+            "Latest Release",
+            info.latest_release
+                .clone()
+                .unwrap_or_else(|| "None".to_string()),
+        ),
+    ]
+}
+
+/// Repository metadata as a table with one field per row
+pub fn repository_info_table(info: &RepositoryInfo) -> Table {
+    let mut table = new_table(&["Field", "Value"], &[]);
+    for (field, value) in repository_info_fields(info, true) {
+        table.add_row(vec![field.to_string(), value]);
+    }
+    table
+}
+
+/// Repository metadata as CSV with a single row, with the size in bytes unless `pretty`
+pub fn repository_info_csv(info: &RepositoryInfo, pretty: bool) -> Result<String> {
+    let (header, row): (Vec<&str>, Vec<String>) =
+        repository_info_fields(info, pretty).into_iter().unzip();
+    to_csv(header, vec![row])
+}
+
+/// Asset listing as a table
+///
+/// Takes each asset together with its number in the release.
+pub fn assets_table(assets: &[(usize, &Asset)]) -> Table {
+    let mut table = new_table(&["No", "Name", "Size", "Downloads"], &[2, 3]);
+    for (number, asset) in assets {
+        table.add_row(vec![
+            number.to_string(),
+            truncate(&asset.name, 50),
+            humanize_bytes(asset.size),
+            asset.download_count.to_string(),
+        ]);
+    }
+    table
+}
+
+/// Section of a release printed by `--info`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InfoSection {
+    /// Metadata, release notes and assets
+    All,
+    /// Only the release notes (--notes-only)
+    Notes,
+    /// Only the asset table (--assets-only)
+    Assets,
+}
+
+/// Text printed by `--info` for one release
+///
+/// `All` frames the release between rulers; the single sections are printed
+/// bare so that they can be piped into other tools.
+pub fn release_info(
+    release: &Release,
+    section: InfoSection,
+    truncate_notes: Option<usize>,
+) -> String {
+    let notes = release.body.as_deref().map(|body| match truncate_notes {
+        Some(limit) => truncate(body, limit),
+        None => body.to_string(),
+    });
+
+    match section {
+        InfoSection::Notes => notes.unwrap_or_default(),
+        InfoSection::Assets => {
+            let numbered: Vec<(usize, &Asset)> = release
+                .assets
+                .iter()
+                .enumerate()
+                .map(|(i, asset)| (i + 1, asset))
+                .collect();
+            assets_table(&numbered).to_string()
+        }
+        InfoSection::All => {
+            let ruler = "=".repeat(80);
+            let mut text = format!("\n{}\n{}\n", ruler, release);
+            if let Some(notes) = notes {
+                text.push_str(&format!(
+                    "\nRelease Notes:\n{}\n{}\n",
+                    "-".repeat(80),
+                    notes
+                ));
+            }
+            text.push_str(&ruler);
+            text
+        }
+    }
+}
+
+/// Table of the `--parallel-hash-verify` report: each file with PASS, FAIL or MISSING-SUM
+pub fn hash_report_table(report: &HashReport) -> Table {
+    let mut table = new_table(&["File", "Result", "Details"], &[]);
+    for (file, outcome) in &report.entries {
+        let details = match outcome {
+            HashOutcome::Pass(source) => source.label(),
+            HashOutcome::Fail(reason) => reason.as_str(),
+            HashOutcome::MissingSum => "",
+        };
+        table.add_row(vec![file.as_str(), outcome.label(), details]);
+    }
+    table
+}
+
+/// Outcome of a download run, as reported by `--summary-only`
+#[derive(Debug, Default)]
+pub struct DownloadSummary {
+    pub downloaded: usize,
+    pub total_bytes: u64,
+    pub elapsed: Duration,
+    /// Names of the assets that were not downloaded
+    pub failed: Vec<String>,
+    /// Errors of the run, including those not tied to a download (e.g. extraction)
+    pub errors: Vec<String>,
+}
+
+/// Lines of the `--summary-only` report, e.g.
+/// `Summary: 3 downloaded, 1 failed, 12.3 MiB in 4.2s`
+pub fn download_summary(summary: &DownloadSummary) -> Vec<String> {
+    let mut lines = vec![format!(
+        "Summary: {} downloaded, {} failed, {} in {:.1}s",
+        summary.downloaded,
+        summary.failed.len(),
+        humanize_bytes(summary.total_bytes),
+        summary.elapsed.as_secs_f64()
+    )];
+    if !summary.failed.is_empty() {
+        lines.push("Failed assets:".to_string());
+        lines.extend(summary.failed.iter().map(|name| format!("  - {}", name)));
+    }
+    if !summary.errors.is_empty() {
+        lines.push("Errors:".to_string());
+        lines.extend(summary.errors.iter().map(|error| format!("  - {}", error)));
+    }
+    lines
+}
+
+/// Lines reporting failed downloads: every error, or with `quiet` (--quiet-errors)
+/// only their count and the first few
+pub fn download_error_lines(errors: &[String], quiet: bool) -> Vec<String> {
+    let shown = if quiet {
+        errors.len().min(crate::constants::QUIET_ERRORS_EXAMPLES)
+    } else {
+        errors.len()
+    };
+    let mut lines = vec![format!("Failed to download {} asset(s):", errors.len())];
+    lines.extend(errors[..shown].iter().map(|error| format!("  - {}", error)));
+    if shown < errors.len() {
+        lines.push(format!("  ... and {} more", errors.len() - shown));
+    }
+    lines
+}
+
+/// Overall report of a run, written by `--summary-json`
+#[derive(Debug, Default, Serialize)]
+pub struct RunReport {
+    pub mode: String,
+    pub repos: Vec<String>,
+    /// Tags of the releases assets were selected from
+    pub tags: Vec<String>,
+    pub succeeded: usize,
+    pub failed: usize,
+    /// Assets of the selected releases left out by the filters
+    pub skipped: usize,
+    pub total_bytes: u64,
+    pub elapsed_secs: f64,
+    /// Outcome of the whole run; failed when it ended with an error
+    pub status: RunStatus,
+    /// Exit code of the run
+    pub exit_code: u8,
+    pub assets: Vec<AssetReport>,
+    /// Errors of the run, including those not tied to a download (e.g. extraction)
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RunStatus {
+    #[default]
+    Success,
+    Failed,
+}
+
+/// Outcome of one asset in a [`RunReport`]
+#[derive(Debug, Serialize)]
+pub struct AssetReport {
+    pub repo: String,
+    pub name: String,
+    pub status: AssetStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// HTTP status of a failed download, if the server answered
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub http_status: Option<u16>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AssetStatus {
+    Downloaded,
+    Failed,
+}
+
+impl RunReport {
+    pub fn new(mode: &str) -> Self {
+        Self {
+            mode: mode.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Record how the run ended, including an error that aborted it before or
+    /// outside of the downloads, e.g. a release that doesn't exist
+    pub fn finish(&mut self, result: &Result<()>) {
+        if let Err(error) = result {
+            let message = error.to_string();
+            if !self.errors.contains(&message) {
+                self.errors.push(message);
+            }
+            self.status = RunStatus::Failed;
+            self.exit_code = error.exit_code();
+        }
+    }
+
+    /// Add the outcome of the downloads from `repo`
+    pub fn record(
+        &mut self,
+        repo: &str,
+        tags: &[&str],
+        successes: &[DownloadTask],
+        errors: &[GhrError],
+        skipped: usize,
+    ) {
+        self.repos.push(repo.to_string());
+        self.tags.extend(tags.iter().map(|tag| tag.to_string()));
+        self.skipped += skipped;
+
+        for task in successes {
+            self.succeeded += 1;
+            self.total_bytes += task.size;
+            self.assets.push(AssetReport {
+                repo: repo.to_string(),
+                name: task.name.clone(),
+                status: AssetStatus::Downloaded,
+                path: Some(task.output_path.display().to_string()),
+                size: Some(task.size),
+                error: None,
+                http_status: None,
+            });
+        }
+        for error in errors {
+            self.errors.push(error.to_string());
+            if let GhrError::DownloadFailed { asset, source } = error {
+                self.failed += 1;
+                self.assets.push(AssetReport {
+                    repo: repo.to_string(),
+                    name: asset.clone(),
+                    status: AssetStatus::Failed,
+                    path: None,
+                    size: None,
+                    error: Some(source.to_string()),
+                    http_status: source.http_status(),
+                });
+            }
+        }
+    }
+}
+
+/// Write `text` to `out` and flush it, so that every call reaches a pipe
+/// right away
+///
+/// A reader that went away (e.g. `ghr ... | head -1`) surfaces as a
+/// `BrokenPipe` error instead of a panic.
+pub fn write_flushed(mut out: impl Write, text: &str) -> std::io::Result<()> {
+    out.write_all(text.as_bytes())?;
+    out.flush()
+}
+
+/// Write a JSON report to `path`
+pub fn write_json_file<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    std::fs::write(path, to_json(value, false)? + "\n")?;
+    Ok(())
+}
+
+/// Write rows as CSV with a header row
+fn to_csv<H, R>(header: H, rows: Vec<R>) -> Result<String>
+where
+    H: IntoIterator,
+    H::Item: AsRef<[u8]>,
+    R: IntoIterator,
+    R::Item: AsRef<[u8]>,
+{
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer.write_record(header)?;
+    for row in rows {
+        writer.write_record(row)?;
+    }
+    let bytes = writer.into_inner().map_err(|e| e.into_error())?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Size column value: bytes, or a human-readable size with `--pretty-size`
+fn csv_size(bytes: u64, pretty: bool) -> String {
+    if pretty {
+        humanize_bytes(bytes)
+    } else {
+        bytes.to_string()
+    }
+}
+
+/// Release listing as CSV with the given columns, with sizes in bytes unless `pretty`
+pub fn releases_csv(
+    releases: &[&Release],
+    columns: &[ReleaseColumn],
+    pretty: bool,
+) -> Result<String> {
+    let rows = releases
+        .iter()
+        .enumerate()
+        .map(|(i, release)| {
+            columns
+                .iter()
+                .map(|column| match column {
+                    ReleaseColumn::No => (i + 1).to_string(),
+                    ReleaseColumn::Tag => release.tag_name.clone(),
+                    ReleaseColumn::Name => release.name.clone().unwrap_or_default(),
+                    ReleaseColumn::Published => format_published(&release.published_at),
+                    ReleaseColumn::Assets => release.assets.len().to_string(),
+                    ReleaseColumn::Size => csv_size(release.total_size(), pretty),
+                    ReleaseColumn::Type => release_kind(release).to_string(),
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect();
+    to_csv(columns.iter().map(|column| column.title()), rows)
+}
+
+/// Search results as CSV
+pub fn repositories_csv(repositories: &[Repository]) -> Result<String> {
+    let rows = repositories
+        .iter()
+        .enumerate()
+        .map(|(i, repo)| {
+            [
+                (i + 1).to_string(),
+                repo.stargazers_count.to_string(),
+                repo.private.to_string(),
+                repo.full_name.clone(),
+                repo.description.clone().unwrap_or_default(),
+            ]
+        })
+        .collect();
+    to_csv(
+        ["No", "Stars", "Private", "Repository", "Description"],
+        rows,
+    )
+}
+
+/// Branch listing as CSV
+pub fn branches_csv(branches: &[Branch]) -> Result<String> {
+    let rows = branches
+        .iter()
+        .enumerate()
+        .map(|(i, branch)| {
+            [
+                (i + 1).to_string(),
+                branch.name.clone(),
+                branch.commit.sha.clone(),
+            ]
+        })
+        .collect();
+    to_csv(["No", "Branch", "Commit"], rows)
+}
+
+/// Asset listing as CSV, with sizes in bytes unless `pretty`
+///
+/// Takes each asset together with its number in the release.
+pub fn assets_csv(assets: &[(usize, &Asset)], pretty: bool) -> Result<String> {
+    let rows = assets
+        .iter()
+        .map(|(number, asset)| {
+            [
+                number.to_string(),
+                asset.name.clone(),
+                csv_size(asset.size, pretty),
+                asset.download_count.to_string(),
+            ]
+        })
+        .collect();
+    to_csv(["No", "Name", "Size", "Downloads"], rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checksum::DigestSource;
+    use crate::models::Owner;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_write_flushed_closed_pipe() {
+        use std::io::Read;
+        use std::os::unix::net::UnixStream;
+
+        let (writer, mut reader) = UnixStream::pair().unwrap();
+        write_flushed(&writer, "v1.0.0\n").unwrap();
+        let mut line = [0; 7];
+        reader.read_exact(&mut line).unwrap();
+        assert_eq!(&line, b"v1.0.0\n");
+
+        // The reader went away, like `head` after its first line
+        drop(reader);
+        let error = write_flushed(&writer, "v0.9.0\n").unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::BrokenPipe);
+        assert!(GhrError::from(error).is_broken_pipe());
+    }
+
+    #[test]
+    fn test_releases_oneline() {
+        let stable = Release {
+            tag_name: "v1.0.0".to_string(),
+            published_at: "2024-03-01T10:00:00Z".to_string(),
+            assets: vec![Asset::default(), Asset::default()],
+            ..Default::default()
+        };
+        let nightly = Release {
+            tag_name: "nightly-2024-03-02".to_string(),
+            published_at: "2024-03-02T10:00:00Z".to_string(),
+            prerelease: true,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            releases_oneline(&[&stable, &nightly], DateStyle::Absolute),
+            vec![
+                "v1.0.0              (release)  2024-03-01  assets:2",
+                "nightly-2024-03-02  (prerelease)  2024-03-02  assets:0",
+            ]
+        );
+
+        // Two columns per CJK character
+        let wide = Release {
+            tag_name: "版本-1".to_string(),
+            published_at: "2024-03-03T10:00:00Z".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            releases_oneline(&[&stable, &wide], DateStyle::Absolute),
+            vec![
+                "v1.0.0  (release)  2024-03-01  assets:2",
+                "版本-1  (release)  2024-03-03  assets:0",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_release_info_sections() {
+        let release = Release {
+            tag_name: "v1.0.0".to_string(),
+            published_at: "2024-03-01T10:00:00Z".to_string(),
+            body: Some("Fixed the frobnicator".to_string()),
+            assets: vec![Asset {
+                name: "app-linux.tar.gz".to_string(),
+                size: 2048,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let all = release_info(&release, InfoSection::All, None);
+        assert!(all.contains("Tag: v1.0.0"));
+        assert!(all.contains("Release Notes:"));
+        assert!(all.contains("Fixed the frobnicator"));
+
+        assert_eq!(
+            release_info(&release, InfoSection::Notes, None),
+            "Fixed the frobnicator"
+        );
+        assert_eq!(
+            release_info(&release, InfoSection::Notes, Some(5)),
+            truncate("Fixed the frobnicator", 5)
+        );
+
+        let assets = release_info(&release, InfoSection::Assets, None);
+        assert!(assets.contains("app-linux.tar.gz"));
+        assert!(assets.contains("2.0 KiB"));
+        assert!(!assets.contains("Tag:"));
+        assert!(!assets.contains("frobnicator"));
+
+        // A release without notes prints nothing for --notes-only
+        let bare = Release {
+            body: None,
+            ..release
+        };
+        assert_eq!(release_info(&bare, InfoSection::Notes, None), "");
+        assert!(!release_info(&bare, InfoSection::All, None).contains("Release Notes:"));
+    }
+
+    #[test]
+    fn test_to_json_compact() {
+        let values = vec!["v1.0.0", "v0.9.0"];
+        assert_eq!(to_json(&values, true).unwrap(), r#"["v1.0.0","v0.9.0"]"#);
+        assert!(to_json(&values, false).unwrap().contains("\n"));
+    }
+
+    #[test]
+    fn test_releases_info_json() {
+        let release = |tag: &str| Release {
+            tag_name: tag.to_string(),
+            ..Default::default()
+        };
+
+        let single = releases_info_json(&[release("v1.0.0")], false, true).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&single).unwrap();
+        assert_eq!(value["tag_name"], "v1.0.0");
+
+        let several =
+            releases_info_json(&[release("v1.0.0"), release("v0.9.0")], true, true).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&several).unwrap();
+        let tags: Vec<&str> = value
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["tag_name"].as_str().unwrap())
+            .collect();
+        assert_eq!(tags, vec!["v1.0.0", "v0.9.0"]);
+
+        // Requesting several tags gives an array even if only one was found
+        let one_found = releases_info_json(&[release("v1.0.0")], true, true).unwrap();
+        assert!(one_found.starts_with('['));
+    }
+
+    #[test]
+    fn test_download_error_lines() {
+        let errors: Vec<String> = (1..=10)
+            .map(|n| format!("Download failed for asset-{}.zip: HTTP 404", n))
+            .collect();
+
+        let all = download_error_lines(&errors, false);
+        assert_eq!(all.len(), 11);
+        assert_eq!(all[10], "  - Download failed for asset-10.zip: HTTP 404");
+
+        assert_eq!(
+            download_error_lines(&errors, true),
+            vec![
+                "Failed to download 10 asset(s):",
+                "  - Download failed for asset-1.zip: HTTP 404",
+                "  - Download failed for asset-2.zip: HTTP 404",
+                "  - Download failed for asset-3.zip: HTTP 404",
+                "  ... and 7 more",
+            ]
+        );
+
+        // Nothing is left out of short lists
+        assert_eq!(
+            download_error_lines(&errors[..2], true),
+            download_error_lines(&errors[..2], false)
+        );
+    }
+
+    #[test]
+    fn test_download_summary() {
+        let clean = DownloadSummary {
+            downloaded: 3,
+            total_bytes: 12_897_485,
+            elapsed: Duration::from_millis(4_210),
+            ..Default::default()
+        };
+        assert_eq!(
+            download_summary(&clean),
+            vec!["Summary: 3 downloaded, 0 failed, 12.3 MiB in 4.2s"]
+        );
+
+        let failed = DownloadSummary {
+            downloaded: 1,
+            total_bytes: 1024,
+            elapsed: Duration::from_secs(2),
+            failed: vec!["app-windows.zip".to_string()],
+            errors: vec!["Network error: connection reset".to_string()],
+        };
+        assert_eq!(
+            download_summary(&failed),
+            vec![
+                "Summary: 1 downloaded, 1 failed, 1.0 KiB in 2.0s",
+                "Failed assets:",
+                "  - app-windows.zip",
+                "Errors:",
+                "  - Network error: connection reset",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_run_report() {
+        let dir = std::path::PathBuf::from("bin");
+        let task = |name: &str, size: u64| DownloadTask {
+            name: name.to_string(),
+            url: format!("https://example.com/{}", name),
+            output_path: dir.join(name),
+            size,
+            digest: None,
+            modified: None,
+        };
+        let errors = vec![
+            GhrError::HttpStatus(reqwest::StatusCode::NOT_FOUND).for_asset("app-windows.zip"),
+            GhrError::Extract("bad archive".to_string()),
+        ];
+
+        let mut report = RunReport::new("download");
+        report.record(
+            "owner/repo",
+            &["v1.0.0"],
+            &[task("app-linux.tar.gz", 1000), task("app-macos.tar.gz", 24)],
+            &errors,
+            3,
+        );
+        report.elapsed_secs = 1.5;
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["mode"], "download");
+        assert_eq!(json["repos"], serde_json::json!(["owner/repo"]));
+        assert_eq!(json["tags"], serde_json::json!(["v1.0.0"]));
+        assert_eq!(json["succeeded"], 2);
+        assert_eq!(json["failed"], 1);
+        assert_eq!(json["skipped"], 3);
+        assert_eq!(json["total_bytes"], 1024);
+        assert_eq!(json["elapsed_secs"], 1.5);
+        assert_eq!(json["errors"].as_array().unwrap().len(), 2);
+
+        let assets = json["assets"].as_array().unwrap();
+        assert_eq!(assets.len(), 3);
+        assert_eq!(assets[0]["name"], "app-linux.tar.gz");
+        assert_eq!(assets[0]["status"], "downloaded");
+        assert_eq!(assets[0]["size"], 1000);
+        assert!(assets[0].get("error").is_none());
+        assert_eq!(assets[2]["name"], "app-windows.zip");
+        assert_eq!(assets[2]["status"], "failed");
+        assert_eq!(assets[2]["error"], "HTTP 404 Not Found");
+        assert_eq!(assets[2]["http_status"], 404);
+        assert!(assets[2].get("path").is_none());
+    }
+
+    #[test]
+    fn test_run_report_finish() {
+        let mut report = RunReport::new("download");
+        report.finish(&Ok(()));
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["status"], "success");
+        assert_eq!(json["exit_code"], 0);
+        assert_eq!(json["errors"], serde_json::json!([]));
+
+        // A run that aborts before downloading anything
+        report.finish(&Err(GhrError::NoReleases));
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["status"], "failed");
+        assert_eq!(json["exit_code"], GhrError::NoReleases.exit_code());
+        assert_eq!(json["failed"], 0);
+        assert_eq!(
+            json["errors"],
+            serde_json::json!([GhrError::NoReleases.to_string()])
+        );
+    }
+
+    fn repository(full_name: &str, description: &str) -> Repository {
+        Repository {
+            name: full_name.split('/').nth(1).unwrap().to_string(),
+            full_name: full_name.to_string(),
+            description: Some(description.to_string()),
+            stargazers_count: 42,
+            html_url: format!("https://github.com/{}", full_name),
+            owner: Owner {
+                login: full_name.split('/').next().unwrap().to_string(),
+            },
+            private: false,
+            topics: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_repositories_csv_quoting() {
+        let repos = [
+            repository("owner/fast", "Fast, simple \"tool\""),
+            repository("owner/multi", "line one\nline two"),
+        ];
+
+        let csv = repositories_csv(&repos).unwrap();
+        assert!(csv.starts_with("No,Stars,Private,Repository,Description\n"));
+        assert!(csv.contains("\"Fast, simple \"\"tool\"\"\""));
+
+        let mut reader = csv::Reader::from_reader(csv.as_bytes());
+        let records: Vec<csv::StringRecord> = reader.records().map(|r| r.unwrap()).collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(&records[0][3], "owner/fast");
+        assert_eq!(&records[0][4], "Fast, simple \"tool\"");
+        assert_eq!(&records[1][4], "line one\nline two");
+    }
+
+    #[test]
+    fn test_releases_csv() {
+        let release = Release {
+            tag_name: "v1.0.0".to_string(),
+            name: Some("First, stable".to_string()),
+            published_at: "2024-03-01T12:00:00Z".to_string(),
+            assets: vec![
+                Asset {
+                    size: 1024,
+                    ..Default::default()
+                },
+                Asset {
+                    size: 2048,
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        };
+
+        let csv = releases_csv(&[&release], ReleaseColumn::DEFAULT, false).unwrap();
+        let mut reader = csv::Reader::from_reader(csv.as_bytes());
+        assert_eq!(
+            reader.headers().unwrap(),
+            vec!["No", "Tag", "Name", "Published", "Assets", "Size"]
+        );
+        let record = reader.records().next().unwrap().unwrap();
+        assert_eq!(
+            record,
+            vec!["1", "v1.0.0", "First, stable", "2024-03-01", "2", "3072"]
+        );
+
+        let csv = releases_csv(&[&release], ReleaseColumn::DEFAULT, true).unwrap();
+        assert!(csv.ends_with(",3.0 KiB\n"));
+    }
+
+    #[test]
+    fn test_releases_selected_columns() {
+        let release = Release {
+            tag_name: "v2.0.0-rc1".to_string(),
+            published_at: "2024-03-01T12:00:00Z".to_string(),
+            prerelease: true,
+            assets: vec![Asset {
+                size: 1024,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let columns = [ReleaseColumn::Type, ReleaseColumn::Tag, ReleaseColumn::Size];
+
+        let csv = releases_csv(&[&release], &columns, false).unwrap();
+        assert_eq!(csv, "Type,Tag,Size\nprerelease,v2.0.0-rc1,1024\n");
+
+        let table = releases_table(&[&release], &columns, DateStyle::Absolute).to_string();
+        let header = table.lines().find(|line| line.contains("Tag")).unwrap();
+        let titles: Vec<&str> = header
+            .split_whitespace()
+            .filter(|w| w.chars().all(char::is_alphabetic))
+            .collect();
+        assert_eq!(titles, vec!["Type", "Tag", "Size"]);
+        assert!(table.contains("prerelease"));
+        assert!(table.contains("1.0 KiB"));
+        assert!(!table.contains("Published"));
+    }
+
+    #[test]
+    fn test_repository_info_output() {
+        let mut info: RepositoryInfo = serde_json::from_value(serde_json::json!({
+            "name": "repo",
+            "full_name": "owner/repo",
+            "description": "Does things, quickly",
+            "default_branch": "main",
+            "private": false,
+            "visibility": "public",
+            "stargazers_count": 1234,
+            "open_issues_count": 7,
+            "size": 2048,
+            "license": {"key": "mit", "spdx_id": "MIT", "name": "MIT License"},
+            "latest_release": "must not be read from the API"
+        }))
+        .unwrap();
+        assert!(info.latest_release.is_none());
+        info.latest_release = Some("v1.2.3".to_string());
+
+        let csv = repository_info_csv(&info, false).unwrap();
+        let mut reader = csv::Reader::from_reader(csv.as_bytes());
+        assert_eq!(
+            reader.headers().unwrap(),
+            vec![
+                "Repository",
+                "Description",
+                "Stars",
+                "Default Branch",
+                "Visibility",
+                "Size",
+                "License",
+                "Open Issues",
+                "Latest Release"
+            ]
+        );
+        assert_eq!(
+            reader.records().next().unwrap().unwrap(),
+            vec![
+                "owner/repo",
+                "Does things, quickly",
+                "1234",
+                "main",
+                "public",
+                "2097152",
+                "MIT",
+                "7",
+                "v1.2.3"
+            ]
+        );
+
+        let csv = repository_info_csv(&info, true).unwrap();
+        assert!(csv.contains(",2.0 MiB,"));
+
+        let table = repository_info_table(&info).to_string();
+        assert!(table.contains("2.0 MiB"));
+        assert!(table.contains("Latest Release"));
+        assert!(table.contains("v1.2.3"));
+
+        // Minimal responses, e.g. from older GitHub Enterprise versions
+        let info: RepositoryInfo = serde_json::from_value(serde_json::json!({
+            "name": "repo",
+            "full_name": "owner/repo",
+            "default_branch": "main",
+            "private": true
+        }))
+        .unwrap();
+        let fields = repository_info_fields(&info, true);
+        assert!(fields.contains(&("Visibility", "private".to_string())));
+        assert!(fields.contains(&("License", "None".to_string())));
+        assert!(fields.contains(&("Latest Release", "None".to_string())));
+    }
+
+    #[test]
+    fn test_format_published() {
+        assert_eq!(format_published("2024-03-01T12:00:00Z"), "2024-03-01");
+        assert_eq!(format_published("not a date"), "Unknown");
+    }
+
+    #[test]
+    fn test_format_relative() {
+        let now = DateTime::parse_from_rfc3339("2024-06-15T12:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let ago = |published_at| format_relative(published_at, now);
+
+        assert_eq!(ago("2024-06-15T11:59:30Z"), "just now");
+        assert_eq!(ago("2024-06-15T12:00:30Z"), "just now");
+        assert_eq!(ago("2024-06-15T11:59:00Z"), "1 minute ago");
+        assert_eq!(ago("2024-06-15T09:30:00Z"), "2 hours ago");
+        assert_eq!(ago("2024-06-14T12:00:00Z"), "1 day ago");
+        assert_eq!(ago("2024-06-12T08:00:00+02:00"), "3 days ago");
+        assert_eq!(ago("2024-04-10T12:00:00Z"), "2 months ago");
+        assert_eq!(ago("2022-01-01T00:00:00Z"), "2 years ago");
+        assert_eq!(ago("not a date"), "Unknown");
+    }
+
+    #[test]
+    fn test_repositories_table_private_marker() {
+        let mut private = repository("org/private-repo", "A private repository");
+        private.private = true;
+        private.stargazers_count = 123456;
+        let public = repository("user/test-repo", "A test repository");
+
+        let rendered = repositories_table(&[private, public]).to_string();
+        let lines: Vec<&str> = rendered.lines().collect();
+        let private_line = lines
+            .iter()
+            .find(|l| l.contains("org/private-repo"))
+            .unwrap();
+        let public_line = lines.iter().find(|l| l.contains("user/test-repo")).unwrap();
+        assert!(private_line.contains("123456"));
+        assert!(private_line.contains('*'));
+        assert!(public_line.contains("42"));
+        assert!(!public_line.contains('*'));
+    }
+
+    #[test]
+    fn test_repositories_table_topics() {
+        let plain = repositories_table(&[repository("user/tool", "")]).to_string();
+        assert!(!plain.contains("Topics"));
+
+        let mut tagged = repository("user/tool", "");
+        tagged.topics = vec!["cli".to_string(), "rust".to_string()];
+        let rendered = repositories_table(&[tagged, repository("user/other", "")]).to_string();
+        assert!(rendered.contains("Topics"));
+        assert!(rendered.contains("cli, rust"));
+    }
+
+    #[test]
+    fn test_hash_report_table() {
+        let mut report = HashReport::default();
+        report.add(
+            "bin/app-linux.tar.gz",
+            HashOutcome::Pass(DigestSource::Manifest),
+        );
+        report.add("bin/app-linux.deb", HashOutcome::Pass(DigestSource::Api));
+        report.add(
+            "bin/app-macos.tar.gz",
+            HashOutcome::Fail("sha256 00ff, expected 5891".to_string()),
+        );
+        report.add("bin/app-windows.zip", HashOutcome::MissingSum);
+
+        let table = hash_report_table(&report).to_string();
+        let row = |file: &str| {
+            table
+                .lines()
+                .find(|line| line.contains(file))
+                .unwrap()
+                .to_string()
+        };
+        assert!(row("app-linux.tar.gz").contains("PASS"));
+        assert!(row("app-linux.tar.gz").contains("checksums file"));
+        assert!(row("app-linux.deb").contains("PASS"));
+        assert!(row("app-linux.deb").contains("API digest"));
+        assert!(row("app-macos.tar.gz").contains("FAIL"));
+        assert!(row("app-macos.tar.gz").contains("sha256 00ff, expected 5891"));
+        assert!(row("app-windows.zip").contains("MISSING-SUM"));
+        assert_eq!(report.failed(), 1);
+    }
+
+    #[test]
+    fn test_table_aligns_wide_characters() {
+        let repositories = vec![
+            repository("user/plain-repo", ""),
+            repository("用户/中文仓库名称", ""),
+            repository("ユーザー/リポジトリ", ""),
+        ];
+        let rendered = repositories_table(&repositories).to_string();
+        let lines: Vec<&str> = rendered.lines().collect();
+        assert_eq!(lines.len(), 7);
+
+        // Every row, border included, spans the same number of terminal columns
+        let width = lines[0].width();
+        for line in &lines {
+            assert_eq!(line.width(), width, "misaligned row: {}", line);
+        }
+
+        // The column separators line up in display columns
+        let repo_column = |line: &str| {
+            let byte = line
+                .find("user/")
+                .or_else(|| line.find("用户"))
+                .or_else(|| line.find("ユーザー"));
+            line[..byte.unwrap()].width()
+        };
+        let columns: Vec<usize> = lines
+            .iter()
+            .filter(|l| l.contains("user/") || l.contains("用户") || l.contains("ユーザー"))
+            .map(|l| repo_column(l))
+            .collect();
+        assert_eq!(columns.len(), 3);
+        assert!(columns.iter().all(|&c| c == columns[0]));
+    }
+}
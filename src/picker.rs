@@ -0,0 +1,100 @@
+use crate::errors::{GhrError, Result};
+use crate::models::Asset;
+use crate::util::humanize_bytes;
+use dialoguer::theme::ColorfulTheme;
+use dialoguer::{Confirm, FuzzySelect};
+use std::io::IsTerminal;
+
+/// Label of the entry that ends the selection loop
+const DONE_LABEL: &str = "[Done]";
+
+/// Format an asset as a picker candidate, e.g. "app-linux.tar.gz (1.5 MiB)"
+pub fn format_candidate(asset: &Asset) -> String {
+    format!("{} ({})", asset.name, humanize_bytes(asset.size))
+}
+
+/// Interactively pick assets with a fuzzy finder
+///
+/// Each prompt selects one asset; the prompt repeats until "[Done]" is chosen
+/// or the prompt is cancelled with Esc. Returns the indices of the picked
+/// assets in the order they were picked.
+pub fn pick_assets(assets: &[&Asset]) -> Result<Vec<usize>> {
+    if !std::io::stdin().is_terminal() || !std::io::stderr().is_terminal() {
+        return Err(GhrError::Generic(
+            "--pick requires an interactive terminal. Use --filter to select assets non-interactively"
+                .to_string(),
+        ));
+    }
+
+    let mut picked: Vec<usize> = Vec::new();
+
+    loop {
+        // Remaining candidates, keeping their index into `assets`
+        let remaining: Vec<usize> = (0..assets.len()).filter(|i| !picked.contains(i)).collect();
+        if remaining.is_empty() {
+            break;
+        }
+
+        let mut items = vec![DONE_LABEL.to_string()];
+        items.extend(remaining.iter().map(|&i| format_candidate(assets[i])));
+
+        let selection = FuzzySelect::with_theme(&ColorfulTheme::default())
+            .with_prompt(format!(
+                "Select assets to download ({} selected, Esc or {} to finish)",
+                picked.len(),
+                DONE_LABEL
+            ))
+            .items(&items)
+            .default(0)
+            .interact_opt()
+            .map_err(|e| GhrError::Generic(format!("Interactive selection failed: {}", e)))?;
+
+        match selection {
+            Some(0) | None => break,
+            Some(n) => picked.push(remaining[n - 1]),
+        }
+    }
+
+    Ok(picked)
+}
+
+/// Ask a yes/no question on the terminal, defaulting to no
+///
+/// Fails without a terminal to ask on; `hint` tells how to proceed in that case.
+pub fn confirm(prompt: &str, hint: &str) -> Result<bool> {
+    if !std::io::stdin().is_terminal() || !std::io::stderr().is_terminal() {
+        return Err(GhrError::Generic(format!(
+            "Confirmation required but there is no terminal to ask on. {}",
+            hint
+        )));
+    }
+
+    Confirm::with_theme(&ColorfulTheme::default())
+        .with_prompt(prompt)
+        .default(false)
+        .interact()
+        .map_err(|e| GhrError::Generic(format!("Confirmation failed: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_candidate() {
+        let asset = Asset {
+            id: 1,
+            name: "app-linux-amd64.tar.gz".to_string(),
+            browser_download_url: "https://example.com/app-linux-amd64.tar.gz".to_string(),
+            size: 1_572_864,
+            download_count: 7,
+            content_type: None,
+            state: "uploaded".to_string(),
+            digest: None,
+            created_at: None,
+            updated_at: None,
+        };
+
+        assert_eq!(format_candidate(&asset), "app-linux-amd64.tar.gz (1.5 MiB)");
+    }
+}
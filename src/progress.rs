@@ -0,0 +1,335 @@
+use crate::download::{DownloadTask, ProgressCallback, ProgressEvent};
+use crate::errors::{GhrError, Result};
+use indicatif::{HumanBytes, MultiProgress, ProgressBar, ProgressStyle};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Weight given to the newest rate sample
+const SMOOTHING_ALPHA: f64 = 0.3;
+
+/// Minimum time between two rate samples
+const MIN_SAMPLE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Exponentially smoothed transfer rate estimator
+///
+/// Samples are cumulative byte counts with their timestamps. Samples closer
+/// together than the minimum interval are folded into the next one, so the
+/// rate reflects throughput over time rather than the size of single chunks.
+#[derive(Debug)]
+pub struct RateEstimator {
+    alpha: f64,
+    min_interval: Duration,
+    last: Option<(u64, Instant)>,
+    rate: Option<f64>,
+}
+
+impl RateEstimator {
+    /// Create an estimator with the given smoothing factor and sampling interval
+    pub fn new(alpha: f64, min_interval: Duration) -> Self {
+        Self {
+            alpha,
+            min_interval,
+            last: None,
+            rate: None,
+        }
+    }
+
+    /// Record the cumulative number of bytes transferred at time `at`
+    pub fn update(&mut self, bytes: u64, at: Instant) {
+        let Some((last_bytes, last_at)) = self.last else {
+            self.last = Some((bytes, at));
+            return;
+        };
+
+        let elapsed = at.saturating_duration_since(last_at);
+        if elapsed < self.min_interval {
+            return;
+        }
+
+        let sample = bytes.saturating_sub(last_bytes) as f64 / elapsed.as_secs_f64();
+        self.rate = Some(match self.rate {
+            Some(rate) => self.alpha * sample + (1.0 - self.alpha) * rate,
+            None => sample,
+        });
+        self.last = Some((bytes, at));
+    }
+
+    /// Smoothed rate in bytes per second, once at least two samples were taken
+    pub fn rate(&self) -> Option<f64> {
+        self.rate
+    }
+
+    /// Estimated time to transfer `remaining` bytes at the smoothed rate
+    pub fn eta(&self, remaining: u64) -> Option<Duration> {
+        match self.rate {
+            Some(rate) if rate > 0.0 => Some(Duration::from_secs_f64(remaining as f64 / rate)),
+            _ => None,
+        }
+    }
+}
+
+impl Default for RateEstimator {
+    fn default() -> Self {
+        Self::new(SMOOTHING_ALPHA, MIN_SAMPLE_INTERVAL)
+    }
+}
+
+/// Format a duration as HH:MM:SS
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    format!(
+        "{:02}:{:02}:{:02}",
+        secs / 3600,
+        (secs % 3600) / 60,
+        secs % 60
+    )
+}
+
+/// Create a progress bar for a single asset
+pub fn asset_bar(multi_progress: &MultiProgress, name: &str, size: u64) -> ProgressBar {
+    let pb = multi_progress.add(ProgressBar::new(size));
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template("{msg}\n{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({eta})")
+            .unwrap()
+            .progress_chars("#>-"),
+    );
+    pb.set_message(format!("Downloading: {}", name));
+    pb
+}
+
+/// Aggregate progress over all assets of a download run
+///
+/// Throughput and ETA are computed from the cumulative byte count of all
+/// assets, so they describe the whole job rather than individual chunks.
+pub struct TotalProgress {
+    bar: ProgressBar,
+    state: Mutex<(u64, RateEstimator)>,
+}
+
+impl TotalProgress {
+    /// Create the aggregate bar for `count` assets totalling `total_size` bytes
+    pub fn new(multi_progress: &MultiProgress, total_size: u64, count: usize) -> Self {
+        let bar = multi_progress.add(ProgressBar::new(total_size));
+        bar.set_style(
+            ProgressStyle::default_bar()
+                .template("{msg}\n{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {bytes}/{total_bytes} ({prefix})")
+                .unwrap()
+                .progress_chars("#>-"),
+        );
+        bar.set_message(format!("Downloading {} asset(s)", count));
+        bar.set_prefix("estimating...");
+
+        Self {
+            bar,
+            state: Mutex::new((0, RateEstimator::default())),
+        }
+    }
+
+    /// Account for `bytes` more bytes downloaded by any asset
+    pub fn advance(&self, bytes: u64) {
+        let mut state = self.state.lock().unwrap();
+        let (downloaded, estimator) = &mut *state;
+        *downloaded += bytes;
+        estimator.update(*downloaded, Instant::now());
+        self.bar.set_position(*downloaded);
+
+        if let (Some(rate), Some(eta)) = (
+            estimator.rate(),
+            estimator.eta(self.bar.length().unwrap_or(0).saturating_sub(*downloaded)),
+        ) {
+            self.bar.set_prefix(format!(
+                "{}/s, ETA {}",
+                HumanBytes(rate as u64),
+                format_duration(eta)
+            ));
+        }
+    }
+
+    /// Finish the aggregate bar with a final message
+    pub fn finish(&self, message: String) {
+        self.bar.finish_with_message(message);
+    }
+}
+
+/// Progress bars of a download run, drawn from the download progress callback
+///
+/// Shows one bar per asset, finished as soon as its download ends, or a
+/// single aggregate bar with `--total-progress`.
+pub struct DownloadBars {
+    multi_progress: MultiProgress,
+    total: Option<TotalProgress>,
+    /// Bar (per-asset mode only) and last reported byte count of each asset
+    assets: Mutex<HashMap<String, (Option<ProgressBar>, u64)>>,
+}
+
+impl DownloadBars {
+    /// Bars for downloading `tasks`, aggregated into one when `total` is set
+    pub fn new(multi_progress: MultiProgress, tasks: &[DownloadTask], total: bool) -> Arc<Self> {
+        let total = total.then(|| {
+            let total_size = tasks.iter().map(|t| t.size).sum();
+            TotalProgress::new(&multi_progress, total_size, tasks.len())
+        });
+        Arc::new(Self {
+            multi_progress,
+            total,
+            assets: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Callback for [`crate::download::DownloadOptions::progress`] that updates these bars
+    pub fn callback(self: &Arc<Self>) -> ProgressCallback {
+        let bars = Arc::clone(self);
+        Arc::new(move |name, event| match event {
+            ProgressEvent::Bytes { downloaded, size } => bars.update(name, downloaded, size),
+            ProgressEvent::Finished => bars.finish_asset(name, format!("Complete: {}", name)),
+            ProgressEvent::Failed(error) => {
+                let reason = match error {
+                    GhrError::DownloadFailed { source, .. } => source.as_ref(),
+                    error => error,
+                };
+                bars.finish_asset(name, format!("Failed: {} ({})", name, reason));
+            }
+        })
+    }
+
+    /// Finish the bar of one asset (per-asset mode only)
+    fn finish_asset(&self, name: &str, message: String) {
+        let assets = self.assets.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((Some(bar), _)) = assets.get(name) {
+            bar.finish_with_message(message);
+        }
+    }
+
+    fn update(&self, name: &str, downloaded: u64, size: u64) {
+        let mut assets = self.assets.lock().unwrap_or_else(|e| e.into_inner());
+        let (bar, last) = assets.entry(name.to_string()).or_insert_with(|| {
+            let bar = match self.total {
+                Some(_) => None,
+                None => Some(asset_bar(&self.multi_progress, name, size)),
+            };
+            (bar, 0)
+        });
+        if let Some(total) = &self.total {
+            // A retry starts from zero again and adds nothing until it catches up
+            total.advance(downloaded.saturating_sub(*last));
+        }
+        *last = downloaded;
+        if let Some(bar) = bar {
+            bar.set_position(downloaded);
+        }
+    }
+
+    /// Finish the aggregate bar, if any, with the outcome of the run; the bars
+    /// of single assets finish as their downloads end
+    pub fn finish(&self, results: &[Result<DownloadTask>]) {
+        if let Some(total) = &self.total {
+            let failed = results.iter().filter(|r| r.is_err()).count();
+            total.finish(format!(
+                "Complete: {} asset(s), {} failed",
+                results.len() - failed,
+                failed
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_estimator_smoothing() {
+        let start = Instant::now();
+        let mut estimator = RateEstimator::new(0.5, Duration::from_millis(500));
+
+        estimator.update(0, start);
+        assert_eq!(estimator.rate(), None);
+
+        // Samples closer than the minimum interval are ignored
+        estimator.update(5_000, start + Duration::from_millis(100));
+        assert_eq!(estimator.rate(), None);
+
+        // 1000 B/s over the first second
+        estimator.update(1_000, start + Duration::from_secs(1));
+        assert_eq!(estimator.rate(), Some(1_000.0));
+
+        // 3000 B/s over the next second, blended 50/50 with the previous rate
+        estimator.update(4_000, start + Duration::from_secs(2));
+        assert_eq!(estimator.rate(), Some(2_000.0));
+
+        assert_eq!(estimator.eta(10_000), Some(Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn test_rate_estimator_no_eta_without_rate() {
+        let estimator = RateEstimator::default();
+        assert_eq!(estimator.eta(1_000), None);
+    }
+
+    #[test]
+    fn test_download_bars_total() {
+        let task = |name: &str, size: u64| DownloadTask {
+            name: name.to_string(),
+            url: String::new(),
+            output_path: name.into(),
+            size,
+            digest: None,
+            modified: None,
+        };
+        let tasks = [task("app.tar.gz", 100), task("app.zip", 50)];
+        let hidden = MultiProgress::with_draw_target(indicatif::ProgressDrawTarget::hidden());
+        let bars = DownloadBars::new(hidden, &tasks, true);
+        let callback = bars.callback();
+
+        let bytes = |downloaded, size| ProgressEvent::Bytes { downloaded, size };
+
+        // Cumulative counts of interleaved downloads add up
+        callback("app.tar.gz", bytes(0, 100));
+        callback("app.zip", bytes(0, 50));
+        callback("app.tar.gz", bytes(60, 100));
+        callback("app.zip", bytes(20, 50));
+        callback("app.tar.gz", bytes(100, 100));
+
+        let total = bars.total.as_ref().unwrap();
+        assert_eq!(total.bar.length(), Some(150));
+        assert_eq!(total.bar.position(), 120);
+    }
+
+    #[test]
+    fn test_download_bars_finish_per_asset() {
+        let hidden = MultiProgress::with_draw_target(indicatif::ProgressDrawTarget::hidden());
+        let bars = DownloadBars::new(hidden, &[], false);
+        let callback = bars.callback();
+        let bytes = |downloaded| ProgressEvent::Bytes {
+            downloaded,
+            size: 100,
+        };
+
+        callback("app.tar.gz", bytes(0));
+        callback("app.zip", bytes(0));
+        callback("app.tar.gz", bytes(100));
+        callback("app.tar.gz", ProgressEvent::Finished);
+        let error = GhrError::HttpStatus(reqwest::StatusCode::NOT_FOUND).for_asset("app.deb");
+        callback("app.deb", bytes(0));
+        callback("app.deb", ProgressEvent::Failed(&error));
+
+        // Each bar finishes when its own download ends, not with the batch
+        let assets = bars.assets.lock().unwrap();
+        let bar = |name: &str| assets[name].0.clone().unwrap();
+        assert!(bar("app.tar.gz").is_finished());
+        assert_eq!(bar("app.tar.gz").message(), "Complete: app.tar.gz");
+        assert!(!bar("app.zip").is_finished());
+        assert_eq!(
+            bar("app.deb").message(),
+            "Failed: app.deb (HTTP 404 Not Found)"
+        );
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_secs(0)), "00:00:00");
+        assert_eq!(format_duration(Duration::from_secs(3_725)), "01:02:05");
+    }
+}
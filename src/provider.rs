@@ -0,0 +1,166 @@
+use crate::cache::Cache;
+use crate::cli::Provider;
+use crate::constants;
+use crate::errors::Result;
+use crate::http::ApiClient;
+use crate::models::{Asset, Release};
+use crate::{github, gitlab};
+use futures::future::BoxFuture;
+
+/// Service hosting the releases of a repository
+pub trait ReleaseProvider: Send + Sync {
+    /// Fetch the releases of `repo`, or only the release tagged `tag`
+    fn releases<'a>(
+        &'a self,
+        repo: &'a str,
+        tag: Option<&'a str>,
+        cache: Option<&'a Cache>,
+    ) -> BoxFuture<'a, Result<Vec<Release>>>;
+
+    /// Fetch up to `limit` releases of `repo`, newest first, across all pages
+    fn all_releases<'a>(
+        &'a self,
+        repo: &'a str,
+        limit: usize,
+    ) -> BoxFuture<'a, Result<Vec<Release>>>;
+
+    /// URL to download an asset of `repo` from
+    fn asset_url(&self, repo: &str, asset: &Asset) -> String;
+
+    /// URL of the gzip-compressed source tarball of `repo` at `git_ref`
+    fn source_archive_url(&self, repo: &str, git_ref: &str) -> String;
+}
+
+/// Create the provider selected with `--provider`
+pub fn new<'a>(
+    provider: Provider,
+    client: &'a ApiClient,
+    base_url: &'a str,
+) -> Box<dyn ReleaseProvider + 'a> {
+    match provider {
+        Provider::Github => Box::new(GitHub { client, base_url }),
+        Provider::Gitlab => Box::new(GitLab { client, base_url }),
+    }
+}
+
+/// GitHub or GitHub Enterprise
+pub struct GitHub<'a> {
+    client: &'a ApiClient,
+    base_url: &'a str,
+}
+
+impl ReleaseProvider for GitHub<'_> {
+    fn releases<'a>(
+        &'a self,
+        repo: &'a str,
+        tag: Option<&'a str>,
+        cache: Option<&'a Cache>,
+    ) -> BoxFuture<'a, Result<Vec<Release>>> {
+        Box::pin(github::get_release_info_with_cache(
+            self.client,
+            self.base_url,
+            repo,
+            tag,
+            cache,
+        ))
+    }
+
+    fn all_releases<'a>(
+        &'a self,
+        repo: &'a str,
+        limit: usize,
+    ) -> BoxFuture<'a, Result<Vec<Release>>> {
+        Box::pin(github::get_all_releases(
+            self.client,
+            self.base_url,
+            repo,
+            constants::RELEASES_PER_PAGE,
+            limit,
+        ))
+    }
+
+    /// The API URL of the asset, which unlike the browser URL works for private repositories
+    fn asset_url(&self, repo: &str, asset: &Asset) -> String {
+        format!(
+            "{}/repos/{}/releases/assets/{}",
+            self.base_url, repo, asset.id
+        )
+    }
+
+    fn source_archive_url(&self, repo: &str, git_ref: &str) -> String {
+        let (owner, name) = repo.split_once('/').unwrap_or((repo, ""));
+        constants::endpoints::tarball_with_base(self.base_url, owner, name, git_ref)
+    }
+}
+
+/// GitLab.com or a self-managed GitLab instance
+pub struct GitLab<'a> {
+    client: &'a ApiClient,
+    base_url: &'a str,
+}
+
+impl ReleaseProvider for GitLab<'_> {
+    fn releases<'a>(
+        &'a self,
+        repo: &'a str,
+        tag: Option<&'a str>,
+        cache: Option<&'a Cache>,
+    ) -> BoxFuture<'a, Result<Vec<Release>>> {
+        Box::pin(gitlab::get_release_info_with_cache(
+            self.client,
+            self.base_url,
+            repo,
+            tag,
+            cache,
+        ))
+    }
+
+    fn all_releases<'a>(
+        &'a self,
+        repo: &'a str,
+        limit: usize,
+    ) -> BoxFuture<'a, Result<Vec<Release>>> {
+        Box::pin(gitlab::get_all_releases(
+            self.client,
+            self.base_url,
+            repo,
+            constants::RELEASES_PER_PAGE,
+            limit,
+        ))
+    }
+
+    fn asset_url(&self, _repo: &str, asset: &Asset) -> String {
+        asset.browser_download_url.clone()
+    }
+
+    fn source_archive_url(&self, repo: &str, git_ref: &str) -> String {
+        constants::gitlab_endpoints::archive_with_base(self.base_url, repo, git_ref)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::ApiOptions;
+
+    #[test]
+    fn test_source_archive_url() {
+        let api = ApiClient::new(reqwest::Client::new(), ApiOptions::default());
+
+        let github = new(Provider::Github, &api, "https://api.github.com");
+        assert_eq!(
+            github.source_archive_url("owner/repo", "v1.0.0"),
+            "https://api.github.com/repos/owner/repo/tarball/v1.0.0"
+        );
+        assert_eq!(
+            github.source_archive_url("owner/repo", "feature/x"),
+            "https://api.github.com/repos/owner/repo/tarball/feature%2Fx"
+        );
+
+        let gitlab = new(Provider::Gitlab, &api, "https://gitlab.com/api/v4");
+        assert_eq!(
+            gitlab.source_archive_url("group/project", "v1.0.0"),
+            "https://gitlab.com/api/v4/projects/group%2Fproject/repository/archive.tar.gz?sha=v1.0.0"
+        );
+    }
+}
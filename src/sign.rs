@@ -0,0 +1,166 @@
+use crate::checksum;
+use crate::cli::HashAlgorithm;
+use crate::constants;
+use crate::errors::{GhrError, Result};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Untrusted comment of the signatures written by ghr
+const UNTRUSTED_COMMENT: &str = "signature from ghr secret key";
+
+/// Secret key of a minisign key pair, loaded with [`load_secret_key`]
+pub struct SigningKey(minisign::SecretKey);
+
+/// Read a minisign secret key file
+///
+/// The password of an encrypted key is read from the environment variable
+/// named by [`constants::SIGNING_KEY_PASSWORD_ENV`], or else prompted for.
+pub fn load_secret_key(path: &Path) -> Result<SigningKey> {
+    let content = std::fs::read_to_string(path)?;
+    let password = std::env::var(constants::SIGNING_KEY_PASSWORD_ENV).ok();
+    parse_secret_key(&content, password)
+        .map_err(|e| GhrError::Signing(format!("'{}': {}", path.display(), e)))
+}
+
+/// Decode a secret key file's content; without a password an encrypted key
+/// prompts for one
+fn parse_secret_key(
+    content: &str,
+    password: Option<String>,
+) -> std::result::Result<SigningKey, minisign::PError> {
+    let key = minisign::SecretKeyBox::from_string(content)?.into_secret_key(password)?;
+    Ok(SigningKey(key))
+}
+
+/// Sign `data` the way `minisign -S` does, returning the contents of the `.minisig` file
+pub fn sign(key: &SigningKey, data: &[u8], trusted_comment: &str) -> Result<String> {
+    let signature = minisign::sign(
+        None,
+        &key.0,
+        data,
+        Some(trusted_comment),
+        Some(UNTRUSTED_COMMENT),
+    )
+    .map_err(|e| GhrError::Signing(e.to_string()))?;
+    Ok(signature.into_string())
+}
+
+/// Write a SHA-256 manifest of `files` (`name`, `path`) into `dir`, with its
+/// minisign signature next to it (--sign-output)
+///
+/// Returns the path of the manifest; the signature is the same path with a
+/// `.minisig` suffix.
+pub fn write_signed_manifest(
+    dir: &Path,
+    files: &[(&str, &Path)],
+    key: &SigningKey,
+) -> Result<PathBuf> {
+    let mut manifest = checksum::checksum_lines(files, HashAlgorithm::Sha256)?.join("\n");
+    manifest.push('\n');
+
+    let path = dir.join(constants::SIGNED_MANIFEST_NAME);
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let trusted_comment = format!(
+        "timestamp:{}\tfile:{}\thashed",
+        timestamp,
+        constants::SIGNED_MANIFEST_NAME
+    );
+    let signature = sign(key, manifest.as_bytes(), &trusted_comment)?;
+
+    std::fs::write(&path, &manifest)?;
+    let mut signature_path = path.clone().into_os_string();
+    signature_path.push(".minisig");
+    std::fs::write(signature_path, signature)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Minisign key pair files of a new key without password: (secret key, public key)
+    fn key_files() -> (String, String) {
+        let pair = minisign::KeyPair::generate_unencrypted_keypair().unwrap();
+        let secret = pair.sk.to_box(None).unwrap().into_string();
+        (secret, pair.pk.to_base64())
+    }
+
+    #[test]
+    fn test_signed_manifest_verifies() {
+        let dir = tempfile::tempdir().unwrap();
+        let (secret, public) = key_files();
+        let key_path = dir.path().join("ghr.key");
+        std::fs::write(&key_path, secret).unwrap();
+        let app = dir.path().join("app.tar.gz");
+        std::fs::write(&app, "hello\n").unwrap();
+
+        let key = load_secret_key(&key_path).unwrap();
+        let manifest_path =
+            write_signed_manifest(dir.path(), &[("app.tar.gz", app.as_path())], &key).unwrap();
+        let manifest = std::fs::read(&manifest_path).unwrap();
+        assert_eq!(
+            manifest,
+            b"5891b5b522d5df086d0ff0b110fbd9d21bb4fc7163af34d08286a2e846f6be03  app.tar.gz\n"
+        );
+
+        let signature = std::fs::read_to_string(dir.path().join("SHA256SUMS.minisig")).unwrap();
+        let signature = minisign_verify::Signature::decode(&signature).unwrap();
+        assert!(signature
+            .trusted_comment()
+            .ends_with("file:SHA256SUMS\thashed"));
+        let public = minisign_verify::PublicKey::from_base64(&public).unwrap();
+        public.verify(&manifest, &signature, false).unwrap();
+
+        // A changed download set no longer matches the signature
+        let mut tampered = manifest.clone();
+        tampered[0] = b'0';
+        assert!(public.verify(&tampered, &signature, false).is_err());
+
+        // Nor does another key
+        let (_, other) = key_files();
+        let other = minisign_verify::PublicKey::from_base64(&other).unwrap();
+        assert!(other.verify(&manifest, &signature, false).is_err());
+    }
+
+    /// Password-protected key in minisign's format, created with libsodium
+    /// (which minisign is built on) independently of this module
+    const FIXTURE_KEY: &str = include_str!("../tests/fixtures/minisign.key");
+    const FIXTURE_PASSWORD: &str = "ghr-test";
+
+    #[test]
+    fn test_encrypted_key_matches_reference_signature() {
+        let key = parse_secret_key(FIXTURE_KEY, Some(FIXTURE_PASSWORD.to_string())).unwrap();
+
+        // libsodium's signature of the same manifest and trusted comment
+        let manifest = include_bytes!("../tests/fixtures/SHA256SUMS");
+        let reference = include_str!("../tests/fixtures/SHA256SUMS.minisig");
+        let signature = sign(
+            &key,
+            manifest,
+            "timestamp:1700000000\tfile:SHA256SUMS\thashed",
+        )
+        .unwrap();
+        // Ed25519 is deterministic, so all but the untrusted comment match
+        assert_eq!(
+            signature.lines().skip(1).collect::<Vec<_>>(),
+            reference.lines().skip(1).collect::<Vec<_>>()
+        );
+
+        let public =
+            minisign_verify::PublicKey::decode(include_str!("../tests/fixtures/minisign.pub"))
+                .unwrap();
+        let signature = minisign_verify::Signature::decode(&signature).unwrap();
+        public.verify(manifest, &signature, false).unwrap();
+
+        assert!(parse_secret_key(FIXTURE_KEY, Some("wrong".to_string())).is_err());
+    }
+
+    #[test]
+    fn test_parse_secret_key_errors() {
+        assert!(parse_secret_key("untrusted comment: x\nbm90IGEga2V5\n", None).is_err());
+        assert!(parse_secret_key("", None).is_err());
+    }
+}
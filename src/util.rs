@@ -0,0 +1,125 @@
+/// Format a byte count with binary units, e.g. "512 B", "1.5 KiB", "3.2 MiB"
+pub fn humanize_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    // Switch units before rounding would print "1024.0"
+    while value >= 1023.95 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+/// Shorten `s` to at most `max_len` characters, ending in "..." when cut
+///
+/// Counts characters rather than bytes, so multibyte text is never split.
+/// Below 3 characters there is no room for the "...", so `s` is just cut.
+pub fn truncate(s: &str, max_len: usize) -> String {
+    if s.chars().count() <= max_len {
+        s.to_string()
+    } else if max_len < 3 {
+        s.chars().take(max_len).collect()
+    } else {
+        let truncated: String = s.chars().take(max_len - 3).collect();
+        format!("{}...", truncated)
+    }
+}
+
+/// Candidates resembling `target`, most similar first
+///
+/// Names are compared case-insensitively; at most `limit` candidates with a
+/// similarity of at least `min_similarity` (0.0 to 1.0) are returned.
+pub fn closest_matches<'a>(
+    target: &str,
+    candidates: impl IntoIterator<Item = &'a str>,
+    min_similarity: f64,
+    limit: usize,
+) -> Vec<&'a str> {
+    let target = target.to_lowercase();
+    let mut scored: Vec<(f64, &str)> = candidates
+        .into_iter()
+        .map(|name| (strsim::jaro_winkler(&target, &name.to_lowercase()), name))
+        .filter(|(score, _)| *score >= min_similarity)
+        .collect();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+    scored
+        .into_iter()
+        .take(limit)
+        .map(|(_, name)| name)
+        .collect()
+}
+
+/// Open `url` in the default browser
+pub fn open_in_browser(url: &str) -> std::io::Result<()> {
+    let mut command = if cfg!(target_os = "macos") {
+        std::process::Command::new("open")
+    } else if cfg!(windows) {
+        let mut command = std::process::Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else {
+        std::process::Command::new("xdg-open")
+    };
+    command.arg(url).spawn().map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_humanize_bytes() {
+        assert_eq!(humanize_bytes(0), "0 B");
+        assert_eq!(humanize_bytes(1023), "1023 B");
+        assert_eq!(humanize_bytes(1024), "1.0 KiB");
+        assert_eq!(humanize_bytes(1536), "1.5 KiB");
+        assert_eq!(humanize_bytes(1_048_575), "1.0 MiB");
+        assert_eq!(humanize_bytes(3 * 1_048_576 + 209_716), "3.2 MiB");
+        assert_eq!(humanize_bytes(1_181_116_007), "1.1 GiB");
+        assert_eq!(humanize_bytes(u64::MAX), "16384.0 PiB");
+    }
+
+    #[test]
+    fn test_closest_matches() {
+        let names = ["gh_release", "dotfiles", "GH-Releases", "release-notes"];
+        assert_eq!(
+            closest_matches("gh_relase", names, 0.8, 3),
+            vec!["gh_release", "GH-Releases"]
+        );
+        assert_eq!(
+            closest_matches("gh_relase", names, 0.8, 1),
+            vec!["gh_release"]
+        );
+        assert!(closest_matches("kernel", names, 0.8, 3).is_empty());
+    }
+
+    #[test]
+    fn test_truncate() {
+        assert_eq!(truncate("short", 10), "short");
+        assert_eq!(truncate("a-very-long-asset-name", 10), "a-very-...");
+    }
+
+    #[test]
+    fn test_truncate_shorter_than_ellipsis() {
+        assert_eq!(truncate("release", 3), "...");
+        assert_eq!(truncate("release", 2), "re");
+        assert_eq!(truncate("🚀🚀🚀", 1), "🚀");
+        assert_eq!(truncate("release", 0), "");
+        assert_eq!(truncate("ab", 2), "ab");
+    }
+
+    #[test]
+    fn test_truncate_multibyte() {
+        let notes = "修正: 日本語のリリースノート 🚀🚀";
+        assert_eq!(truncate(notes, 10), "修正: 日本語...");
+        assert_eq!(truncate(notes, 10).chars().count(), 10);
+        assert_eq!(truncate("🚀🚀🚀🚀", 4), "🚀🚀🚀🚀");
+        assert_eq!(truncate("🚀🚀🚀🚀🚀", 4), "🚀...");
+    }
+}